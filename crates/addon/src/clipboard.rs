@@ -0,0 +1,78 @@
+use log::warn;
+use windows::Win32::{
+    Foundation::{GlobalFree, HANDLE, HGLOBAL, HWND},
+    System::{
+        DataExchange::{
+            CloseClipboard, EmptyClipboard, GetClipboardData, OpenClipboard, SetClipboardData,
+        },
+        Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE},
+        Ole::CF_UNICODETEXT,
+    },
+};
+
+pub fn get_clipboard_text() -> Option<String> {
+    unsafe {
+        OpenClipboard(HWND::default())
+            .inspect_err(|err| warn!("could not open clipboard: {err}"))
+            .ok()?;
+
+        let text = GetClipboardData(CF_UNICODETEXT.0 as u32)
+            .ok()
+            .and_then(|handle| {
+                let memory = HGLOBAL(handle.0);
+                let data = GlobalLock(memory) as *const u16;
+
+                if data.is_null() {
+                    return None;
+                }
+
+                let len = (0..).take_while(|i| *data.add(*i) != 0).count();
+                let text = String::from_utf16_lossy(std::slice::from_raw_parts(data, len));
+
+                // Fails if the memory is not locked anymore afterwards, which is the expected case.
+                let _ = GlobalUnlock(memory);
+
+                Some(text)
+            });
+
+        let _ = CloseClipboard();
+
+        text
+    }
+}
+
+pub fn set_clipboard_text(text: &str) {
+    let text = text.encode_utf16().chain([0]).collect::<Vec<_>>();
+
+    unsafe {
+        if let Err(err) = OpenClipboard(HWND::default()) {
+            warn!("could not open clipboard: {err}");
+            return;
+        }
+
+        let result = GlobalAlloc(GMEM_MOVEABLE, text.len() * size_of::<u16>()).and_then(|memory| {
+            let data = GlobalLock(memory) as *mut u16;
+
+            if !data.is_null() {
+                data.copy_from_nonoverlapping(text.as_ptr(), text.len());
+                let _ = GlobalUnlock(memory);
+            }
+
+            let result = EmptyClipboard()
+                .and_then(|_| SetClipboardData(CF_UNICODETEXT.0 as u32, HANDLE(memory.0)));
+
+            // The clipboard owns the memory only if it was set successfully.
+            if result.is_err() {
+                let _ = GlobalFree(memory);
+            }
+
+            result
+        });
+
+        if let Err(err) = result {
+            warn!("could not write clipboard: {err}");
+        }
+
+        let _ = CloseClipboard();
+    }
+}
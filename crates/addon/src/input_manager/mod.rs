@@ -0,0 +1,174 @@
+mod translation;
+
+use std::collections::HashMap;
+
+use egui::{Context, Event, Key, RawInput};
+use paths_core::ui::are_undo_shortcuts_enabled;
+use windows::Win32::UI::WindowsAndMessaging::WM_KILLFOCUS;
+
+use crate::clipboard::get_clipboard_text;
+
+use self::translation::{InputKind, MessageTranslator};
+
+pub struct InputManager {
+    egui_context: Context,
+    translator: MessageTranslator,
+    events: Vec<Event>,
+    /// The keys that are held down, and whether their key down message was kept from the game. The key up
+    /// message follows it, so the game does not see a key that is never released.
+    pressed_keys: HashMap<u16, bool>,
+}
+
+impl InputManager {
+    pub fn new(egui_context: Context) -> Self {
+        Self {
+            egui_context,
+            translator: MessageTranslator::default(),
+            events: vec![],
+            pressed_keys: HashMap::new(),
+        }
+    }
+
+    /// Returns whether the message was consumed by the UI and should not reach the game.
+    pub fn handle_wnd_proc(&mut self, msg: u32, w_param: u32, l_param: i32) -> bool {
+        if msg == WM_KILLFOCUS {
+            // The key up messages of pressed keys are not received anymore.
+            self.pressed_keys.clear();
+        }
+
+        let Some((kind, events)) = self.translator.translate(msg, w_param, l_param) else {
+            return false;
+        };
+
+        let is_handled_shortcut = events.iter().any(|event| self.is_handled_shortcut(event));
+
+        for event in events {
+            if let Event::Key {
+                key: Key::V,
+                pressed: true,
+                modifiers,
+                ..
+            } = event
+            {
+                if modifiers.command {
+                    if let Some(text) = get_clipboard_text() {
+                        self.events.push(Event::Paste(text));
+                    }
+                }
+            }
+
+            self.events.push(event);
+        }
+
+        match kind {
+            InputKind::Pointer => self.egui_context.wants_pointer_input(),
+            InputKind::Text => self.egui_context.wants_keyboard_input(),
+
+            InputKind::Key {
+                virtual_key,
+                pressed: true,
+            } => *self
+                .pressed_keys
+                .entry(virtual_key)
+                .or_insert_with(|| self.egui_context.wants_keyboard_input() || is_handled_shortcut),
+
+            // Keys pressed before the addon was loaded reach the game.
+            InputKind::Key {
+                virtual_key,
+                pressed: false,
+            } => self.pressed_keys.remove(&virtual_key).unwrap_or(false),
+        }
+    }
+
+    /// Whether the UI uses the key for undo or redo, so the game should not see it.
+    fn is_handled_shortcut(&self, event: &Event) -> bool {
+        matches!(
+            event,
+            Event::Key {
+                key: Key::Z | Key::Y,
+                pressed: true,
+                modifiers,
+                ..
+            } if modifiers.command
+        ) && are_undo_shortcuts_enabled(&self.egui_context)
+    }
+
+    /// Whether a mouse button is held down, for example while dragging a slider.
+    pub fn is_pointer_down(&self) -> bool {
+        self.egui_context.input(|input| input.pointer.any_down())
+    }
+
+    /// The input since the last call. The renderer completes it with the screen and focus information.
+    pub fn take_input(&mut self) -> RawInput {
+        RawInput {
+            events: std::mem::take(&mut self.events),
+            modifiers: self.translator.modifiers(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::Id;
+    use windows::Win32::UI::{
+        Input::KeyboardAndMouse::{self as vk, VIRTUAL_KEY},
+        WindowsAndMessaging::{WM_KEYDOWN, WM_KEYUP},
+    };
+
+    use super::*;
+
+    fn key_down(input_manager: &mut InputManager, virtual_key: VIRTUAL_KEY) -> bool {
+        input_manager.handle_wnd_proc(WM_KEYDOWN, virtual_key.0 as u32, 0)
+    }
+
+    fn key_up(input_manager: &mut InputManager, virtual_key: VIRTUAL_KEY) -> bool {
+        input_manager.handle_wnd_proc(WM_KEYUP, virtual_key.0 as u32, 0)
+    }
+
+    fn set_text_field_focused(ctx: &Context, is_focused: bool) {
+        ctx.memory_mut(|memory| {
+            if is_focused {
+                memory.request_focus(Id::new("text field"));
+            } else {
+                memory.stop_text_input();
+            }
+        });
+    }
+
+    #[test]
+    fn releases_keys_like_they_were_pressed() {
+        let ctx = Context::default();
+        let mut input_manager = InputManager::new(ctx.clone());
+
+        // Typing into a text field keeps the key from the game until it is released.
+        set_text_field_focused(&ctx, true);
+        assert!(key_down(&mut input_manager, vk::VK_A));
+        set_text_field_focused(&ctx, false);
+        assert!(key_down(&mut input_manager, vk::VK_A));
+        assert!(key_up(&mut input_manager, vk::VK_A));
+
+        // The game sees the whole key press if it started there.
+        assert!(!key_down(&mut input_manager, vk::VK_B));
+        set_text_field_focused(&ctx, true);
+        assert!(!key_up(&mut input_manager, vk::VK_B));
+
+        // Keys pressed before the addon was loaded.
+        assert!(!key_up(&mut input_manager, vk::VK_C));
+    }
+
+    #[test]
+    fn forgets_pressed_keys_when_losing_focus() {
+        let ctx = Context::default();
+        let mut input_manager = InputManager::new(ctx.clone());
+
+        set_text_field_focused(&ctx, true);
+        assert!(key_down(&mut input_manager, vk::VK_A));
+
+        input_manager.handle_wnd_proc(WM_KILLFOCUS, 0, 0);
+        set_text_field_focused(&ctx, false);
+
+        assert!(!key_down(&mut input_manager, vk::VK_A));
+        assert!(!key_up(&mut input_manager, vk::VK_A));
+    }
+}
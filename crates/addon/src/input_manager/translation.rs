@@ -0,0 +1,589 @@
+use egui::{Event, Key, Modifiers, MouseWheelUnit, PointerButton, Pos2, Vec2};
+use windows::Win32::UI::{
+    Input::KeyboardAndMouse::{self as vk, VIRTUAL_KEY},
+    WindowsAndMessaging,
+};
+
+/// Which kind of input an event belongs to. The game only loses the input if egui wants this kind.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InputKind {
+    Pointer,
+    Text,
+    /// A key was pressed or released, which has to reach the game either both times or never.
+    Key {
+        virtual_key: u16,
+        pressed: bool,
+    },
+}
+
+/// Translates window messages to egui events. Does not call into Windows, so it only depends on the
+/// messages it has seen before.
+#[derive(Debug, Default)]
+pub struct MessageTranslator {
+    modifiers: Modifiers,
+    high_surrogate: Option<u16>,
+}
+
+impl MessageTranslator {
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    pub fn translate(
+        &mut self,
+        msg: u32,
+        w_param: u32,
+        l_param: i32,
+    ) -> Option<(InputKind, Vec<Event>)> {
+        match msg {
+            WindowsAndMessaging::WM_MOUSEMOVE => Some((
+                InputKind::Pointer,
+                vec![Event::PointerMoved(get_position(l_param))],
+            )),
+
+            WindowsAndMessaging::WM_LBUTTONDOWN
+            | WindowsAndMessaging::WM_LBUTTONDBLCLK
+            | WindowsAndMessaging::WM_LBUTTONUP => {
+                self.pointer_button(msg, l_param, PointerButton::Primary)
+            }
+
+            WindowsAndMessaging::WM_RBUTTONDOWN
+            | WindowsAndMessaging::WM_RBUTTONDBLCLK
+            | WindowsAndMessaging::WM_RBUTTONUP => {
+                self.pointer_button(msg, l_param, PointerButton::Secondary)
+            }
+
+            WindowsAndMessaging::WM_MBUTTONDOWN
+            | WindowsAndMessaging::WM_MBUTTONDBLCLK
+            | WindowsAndMessaging::WM_MBUTTONUP => {
+                self.pointer_button(msg, l_param, PointerButton::Middle)
+            }
+
+            WindowsAndMessaging::WM_XBUTTONDOWN
+            | WindowsAndMessaging::WM_XBUTTONDBLCLK
+            | WindowsAndMessaging::WM_XBUTTONUP => {
+                let button = match (w_param >> 16) as u16 {
+                    WindowsAndMessaging::XBUTTON1 => PointerButton::Extra1,
+                    WindowsAndMessaging::XBUTTON2 => PointerButton::Extra2,
+                    _ => return None,
+                };
+
+                self.pointer_button(msg, l_param, button)
+            }
+
+            WindowsAndMessaging::WM_MOUSEWHEEL | WindowsAndMessaging::WM_MOUSEHWHEEL => {
+                let delta = (w_param >> 16) as i16 as f32 / WindowsAndMessaging::WHEEL_DELTA as f32;
+
+                let delta = match msg {
+                    WindowsAndMessaging::WM_MOUSEWHEEL => Vec2::new(0.0, delta),
+                    _ => Vec2::new(delta, 0.0),
+                };
+
+                Some((
+                    InputKind::Pointer,
+                    vec![Event::MouseWheel {
+                        unit: MouseWheelUnit::Line,
+                        delta,
+                        modifiers: self.modifiers,
+                    }],
+                ))
+            }
+
+            WindowsAndMessaging::WM_KEYDOWN
+            | WindowsAndMessaging::WM_SYSKEYDOWN
+            | WindowsAndMessaging::WM_KEYUP
+            | WindowsAndMessaging::WM_SYSKEYUP => {
+                let pressed = matches!(
+                    msg,
+                    WindowsAndMessaging::WM_KEYDOWN | WindowsAndMessaging::WM_SYSKEYDOWN
+                );
+
+                Some((
+                    InputKind::Key {
+                        virtual_key: w_param as u16,
+                        pressed,
+                    },
+                    self.key(w_param, l_param, pressed),
+                ))
+            }
+
+            WindowsAndMessaging::WM_CHAR => Some((
+                InputKind::Text,
+                self.character(w_param as u16).into_iter().collect(),
+            )),
+
+            WindowsAndMessaging::WM_KILLFOCUS => {
+                // The key up messages of pressed keys are not received anymore.
+                self.modifiers = Modifiers::NONE;
+                self.high_surrogate = None;
+
+                None
+            }
+
+            _ => None,
+        }
+    }
+
+    fn pointer_button(
+        &self,
+        msg: u32,
+        l_param: i32,
+        button: PointerButton,
+    ) -> Option<(InputKind, Vec<Event>)> {
+        let pressed = !matches!(
+            msg,
+            WindowsAndMessaging::WM_LBUTTONUP
+                | WindowsAndMessaging::WM_RBUTTONUP
+                | WindowsAndMessaging::WM_MBUTTONUP
+                | WindowsAndMessaging::WM_XBUTTONUP
+        );
+
+        Some((
+            InputKind::Pointer,
+            vec![Event::PointerButton {
+                pos: get_position(l_param),
+                button,
+                pressed,
+                modifiers: self.modifiers,
+            }],
+        ))
+    }
+
+    fn key(&mut self, w_param: u32, l_param: i32, pressed: bool) -> Vec<Event> {
+        let virtual_key = VIRTUAL_KEY(w_param as u16);
+
+        match virtual_key {
+            vk::VK_SHIFT | vk::VK_LSHIFT | vk::VK_RSHIFT => self.modifiers.shift = pressed,
+
+            vk::VK_CONTROL | vk::VK_LCONTROL | vk::VK_RCONTROL => {
+                self.modifiers.ctrl = pressed;
+                self.modifiers.command = pressed;
+            }
+
+            vk::VK_MENU | vk::VK_LMENU | vk::VK_RMENU => self.modifiers.alt = pressed,
+
+            _ => {}
+        }
+
+        let Some(key) = key_from_virtual_key(virtual_key) else {
+            return vec![];
+        };
+
+        let mut events = vec![];
+
+        if pressed && self.modifiers.command {
+            match key {
+                Key::C => events.push(Event::Copy),
+                Key::X => events.push(Event::Cut),
+                // Pasting needs the clipboard content, see `Event::Paste`.
+                _ => {}
+            }
+        }
+
+        events.push(Event::Key {
+            key,
+            physical_key: None,
+            pressed,
+            // Bit 30 is set if the key was down before.
+            repeat: pressed && l_param & (1 << 30) != 0,
+            modifiers: self.modifiers,
+        });
+
+        events
+    }
+
+    fn character(&mut self, code_unit: u16) -> Option<Event> {
+        if (0xD800..0xDC00).contains(&code_unit) {
+            // The second half of the character follows in the next message.
+            self.high_surrogate = Some(code_unit);
+
+            return None;
+        }
+
+        let code_units = match self.high_surrogate.take() {
+            Some(high_surrogate) => vec![high_surrogate, code_unit],
+            None => vec![code_unit],
+        };
+
+        let text = String::from_utf16(&code_units).ok()?;
+
+        // Control characters like backspace are already handled as keys.
+        if text.chars().any(char::is_control) || (self.modifiers.ctrl && !self.modifiers.alt) {
+            return None;
+        }
+
+        Some(Event::Text(text))
+    }
+}
+
+fn get_position(l_param: i32) -> Pos2 {
+    let x = (l_param & 0xFFFF) as i16 as f32;
+    let y = (l_param >> 16 & 0xFFFF) as i16 as f32;
+
+    Pos2::new(x, y)
+}
+
+pub fn key_from_virtual_key(virtual_key: VIRTUAL_KEY) -> Option<Key> {
+    let key = match virtual_key {
+        vk::VK_DOWN => Key::ArrowDown,
+        vk::VK_LEFT => Key::ArrowLeft,
+        vk::VK_RIGHT => Key::ArrowRight,
+        vk::VK_UP => Key::ArrowUp,
+
+        vk::VK_ESCAPE => Key::Escape,
+        vk::VK_TAB => Key::Tab,
+        vk::VK_BACK => Key::Backspace,
+        vk::VK_RETURN => Key::Enter,
+        vk::VK_SPACE => Key::Space,
+
+        vk::VK_INSERT => Key::Insert,
+        vk::VK_DELETE => Key::Delete,
+        vk::VK_HOME => Key::Home,
+        vk::VK_END => Key::End,
+        vk::VK_PRIOR => Key::PageUp,
+        vk::VK_NEXT => Key::PageDown,
+
+        vk::VK_OEM_COMMA => Key::Comma,
+        vk::VK_OEM_PERIOD => Key::Period,
+        vk::VK_OEM_MINUS | vk::VK_SUBTRACT => Key::Minus,
+        vk::VK_OEM_PLUS | vk::VK_ADD => Key::Plus,
+
+        vk::VK_0 | vk::VK_NUMPAD0 => Key::Num0,
+        vk::VK_1 | vk::VK_NUMPAD1 => Key::Num1,
+        vk::VK_2 | vk::VK_NUMPAD2 => Key::Num2,
+        vk::VK_3 | vk::VK_NUMPAD3 => Key::Num3,
+        vk::VK_4 | vk::VK_NUMPAD4 => Key::Num4,
+        vk::VK_5 | vk::VK_NUMPAD5 => Key::Num5,
+        vk::VK_6 | vk::VK_NUMPAD6 => Key::Num6,
+        vk::VK_7 | vk::VK_NUMPAD7 => Key::Num7,
+        vk::VK_8 | vk::VK_NUMPAD8 => Key::Num8,
+        vk::VK_9 | vk::VK_NUMPAD9 => Key::Num9,
+
+        vk::VK_A => Key::A,
+        vk::VK_B => Key::B,
+        vk::VK_C => Key::C,
+        vk::VK_D => Key::D,
+        vk::VK_E => Key::E,
+        vk::VK_F => Key::F,
+        vk::VK_G => Key::G,
+        vk::VK_H => Key::H,
+        vk::VK_I => Key::I,
+        vk::VK_J => Key::J,
+        vk::VK_K => Key::K,
+        vk::VK_L => Key::L,
+        vk::VK_M => Key::M,
+        vk::VK_N => Key::N,
+        vk::VK_O => Key::O,
+        vk::VK_P => Key::P,
+        vk::VK_Q => Key::Q,
+        vk::VK_R => Key::R,
+        vk::VK_S => Key::S,
+        vk::VK_T => Key::T,
+        vk::VK_U => Key::U,
+        vk::VK_V => Key::V,
+        vk::VK_W => Key::W,
+        vk::VK_X => Key::X,
+        vk::VK_Y => Key::Y,
+        vk::VK_Z => Key::Z,
+
+        vk::VK_F1 => Key::F1,
+        vk::VK_F2 => Key::F2,
+        vk::VK_F3 => Key::F3,
+        vk::VK_F4 => Key::F4,
+        vk::VK_F5 => Key::F5,
+        vk::VK_F6 => Key::F6,
+        vk::VK_F7 => Key::F7,
+        vk::VK_F8 => Key::F8,
+        vk::VK_F9 => Key::F9,
+        vk::VK_F10 => Key::F10,
+        vk::VK_F11 => Key::F11,
+        vk::VK_F12 => Key::F12,
+
+        _ => return None,
+    };
+
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_REPEATED: i32 = 1 << 30;
+
+    fn key_down(translator: &mut MessageTranslator, virtual_key: VIRTUAL_KEY) -> Vec<Event> {
+        translate(
+            translator,
+            WindowsAndMessaging::WM_KEYDOWN,
+            virtual_key.0 as u32,
+            0,
+        )
+    }
+
+    fn key_up(translator: &mut MessageTranslator, virtual_key: VIRTUAL_KEY) -> Vec<Event> {
+        translate(
+            translator,
+            WindowsAndMessaging::WM_KEYUP,
+            virtual_key.0 as u32,
+            0,
+        )
+    }
+
+    fn translate(
+        translator: &mut MessageTranslator,
+        msg: u32,
+        w_param: u32,
+        l_param: i32,
+    ) -> Vec<Event> {
+        translator
+            .translate(msg, w_param, l_param)
+            .map(|(_, events)| events)
+            .unwrap_or_default()
+    }
+
+    fn position(x: i16, y: i16) -> i32 {
+        (y as u16 as i32) << 16 | x as u16 as i32
+    }
+
+    #[test]
+    fn translates_keys() {
+        let mut translator = MessageTranslator::default();
+
+        assert_eq!(
+            translator.translate(WindowsAndMessaging::WM_KEYDOWN, vk::VK_A.0 as u32, 0),
+            Some((
+                InputKind::Key {
+                    virtual_key: vk::VK_A.0,
+                    pressed: true,
+                },
+                vec![Event::Key {
+                    key: Key::A,
+                    physical_key: None,
+                    pressed: true,
+                    repeat: false,
+                    modifiers: Modifiers::NONE,
+                }]
+            ))
+        );
+
+        assert_eq!(
+            translate(
+                &mut translator,
+                WindowsAndMessaging::WM_KEYDOWN,
+                vk::VK_A.0 as u32,
+                KEY_REPEATED
+            ),
+            vec![Event::Key {
+                key: Key::A,
+                physical_key: None,
+                pressed: true,
+                repeat: true,
+                modifiers: Modifiers::NONE,
+            }]
+        );
+
+        assert_eq!(
+            key_up(&mut translator, vk::VK_A),
+            vec![Event::Key {
+                key: Key::A,
+                physical_key: None,
+                pressed: false,
+                repeat: false,
+                modifiers: Modifiers::NONE,
+            }]
+        );
+    }
+
+    #[test]
+    fn tracks_modifiers() {
+        let mut translator = MessageTranslator::default();
+
+        assert_eq!(key_down(&mut translator, vk::VK_SHIFT), vec![]);
+        assert_eq!(key_down(&mut translator, vk::VK_CONTROL), vec![]);
+        assert_eq!(
+            translate(
+                &mut translator,
+                WindowsAndMessaging::WM_SYSKEYDOWN,
+                vk::VK_MENU.0 as u32,
+                0
+            ),
+            vec![]
+        );
+
+        let modifiers = Modifiers {
+            alt: true,
+            ctrl: true,
+            shift: true,
+            mac_cmd: false,
+            command: true,
+        };
+        assert_eq!(translator.modifiers(), modifiers);
+
+        assert_eq!(
+            key_down(&mut translator, vk::VK_Z),
+            vec![Event::Key {
+                key: Key::Z,
+                physical_key: None,
+                pressed: true,
+                repeat: false,
+                modifiers,
+            }]
+        );
+
+        key_up(&mut translator, vk::VK_SHIFT);
+        key_up(&mut translator, vk::VK_MENU);
+        assert_eq!(translator.modifiers(), Modifiers::COMMAND | Modifiers::CTRL);
+
+        // The key up messages are lost while the game is not focused.
+        translate(&mut translator, WindowsAndMessaging::WM_KILLFOCUS, 0, 0);
+        assert_eq!(translator.modifiers(), Modifiers::NONE);
+    }
+
+    #[test]
+    fn translates_copy_and_cut() {
+        let mut translator = MessageTranslator::default();
+
+        key_down(&mut translator, vk::VK_CONTROL);
+
+        assert!(matches!(
+            key_down(&mut translator, vk::VK_C)[..],
+            [Event::Copy, Event::Key { key: Key::C, .. }]
+        ));
+        assert!(matches!(
+            key_down(&mut translator, vk::VK_X)[..],
+            [Event::Cut, Event::Key { key: Key::X, .. }]
+        ));
+        assert!(matches!(
+            key_down(&mut translator, vk::VK_V)[..],
+            [Event::Key { key: Key::V, .. }]
+        ));
+    }
+
+    #[test]
+    fn translates_characters() {
+        let mut translator = MessageTranslator::default();
+
+        assert_eq!(
+            translator.translate(WindowsAndMessaging::WM_CHAR, 'ä' as u32, 0),
+            Some((InputKind::Text, vec![Event::Text("ä".to_owned())]))
+        );
+
+        // Backspace is already handled as a key.
+        assert_eq!(
+            translate(&mut translator, WindowsAndMessaging::WM_CHAR, 0x08, 0),
+            vec![]
+        );
+
+        let mut code_units = [0; 2];
+        '😀'.encode_utf16(&mut code_units);
+
+        assert_eq!(
+            translate(
+                &mut translator,
+                WindowsAndMessaging::WM_CHAR,
+                code_units[0] as u32,
+                0
+            ),
+            vec![]
+        );
+        assert_eq!(
+            translate(
+                &mut translator,
+                WindowsAndMessaging::WM_CHAR,
+                code_units[1] as u32,
+                0
+            ),
+            vec![Event::Text("😀".to_owned())]
+        );
+
+        // Shortcuts like ctrl + a are not text, while AltGr reports ctrl and alt.
+        key_down(&mut translator, vk::VK_CONTROL);
+        assert_eq!(
+            translate(&mut translator, WindowsAndMessaging::WM_CHAR, 'a' as u32, 0),
+            vec![]
+        );
+
+        key_down(&mut translator, vk::VK_MENU);
+        assert_eq!(
+            translate(&mut translator, WindowsAndMessaging::WM_CHAR, '@' as u32, 0),
+            vec![Event::Text("@".to_owned())]
+        );
+    }
+
+    #[test]
+    fn translates_mouse_messages() {
+        let mut translator = MessageTranslator::default();
+
+        assert_eq!(
+            translator.translate(WindowsAndMessaging::WM_MOUSEMOVE, 0, position(-5, 20)),
+            Some((
+                InputKind::Pointer,
+                vec![Event::PointerMoved(Pos2::new(-5.0, 20.0))]
+            ))
+        );
+
+        key_down(&mut translator, vk::VK_SHIFT);
+
+        assert_eq!(
+            translate(
+                &mut translator,
+                WindowsAndMessaging::WM_LBUTTONDOWN,
+                0,
+                position(1, 2)
+            ),
+            vec![Event::PointerButton {
+                pos: Pos2::new(1.0, 2.0),
+                button: PointerButton::Primary,
+                pressed: true,
+                modifiers: Modifiers::SHIFT,
+            }]
+        );
+
+        assert_eq!(
+            translate(
+                &mut translator,
+                WindowsAndMessaging::WM_RBUTTONUP,
+                0,
+                position(3, 4)
+            ),
+            vec![Event::PointerButton {
+                pos: Pos2::new(3.0, 4.0),
+                button: PointerButton::Secondary,
+                pressed: false,
+                modifiers: Modifiers::SHIFT,
+            }]
+        );
+
+        assert_eq!(
+            translate(
+                &mut translator,
+                WindowsAndMessaging::WM_XBUTTONDOWN,
+                (WindowsAndMessaging::XBUTTON2 as u32) << 16,
+                position(0, 0)
+            ),
+            vec![Event::PointerButton {
+                pos: Pos2::ZERO,
+                button: PointerButton::Extra2,
+                pressed: true,
+                modifiers: Modifiers::SHIFT,
+            }]
+        );
+
+        // Scrolling down by one notch.
+        let delta = -(WindowsAndMessaging::WHEEL_DELTA as i16);
+
+        assert_eq!(
+            translate(
+                &mut translator,
+                WindowsAndMessaging::WM_MOUSEWHEEL,
+                (delta as u16 as u32) << 16,
+                0
+            ),
+            vec![Event::MouseWheel {
+                unit: MouseWheelUnit::Line,
+                delta: Vec2::new(0.0, -1.0),
+                modifiers: Modifiers::SHIFT,
+            }]
+        );
+    }
+}
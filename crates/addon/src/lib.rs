@@ -1,5 +1,6 @@
 mod addon_def;
 mod callbacks;
+mod clipboard;
 mod constants;
 mod input_manager;
 mod panic;
@@ -1,9 +1,14 @@
 mod trails;
 
-use std::{rc::Rc, sync::Mutex};
+use std::{collections::HashMap, rc::Rc, sync::Mutex};
 
 use log_err::LogErrResult;
-use paths_core::{markers::ActiveMarkerCategories, settings::Settings};
+use nalgebra::{Matrix3, Point2};
+use paths_core::{
+    maps::{compass_rect, world_to_screen_transformation, BoundingBox, MapView},
+    markers::{ActiveMarkerCategories, TrailId},
+    settings::{Settings, TrailColor, TrailLineStyle},
+};
 use trails::TrailPathCache;
 use windows::{
     Foundation::Numerics::Matrix3x2,
@@ -22,9 +27,11 @@ pub struct MapRenderer {
     d2d1_device_context: Rc<ID2D1DeviceContext>,
 
     trail_path_cache: TrailPathCache,
-    trail_stroke_style: Option<ID2D1StrokeStyle1>,
-    black_brush: Option<ID2D1SolidColorBrush>,
-    white_brush: Option<ID2D1SolidColorBrush>,
+    trail_stroke_styles: HashMap<TrailLineStyle, ID2D1StrokeStyle1>,
+    /// In continent coordinates, computed once per trail.
+    trail_bounds: HashMap<TrailId, Option<BoundingBox>>,
+    /// By their color including the opacity, since a brush is shared by every outline drawn with it.
+    outline_brushes: HashMap<TrailColor, ID2D1SolidColorBrush>,
 }
 
 impl MapRenderer {
@@ -40,9 +47,9 @@ impl MapRenderer {
             d2d1_device_context,
 
             trail_path_cache: TrailPathCache::new(d2d1_factory),
-            trail_stroke_style: None,
-            black_brush: None,
-            white_brush: None,
+            trail_stroke_styles: HashMap::new(),
+            trail_bounds: HashMap::new(),
+            outline_brushes: HashMap::new(),
         }
     }
 
@@ -74,25 +81,35 @@ impl MapRenderer {
         active_marker_categories: &ActiveMarkerCategories,
         settings: &Settings,
     ) {
-        let world_to_screen_transformation = self.get_world_to_screen_transformation(
-            &mumble_data.Context.Compass,
-            // Move map center to screen center.
-            {
-                let config = self.config.lock().log_unwrap();
+        let (half_screen_size, screen_rect) = {
+            let config = self.config.lock().log_unwrap();
+
+            (
+                Point2::new(config.half_screen_width, config.half_screen_height),
+                BoundingBox {
+                    min: Point2::origin(),
+                    max: Point2::new(config.screen_width, config.screen_height),
+                },
+            )
+        };
 
-                Matrix3x2::translation(config.half_screen_width, config.half_screen_height)
-            },
+        let world_to_screen_transformation = world_to_screen_transformation(
+            &self.get_map_view(&mumble_data.Context.Compass, false),
+            // Move map center to screen center.
+            half_screen_size,
         );
 
         if settings.limit_markers_to_current_map {
             self.draw_trails(
                 &world_to_screen_transformation,
+                &screen_rect,
                 active_marker_categories.active_trails_of_current_map(),
                 settings,
             );
         } else {
             self.draw_trails(
                 &world_to_screen_transformation,
+                &screen_rect,
                 active_marker_categories.all_active_trails(),
                 settings,
             );
@@ -105,25 +122,28 @@ impl MapRenderer {
         active_marker_categories: &ActiveMarkerCategories,
         settings: &Settings,
     ) {
-        let compass_rect = self.get_compass_rect(&mumble_data.Context);
-        let world_to_screen_transformation = self.get_world_to_screen_transformation(
-            &mumble_data.Context.Compass,
+        let compass_rect = self.get_compass_rect(&mumble_data.Context, settings);
+        let world_to_screen_transformation = world_to_screen_transformation(
+            &self.get_map_view(
+                &mumble_data.Context.Compass,
+                mumble_data.Context.IsCompassRotating() > 0,
+            ),
             // Move map center to compass center.
-            Matrix3x2::translation(
+            Point2::new(
                 (compass_rect.right + compass_rect.left) / 2.0,
                 (compass_rect.bottom + compass_rect.top) / 2.0,
             ),
         );
 
-        if mumble_data.Context.IsCompassRotating() > 0 {
-            // TODO: Handle rotating compass (with matrix transformation?).
-        }
-
         self.d2d1_device_context
             .PushAxisAlignedClip(&compass_rect, D2D1_ANTIALIAS_MODE_PER_PRIMITIVE);
 
         self.draw_trails(
             &world_to_screen_transformation,
+            &BoundingBox {
+                min: Point2::new(compass_rect.left, compass_rect.top),
+                max: Point2::new(compass_rect.right, compass_rect.bottom),
+            },
             active_marker_categories.active_trails_of_current_map(),
             settings,
         );
@@ -131,63 +151,65 @@ impl MapRenderer {
         self.d2d1_device_context.PopAxisAlignedClip();
     }
 
-    fn get_world_to_screen_transformation(
-        &self,
-        compass: &api::Mumble_Compass,
-        translate_to_screen: Matrix3x2,
-    ) -> Matrix3x2 {
+    /// Only the compass rotates, the opened map always faces north.
+    fn get_map_view(&self, compass: &api::Mumble_Compass, is_rotating: bool) -> MapView {
         let map_scale = {
             let compass_scale = compass.Scale;
 
-            compass_scale / { self.config.lock().log_unwrap().ui_scale_factor }
-        };
-
-        // Move map center to 0,0
-        let translate_map_center = Matrix3x2::translation(-compass.Center.X, -compass.Center.Y);
-
-        let scale = Matrix3x2 {
-            M11: 1.0 / map_scale,
-            M12: 0.0,
-            M21: 0.0,
-            M22: 1.0 / map_scale,
-            M31: 0.0,
-            M32: 0.0,
+            compass_scale / { self.config.lock().log_unwrap().ui_scale_factor() }
         };
 
-        translate_map_center * scale * translate_to_screen
+        MapView {
+            center: Point2::new(compass.Center.X, compass.Center.Y),
+            scale: map_scale,
+            rotation: if is_rotating { compass.Rotation } else { 0.0 },
+            rotation_center: Point2::new(compass.PlayerPosition.X, compass.PlayerPosition.Y),
+        }
     }
 
-    fn get_compass_rect(&self, mumble_context: &api::Mumble_Context) -> D2D_RECT_F {
-        let compass_width = mumble_context.Compass.Width as f32;
-        let compass_height = mumble_context.Compass.Height as f32;
-
-        let config = self.config.lock().log_unwrap();
-
-        let left = config.screen_width - (compass_width * config.ui_scale_factor);
-        let right = config.screen_width;
-
-        let (top, bottom) = if mumble_context.IsCompassTopRight() > 0 {
-            let top = 1.0;
-            let bottom = compass_height * config.ui_scale_factor + 1.0;
-
-            (top, bottom)
-        } else {
-            const DISTANCE_FROM_BOTTOM: f32 = 37.0;
-
-            let scaled_distance = DISTANCE_FROM_BOTTOM * config.ui_scale_factor;
-
-            let top =
-                config.screen_height - compass_height * config.ui_scale_factor - scaled_distance;
-            let bottom = config.screen_height - scaled_distance;
-
-            (top, bottom)
-        };
+    fn get_compass_rect(
+        &self,
+        mumble_context: &api::Mumble_Context,
+        settings: &Settings,
+    ) -> D2D_RECT_F {
+        let layout = self
+            .config
+            .lock()
+            .log_unwrap()
+            .compass_layout(mumble_context);
+
+        let rect = compass_rect(
+            &layout,
+            &settings
+                .compass_calibrations
+                .get(&layout.ui_size)
+                .copied()
+                .unwrap_or_default(),
+        );
 
         D2D_RECT_F {
-            left,
-            top,
-            right,
-            bottom,
+            left: rect.left(),
+            top: rect.top(),
+            right: rect.right(),
+            bottom: rect.bottom(),
         }
     }
 }
+
+/// Direct2D transforms row vectors, so the matrix is transposed.
+pub(super) fn to_matrix3x2(matrix: &Matrix3<f32>) -> Matrix3x2 {
+    Matrix3x2 {
+        M11: matrix[(0, 0)],
+        M12: matrix[(1, 0)],
+        M21: matrix[(0, 1)],
+        M22: matrix[(1, 1)],
+        M31: matrix[(0, 2)],
+        M32: matrix[(1, 2)],
+    }
+}
+
+pub(super) fn to_matrix3(matrix: &Matrix3x2) -> Matrix3<f32> {
+    Matrix3::new(
+        matrix.M11, matrix.M21, matrix.M31, matrix.M12, matrix.M22, matrix.M32, 0.0, 0.0, 1.0,
+    )
+}
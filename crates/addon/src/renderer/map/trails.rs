@@ -1,13 +1,21 @@
-use std::{collections::HashMap, f32, rc::Rc};
+use std::{
+    collections::HashMap,
+    f32,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
 use egui::{Color32, Rgba};
 use log_err::LogErrResult;
-use nalgebra::distance;
+use nalgebra::Matrix3;
 use paths_core::{
-    maps::MAP_TO_WORLD_TRANSFORMATION_MATRICES,
-    markers::{simplify_line_string, ActiveTrail},
-    points::Point3,
-    settings::{Settings, TrailWidth},
+    maps::{trail_bounds, visible_world_rect, BoundingBox, MAP_TO_WORLD_TRANSFORMATION_MATRICES},
+    markers::{
+        level_of_detail, ActiveTrail, ArrowPlacement, PreparedTrail, PreparedTrailLevel, TrailId,
+        TrailPreparationParams, TrailPreparationWorker,
+    },
+    points::{Dimensions, Point3},
+    settings::{Settings, TrailColor, TrailLineStyle, TrailWidth},
 };
 use windows::{
     core::Interface,
@@ -17,30 +25,38 @@ use windows::{
             D2D1_COLOR_F, D2D1_FIGURE_BEGIN_FILLED, D2D1_FIGURE_BEGIN_HOLLOW,
             D2D1_FIGURE_END_CLOSED, D2D1_FIGURE_END_OPEN, D2D_POINT_2F,
         },
-        ID2D1Factory1, ID2D1Geometry, ID2D1SolidColorBrush, ID2D1StrokeStyle1,
-        D2D1_CAP_STYLE_ROUND, D2D1_LINE_JOIN_ROUND, D2D1_STROKE_STYLE_PROPERTIES1,
+        ID2D1DeviceContext, ID2D1Factory1, ID2D1Geometry, ID2D1SolidColorBrush, ID2D1StrokeStyle1,
+        D2D1_CAP_STYLE_FLAT, D2D1_CAP_STYLE_ROUND, D2D1_DASH_STYLE_DASH, D2D1_DASH_STYLE_DOT,
+        D2D1_DASH_STYLE_SOLID, D2D1_LINE_JOIN_ROUND, D2D1_STROKE_STYLE_PROPERTIES1,
     },
 };
 
-use super::MapRenderer;
+use super::{to_matrix3, to_matrix3x2, MapRenderer};
 
 const TRAIL_OUTLINE_WIDTH_FACTOR: f32 = 0.5;
-const DISTANCE_BETWEEN_ARROWS: f32 = 500.0;
 const ARROW_WIDTH_FACTOR: f32 = 4.0;
 const ARROW_LENGTH_FACTOR: f32 = 4.0;
 
 impl MapRenderer {
     pub unsafe fn draw_trails<'a, Trails: Iterator<Item = (&'a u32, &'a ActiveTrail<'a>)>>(
         &mut self,
-        world_to_screen_transformation: &Matrix3x2,
+        world_to_screen_transformation: &Matrix3<f32>,
+        screen_rect: &BoundingBox,
         trails: Trails,
         settings: &Settings,
     ) {
+        self.trail_path_cache.start_frame();
+
+        let visible_rect = visible_world_rect(world_to_screen_transformation, screen_rect);
+        let world_to_screen_transformation = to_matrix3x2(world_to_screen_transformation);
+
         // Group trails by color.
         let mut trails_by_color = HashMap::<_, Vec<_>>::new();
 
         for (map_id, trail) in trails {
-            if trail.points.len() >= 2 {
+            if trail.points.len() >= 2
+                && self.is_trail_visible(map_id, trail, visible_rect.as_ref())
+            {
                 trails_by_color
                     .entry(trail.color)
                     .or_default()
@@ -49,34 +65,57 @@ impl MapRenderer {
         }
 
         for (color, trails) in trails_by_color {
-            let color: Rgba = Color32::from_rgb(color[0], color[1], color[2]).into();
-
-            let brush = self
-                .d2d1_device_context
-                .CreateSolidColorBrush(
-                    &D2D1_COLOR_F {
-                        r: color.r(),
-                        g: color.g(),
-                        b: color.b(),
-                        a: color.a(),
-                    },
-                    None,
-                )
-                .log_expect("could not create trail brush");
+            let brush = create_trail_brush(&self.d2d1_device_context, color);
 
             for (map_id, trail) in trails {
                 self.draw_trail(
-                    world_to_screen_transformation,
+                    &world_to_screen_transformation,
                     map_id,
                     trail,
                     &brush,
-                    color.intensity() < 0.5,
+                    is_dark(color),
                     settings,
                 );
             }
         }
     }
 
+    /// Trails are skipped if their bounds and the visible part of the continent do not overlap.
+    fn is_trail_visible(
+        &mut self,
+        map_id: &u32,
+        trail: &ActiveTrail,
+        visible_rect: Option<&BoundingBox>,
+    ) -> bool {
+        let Some(map_to_world_transformation) = MAP_TO_WORLD_TRANSFORMATION_MATRICES.get(map_id)
+        else {
+            return false;
+        };
+
+        let Some(visible_rect) = visible_rect else {
+            return true;
+        };
+
+        let map_to_world_transformation = to_matrix3(map_to_world_transformation);
+
+        let Some(bounds) = *self
+            .trail_bounds
+            .entry(trail.trail_id)
+            .or_insert_with(|| trail_bounds(trail.points, &map_to_world_transformation))
+        else {
+            return false;
+        };
+
+        // The arrows stick out of the trail the most.
+        let margin = *trail.width
+            * ARROW_LENGTH_FACTOR.max(ARROW_WIDTH_FACTOR)
+            * map_to_world_transformation
+                .m11
+                .hypot(map_to_world_transformation.m21);
+
+        bounds.expand(margin).intersects(visible_rect)
+    }
+
     unsafe fn draw_trail(
         &mut self,
         world_to_screen_transformation: &Matrix3x2,
@@ -91,80 +130,150 @@ impl MapRenderer {
             return;
         };
 
-        let geometries = self.trail_path_cache.get_trail_geometries(trail, settings);
-
-        let bg_brush: &ID2D1SolidColorBrush = if bg_is_white {
-            self.white_brush.get_or_insert_with(|| {
-                self.d2d1_device_context
-                    .CreateSolidColorBrush(
-                        &D2D1_COLOR_F {
-                            r: 1.0,
-                            g: 1.0,
-                            b: 1.0,
-                            a: 1.0,
-                        },
-                        None,
-                    )
-                    .log_expect("could not create white brush")
-            })
-        } else {
-            self.black_brush.get_or_insert_with(|| {
-                self.d2d1_device_context
-                    .CreateSolidColorBrush(
-                        &D2D1_COLOR_F {
-                            r: 0.0,
-                            g: 0.0,
-                            b: 0.0,
-                            a: 1.0,
-                        },
-                        None,
-                    )
-                    .log_expect("could not create black brush")
-            })
-        };
+        let line_stroke_style = self.trail_stroke_style(trail.style.line);
+        // Dashes of the outline would not line up with the ones of the trail, because their length depends on
+        // the width of the line.
+        let outline_stroke_style = self.trail_stroke_style(TrailLineStyle::Solid);
+
+        // The outline fades together with the trail.
+        let outline_color = match trail.style.outline_color {
+            _ if !trail.style.outline => None,
+            Some(color) => Some(color),
+            None if bg_is_white => Some(TrailColor::from_rgb([255, 255, 255])),
+            None => Some(TrailColor::from_rgb([0, 0, 0])),
+        }
+        .map(|color| color.scale_alpha(trail.color.alpha() as f32 / u8::MAX as f32));
 
-        let stroke_style: &ID2D1StrokeStyle1 = self.trail_stroke_style.get_or_insert_with(|| {
-            self.d2d1_factory
-                .CreateStrokeStyle(
-                    &D2D1_STROKE_STYLE_PROPERTIES1 {
-                        startCap: D2D1_CAP_STYLE_ROUND,
-                        endCap: D2D1_CAP_STYLE_ROUND,
-                        lineJoin: D2D1_LINE_JOIN_ROUND,
-                        ..Default::default()
-                    },
-                    None,
-                )
-                .log_expect("could not create trail stroke style")
+        let outline_brush = outline_color.map(|color| {
+            self.outline_brushes
+                .entry(color)
+                .or_insert_with(|| create_trail_brush(&self.d2d1_device_context, color))
+                .clone()
         });
 
+        let map_to_screen_transformation =
+            map_to_world_transformation * world_to_screen_transformation;
+
+        // The length of a pixel in the units of the trail.
+        let pixel_size = 1.0
+            / map_to_screen_transformation
+                .M11
+                .hypot(map_to_screen_transformation.M12);
+
+        // Appears once it is prepared.
+        let Some(geometries) = self.trail_path_cache.get_trail_geometries(trail, settings) else {
+            return;
+        };
+
+        let geometries =
+            &geometries.levels[level_of_detail(pixel_size, *settings.trail_simplify_epsilon)];
+
         self.d2d1_device_context
-            .SetTransform(&(map_to_world_transformation * world_to_screen_transformation));
+            .SetTransform(&map_to_screen_transformation);
+
+        if let Some(outline_brush) = &outline_brush {
+            self.d2d1_device_context.DrawGeometry(
+                &geometries.path,
+                outline_brush,
+                *trail.width * (1.0 + TRAIL_OUTLINE_WIDTH_FACTOR),
+                &outline_stroke_style,
+            );
+        }
 
         self.d2d1_device_context.DrawGeometry(
             &geometries.path,
-            bg_brush,
-            *trail.width * (1.0 + TRAIL_OUTLINE_WIDTH_FACTOR),
-            stroke_style,
+            brush,
+            *trail.width,
+            &line_stroke_style,
         );
 
-        self.d2d1_device_context
-            .DrawGeometry(&geometries.path, brush, *trail.width, stroke_style);
+        if !trail.style.arrows {
+            return;
+        }
 
         for arrow in &geometries.arrows {
-            self.d2d1_device_context.DrawGeometry(
-                arrow,
-                bg_brush,
-                *trail.width * TRAIL_OUTLINE_WIDTH_FACTOR,
-                stroke_style,
-            );
+            if let Some(outline_brush) = &outline_brush {
+                self.d2d1_device_context.DrawGeometry(
+                    arrow,
+                    outline_brush,
+                    *trail.width * TRAIL_OUTLINE_WIDTH_FACTOR,
+                    &outline_stroke_style,
+                );
+            }
 
             self.d2d1_device_context.FillGeometry(arrow, brush, None);
         }
     }
+
+    unsafe fn trail_stroke_style(&mut self, line: TrailLineStyle) -> ID2D1StrokeStyle1 {
+        self.trail_stroke_styles
+            .entry(line)
+            .or_insert_with(|| {
+                let (dash_style, dash_cap) = match line {
+                    TrailLineStyle::Solid => (D2D1_DASH_STYLE_SOLID, D2D1_CAP_STYLE_FLAT),
+                    TrailLineStyle::Dashed => (D2D1_DASH_STYLE_DASH, D2D1_CAP_STYLE_FLAT),
+                    TrailLineStyle::Dotted => (D2D1_DASH_STYLE_DOT, D2D1_CAP_STYLE_ROUND),
+                };
+
+                self.d2d1_factory
+                    .CreateStrokeStyle(
+                        &D2D1_STROKE_STYLE_PROPERTIES1 {
+                            startCap: D2D1_CAP_STYLE_ROUND,
+                            endCap: D2D1_CAP_STYLE_ROUND,
+                            dashCap: dash_cap,
+                            lineJoin: D2D1_LINE_JOIN_ROUND,
+                            dashStyle: dash_style,
+                            ..Default::default()
+                        },
+                        None,
+                    )
+                    .log_expect("could not create trail stroke style")
+            })
+            .clone()
+    }
+}
+
+/// Direct2D expects colors with straight alpha, so the alpha is applied as the opacity of the brush.
+unsafe fn create_trail_brush(
+    d2d1_device_context: &ID2D1DeviceContext,
+    color: TrailColor,
+) -> ID2D1SolidColorBrush {
+    let [r, g, b, a] = *color;
+    let [r, g, b, a] =
+        Rgba::from(Color32::from_rgba_unmultiplied(r, g, b, a)).to_rgba_unmultiplied();
+
+    let brush = d2d1_device_context
+        .CreateSolidColorBrush(&D2D1_COLOR_F { r, g, b, a: 1.0 }, None)
+        .log_expect("could not create trail brush");
+
+    brush.SetOpacity(a);
+
+    brush
+}
+
+/// Dark trails get a white outline.
+fn is_dark(color: TrailColor) -> bool {
+    let [r, g, b, _] = *color;
+
+    Rgba::from(Color32::from_rgb(r, g, b)).intensity() < 0.5
 }
 
+/// Geometries are only created while there is time left in the frame, the rest waits for the next frames.
+const GEOMETRY_BUDGET_PER_FRAME: Duration = Duration::from_millis(4);
+
+/// Once more trails are cached, the ones drawn least recently are dropped. Trails drawn in the last frame are
+/// always kept.
+const MAX_CACHED_TRAILS: usize = 2000;
+
 pub struct TrailPathCache {
-    cache: HashMap<u64, TrailGeometries>,
+    cache: HashMap<TrailId, TrailGeometries>,
+    /// Prepared by the worker, but without geometries yet.
+    prepared: HashMap<TrailId, PreparedTrail>,
+    /// The frame in which each cached, prepared or requested trail was drawn the last time.
+    last_used: HashMap<TrailId, u64>,
+    frame: u64,
+    worker: TrailPreparationWorker,
+    frame_deadline: Instant,
     d2d1_factory: Rc<ID2D1Factory1>,
 }
 
@@ -172,77 +281,203 @@ impl TrailPathCache {
     pub fn new(d2d1_factory: Rc<ID2D1Factory1>) -> Self {
         Self {
             cache: HashMap::new(),
+            prepared: HashMap::new(),
+            last_used: HashMap::new(),
+            frame: 0,
+            worker: TrailPreparationWorker::new(),
+            frame_deadline: Instant::now(),
             d2d1_factory,
         }
     }
 
+    fn start_frame(&mut self) {
+        self.evict_least_recently_used();
+
+        // Trails that were evicted while they were prepared are not needed anymore.
+        let finished = self.worker.finished();
+        self.prepared.extend(
+            finished
+                .into_iter()
+                .filter(|(trail_id, _)| self.last_used.contains_key(trail_id)),
+        );
+
+        self.frame += 1;
+        self.frame_deadline = Instant::now() + GEOMETRY_BUDGET_PER_FRAME;
+    }
+
+    /// Trails of other maps or categories that were deactivated would stay in the cache forever otherwise.
+    fn evict_least_recently_used(&mut self) {
+        let excess = self.last_used.len().saturating_sub(MAX_CACHED_TRAILS);
+
+        if excess == 0 {
+            return;
+        }
+
+        let mut evictable = self
+            .last_used
+            .iter()
+            .filter(|(_, frame)| **frame < self.frame)
+            .map(|(trail_id, frame)| (*frame, *trail_id))
+            .collect::<Vec<_>>();
+
+        evictable.sort_unstable();
+
+        for (_, trail_id) in evictable.into_iter().take(excess) {
+            self.cache.remove(&trail_id);
+            self.prepared.remove(&trail_id);
+            self.last_used.remove(&trail_id);
+        }
+    }
+
+    /// `None` until the trail is prepared for the first time. While it is prepared again, e.g. after it was
+    /// reversed, the outdated geometries are used.
     unsafe fn get_trail_geometries(
         &mut self,
         trail: &ActiveTrail,
         settings: &Settings,
-    ) -> &TrailGeometries {
-        self.cache
-            .entry(trail.hash)
-            .and_modify(|geometries| {
-                if geometries.last_trail_width != trail.width {
-                    let simplified_points =
-                        simplify_line_string(&trail.points, *settings.trail_simplify_epsilon);
-
-                    geometries.last_trail_width = trail.width;
-                    geometries.arrows = TrailGeometries::build_arrows(
-                        &self.d2d1_factory,
-                        &simplified_points,
-                        trail.width,
+    ) -> Option<&TrailGeometries> {
+        let params = TrailPreparationParams {
+            simplifier: settings.trail_simplifier,
+            epsilon: *settings.trail_simplify_epsilon,
+            max_gap: *settings.trail_gap_distance,
+            // The height is not visible on the map.
+            dimensions: Dimensions::Two,
+            reversed: trail.reversed,
+            arrow_spacing: trail.style.arrow_spacing,
+        };
+
+        self.last_used.insert(trail.trail_id, self.frame);
+
+        let is_up_to_date = self
+            .cache
+            .get(&trail.trail_id)
+            .is_some_and(|geometries| geometries.params == params);
+
+        if !is_up_to_date {
+            let prepared = self
+                .prepared
+                .remove(&trail.trail_id)
+                .filter(|prepared| prepared.params == params);
+
+            match prepared {
+                Some(prepared) if Instant::now() < self.frame_deadline => {
+                    self.cache.insert(
+                        trail.trail_id,
+                        TrailGeometries::build(&self.d2d1_factory, prepared, trail.width),
                     );
                 }
-            })
-            .or_insert_with(|| {
-                let simplified_points =
-                    simplify_line_string(&trail.points, *settings.trail_simplify_epsilon);
-
-                TrailGeometries {
-                    last_trail_width: trail.width,
-                    path: TrailGeometries::build_path(&self.d2d1_factory, &simplified_points),
-                    arrows: TrailGeometries::build_arrows(
-                        &self.d2d1_factory,
-                        &simplified_points,
-                        trail.width,
-                    ),
+
+                // Kept for the next frame.
+                Some(prepared) => {
+                    self.prepared.insert(trail.trail_id, prepared);
                 }
-            })
+
+                None => self.worker.request(trail.trail_id, trail.points, params),
+            }
+        }
+
+        let geometries = self.cache.get_mut(&trail.trail_id)?;
+
+        // The arrows of the previous width are drawn until there is time left in a frame.
+        if geometries.trail_width != trail.width && Instant::now() < self.frame_deadline {
+            geometries.rebuild_arrows(&self.d2d1_factory, trail.width);
+        }
+
+        Some(geometries)
     }
 }
 
 struct TrailGeometries {
-    last_trail_width: TrailWidth,
+    params: TrailPreparationParams,
+    trail_width: TrailWidth,
+    /// From the most to the least detailed level.
+    levels: Vec<TrailLevelGeometries>,
+}
+
+impl TrailGeometries {
+    unsafe fn build(
+        d2d1_factory: &ID2D1Factory1,
+        prepared: PreparedTrail,
+        trail_width: TrailWidth,
+    ) -> Self {
+        let base_arrow = TrailLevelGeometries::build_arrow(d2d1_factory, trail_width);
+
+        let mut levels = Vec::<TrailLevelGeometries>::new();
+
+        for level in prepared.levels {
+            // Short trails often look the same on several levels.
+            let level = match levels.last() {
+                Some(previous_level) if *previous_level.prepared == level => previous_level.clone(),
+                _ => TrailLevelGeometries {
+                    path: TrailLevelGeometries::build_path(d2d1_factory, &level.segments),
+                    arrows: TrailLevelGeometries::build_arrows(
+                        d2d1_factory,
+                        &base_arrow,
+                        &level.arrows,
+                    ),
+                    prepared: Rc::new(level),
+                },
+            };
+
+            levels.push(level);
+        }
+
+        Self {
+            params: prepared.params,
+            trail_width,
+            levels,
+        }
+    }
+
+    unsafe fn rebuild_arrows(&mut self, d2d1_factory: &ID2D1Factory1, trail_width: TrailWidth) {
+        let base_arrow = TrailLevelGeometries::build_arrow(d2d1_factory, trail_width);
+
+        for level in &mut self.levels {
+            level.arrows = TrailLevelGeometries::build_arrows(
+                d2d1_factory,
+                &base_arrow,
+                &level.prepared.arrows,
+            );
+        }
+
+        self.trail_width = trail_width;
+    }
+}
+
+#[derive(Clone)]
+struct TrailLevelGeometries {
+    prepared: Rc<PreparedTrailLevel>,
     path: ID2D1Geometry,
     arrows: Vec<ID2D1Geometry>,
 }
 
-impl TrailGeometries {
-    unsafe fn build_path(d2d1_factory: &ID2D1Factory1, points: &[Point3]) -> ID2D1Geometry {
+impl TrailLevelGeometries {
+    /// Every segment becomes a figure of its own, so no line is drawn across the gaps.
+    unsafe fn build_path(d2d1_factory: &ID2D1Factory1, segments: &[Vec<Point3>]) -> ID2D1Geometry {
         let path = d2d1_factory
             .CreatePathGeometry()
             .log_expect("could not create path geometry");
 
         let sink = path.Open().log_expect("could not open path geometry");
 
-        sink.BeginFigure(
-            D2D_POINT_2F {
-                x: points[0].x,
-                y: points[0].y,
-            },
-            D2D1_FIGURE_BEGIN_HOLLOW,
-        );
+        for points in segments {
+            sink.BeginFigure(
+                D2D_POINT_2F {
+                    x: points[0].x,
+                    y: points[0].y,
+                },
+                D2D1_FIGURE_BEGIN_HOLLOW,
+            );
 
-        for point in points.iter().skip(1) {
-            sink.AddLine(D2D_POINT_2F {
-                x: point.x,
-                y: point.y,
-            });
-        }
+            for point in points.iter().skip(1) {
+                sink.AddLine(D2D_POINT_2F {
+                    x: point.x,
+                    y: point.y,
+                });
+            }
 
-        sink.EndFigure(D2D1_FIGURE_END_OPEN);
+            sink.EndFigure(D2D1_FIGURE_END_OPEN);
+        }
 
         sink.Close().log_expect("could not close path geometry");
 
@@ -251,55 +486,24 @@ impl TrailGeometries {
 
     unsafe fn build_arrows(
         d2d1_factory: &ID2D1Factory1,
-        points: &[Point3],
-        trail_width: TrailWidth,
+        base_arrow: &ID2D1Geometry,
+        placements: &[ArrowPlacement],
     ) -> Vec<ID2D1Geometry> {
-        let base_arrow = Self::build_arrow(d2d1_factory, trail_width);
-
-        let mut geometries = Vec::new();
-
-        let mut last_point = None;
-        let mut distance_to_next_arrow = 0.0;
-
-        for (idx, point) in points.iter().enumerate() {
-            // Subtract the distance between the last and the current point.
-            if let Some(last_point) = last_point.replace(point) {
-                let dist = distance(last_point, point);
-                distance_to_next_arrow -= dist;
-            }
-
-            if distance_to_next_arrow <= 0.0 {
-                distance_to_next_arrow = DISTANCE_BETWEEN_ARROWS;
-
-                // Only draw an arrow if there is another point left.
-                let Some(next_point) = points.get(idx + 1) else {
-                    continue;
-                };
-
-                let rotation = Matrix3x2::rotation(
-                    {
-                        let delta_x = next_point.x - point.x;
-                        let delta_y = next_point.y - point.y;
-
-                        let radians = delta_y.atan2(delta_x);
-                        let degrees = radians * 180.0 / f32::consts::PI;
-
-                        degrees
-                    },
-                    0.0,
-                    0.0,
-                );
-                let translation = Matrix3x2::translation(point.x, point.y);
+        placements
+            .iter()
+            .map(|placement| {
+                let rotation =
+                    Matrix3x2::rotation(placement.direction * 180.0 / f32::consts::PI, 0.0, 0.0);
+                let translation =
+                    Matrix3x2::translation(placement.position.x, placement.position.y);
 
                 let arrow = d2d1_factory
-                    .CreateTransformedGeometry(&base_arrow, &(rotation * translation))
+                    .CreateTransformedGeometry(base_arrow, &(rotation * translation))
                     .log_expect("could not transform arrow geometry");
 
-                geometries.push(arrow.cast().log_unwrap());
-            }
-        }
-
-        geometries
+                arrow.cast().log_unwrap()
+            })
+            .collect()
     }
 
     unsafe fn build_arrow(d2d1_factory: &ID2D1Factory1, trail_width: TrailWidth) -> ID2D1Geometry {
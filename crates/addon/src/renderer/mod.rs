@@ -5,13 +5,12 @@ mod world;
 
 use std::{cell::OnceCell, mem::MaybeUninit, rc::Rc, sync::Mutex};
 
-use api::{Mumble_EUIScale, Mumble_EUIScale_Large, Mumble_EUIScale_Larger, Mumble_EUIScale_Small};
-use egui::{Context, Event};
+use egui::{Context, RawInput};
 use log_err::{LogErrOption, LogErrResult};
 use paths_core::{
-    loadable::BackgroundLoadable,
-    markers::{ActiveMarkerCategories, MarkerCategoryTree},
-    settings::Settings,
+    maps::CompassLayout,
+    markers::ActiveMarkerCategories,
+    settings::{Settings, UiSize},
     ui::{UiActions, UiState},
 };
 use windows::Win32::Graphics::{
@@ -28,6 +27,7 @@ use windows::Win32::Graphics::{
 };
 
 use self::map::MapRenderer;
+pub use self::ui::UiFrameData;
 use self::ui::UiRenderer;
 use self::world::WorldRenderer;
 
@@ -207,25 +207,13 @@ impl<'a> Renderer<'a> {
     pub unsafe fn render_ui<A: UiActions>(
         &mut self,
         state: &mut UiState<A>,
-        events: Vec<Event>,
-
-        mumble_data: &api::Mumble_Data,
-        nexus_link_data: &api::NexusLinkData,
-        tree: &BackgroundLoadable<MarkerCategoryTree>,
+        input: RawInput,
+        frame: &UiFrameData,
         settings: &mut Settings,
-        active_marker_categories: &ActiveMarkerCategories,
     ) {
         self.init_d3d11_render_target();
 
-        self.ui_renderer.render(
-            state,
-            events,
-            mumble_data,
-            nexus_link_data,
-            tree,
-            settings,
-            active_marker_categories,
-        );
+        self.ui_renderer.render(state, input, frame, settings);
     }
 }
 
@@ -234,7 +222,7 @@ pub struct RenderConfig {
     pub screen_height: f32,
     pub half_screen_width: f32,
     pub half_screen_height: f32,
-    pub ui_scale_factor: f32,
+    pub ui_size: UiSize,
 }
 
 impl RenderConfig {
@@ -244,7 +232,7 @@ impl RenderConfig {
             screen_height,
             half_screen_width: screen_width / 2.0,
             half_screen_height: screen_height / 2.0,
-            ui_scale_factor: 1.0,
+            ui_size: UiSize::Normal,
         }
     }
 
@@ -255,15 +243,22 @@ impl RenderConfig {
         self.half_screen_height = height / 2.0;
     }
 
-    pub fn update_ui_size(&mut self, ui_size: Mumble_EUIScale) {
-        self.ui_scale_factor = if ui_size == Mumble_EUIScale_Small {
-            0.9
-        } else if ui_size == Mumble_EUIScale_Large {
-            1.11
-        } else if ui_size == Mumble_EUIScale_Larger {
-            1.22
-        } else {
-            1.0
-        };
+    pub fn update_ui_size(&mut self, ui_size: UiSize) {
+        self.ui_size = ui_size;
+    }
+
+    pub fn ui_scale_factor(&self) -> f32 {
+        self.ui_size.scale_factor()
+    }
+
+    pub fn compass_layout(&self, mumble_context: &api::Mumble_Context) -> CompassLayout {
+        CompassLayout {
+            screen_width: self.screen_width,
+            screen_height: self.screen_height,
+            compass_width: mumble_context.Compass.Width as f32,
+            compass_height: mumble_context.Compass.Height as f32,
+            is_top_right: mumble_context.IsCompassTopRight() > 0,
+            ui_size: self.ui_size,
+        }
     }
 }
@@ -1,19 +1,30 @@
 use std::{cell::OnceCell, rc::Rc, sync::Mutex};
 
-use egui::{Context, Event, Pos2, RawInput, Rect, Vec2};
+use egui::{Context, Pos2, RawInput, Rect, Vec2};
 use log_err::{LogErrOption, LogErrResult};
 use paths_core::{
     loadable::BackgroundLoadable,
     markers::{ActiveMarkerCategories, MarkerCategoryTree},
-    settings::Settings,
-    ui::{UiActions, UiState},
+    settings::{PlayerContext, Settings},
+    ui::{UiActions, UiFrame, UiState},
 };
 use windows::Win32::Graphics::Direct3D11::{
     ID3D11Device, ID3D11DeviceContext, ID3D11RenderTargetView,
 };
 
+use crate::clipboard::set_clipboard_text;
+
 use super::RenderConfig;
 
+/// The state of the game and the addon that the UI shows in a frame.
+pub struct UiFrameData<'a> {
+    pub mumble_data: &'a api::Mumble_Data,
+    pub nexus_link_data: &'a api::NexusLinkData,
+    pub tree: &'a BackgroundLoadable<MarkerCategoryTree>,
+    pub active_marker_categories: &'a ActiveMarkerCategories<'a>,
+    pub player: Option<&'a PlayerContext>,
+}
+
 pub struct UiRenderer {
     config: Rc<Mutex<RenderConfig>>,
     context: Context,
@@ -47,23 +58,29 @@ impl UiRenderer {
     pub fn render<A: UiActions>(
         &mut self,
         state: &mut UiState<A>,
-        events: Vec<Event>,
-
-        mumble_data: &api::Mumble_Data,
-        nexus_link_data: &api::NexusLinkData,
-        tree: &BackgroundLoadable<MarkerCategoryTree>,
+        input: RawInput,
+        frame: &UiFrameData,
         settings: &mut Settings,
-        active_marker_categories: &ActiveMarkerCategories,
     ) {
-        let (screen_width, screen_height) = {
+        let UiFrameData {
+            mumble_data,
+            nexus_link_data,
+            tree,
+            active_marker_categories,
+            player,
+        } = *frame;
+
+        let (screen_width, screen_height, compass) = {
             let config = self.config.lock().log_unwrap();
 
-            (config.screen_width, config.screen_height)
+            // The compass is hidden while the map is open.
+            let compass = (nexus_link_data.IsGameplay && mumble_data.Context.IsMapOpen() == 0)
+                .then(|| config.compass_layout(&mumble_data.Context));
+
+            (config.screen_width, config.screen_height, compass)
         };
 
         let input = RawInput {
-            events,
-
             focused: mumble_data.Context.IsGameFocused() > 0,
 
             screen_rect: Some(Rect::from_min_size(
@@ -74,21 +91,23 @@ impl UiRenderer {
             // TODO: Is this needed?
             time: None,
 
-            ..Default::default()
+            ..input
         };
 
         let output = self.context.run(input, |ctx| {
-            state.render(
-                screen_width,
-                screen_height,
-                ctx,
+            let frame = UiFrame {
                 tree,
-                nexus_link_data.IsGameplay,
-                settings,
+                is_in_gameplay: nexus_link_data.IsGameplay,
                 active_marker_categories,
-            );
+                player,
+                compass: compass.as_ref(),
+            };
+
+            state.render(ctx, &frame, settings);
         });
 
+        let (renderer_output, platform_output, _) = egui_directx11::split_output(output);
+
         self.egui_renderer
             .render(
                 &self.d3d11_device_context,
@@ -96,9 +115,13 @@ impl UiRenderer {
                     .get()
                     .log_expect("did not initialize render target view"),
                 &self.context,
-                egui_directx11::split_output(output).0,
+                renderer_output,
                 1.0,
             )
             .log_expect("could not render ui");
+
+        if !platform_output.copied_text.is_empty() {
+            set_clipboard_text(&platform_output.copied_text);
+        }
     }
 }
@@ -1,10 +1,10 @@
-use std::{mem::MaybeUninit, path::PathBuf};
+use std::{mem::MaybeUninit, path::PathBuf, sync::atomic::AtomicBool};
 
 use debounce::EventDebouncer;
 use paths_core::{
     loadable::BackgroundLoadable,
-    markers::{ActiveMarkerCategories, MarkerCategoryTree},
-    settings::Settings,
+    markers::{ActiveMarkerCategories, MarkerCategoryHistory, MarkerCategoryTree},
+    settings::{MarkerPresetRuleTracker, Settings},
     ui::UiState,
 };
 
@@ -17,9 +17,17 @@ pub static mut ACTIVE_MARKER_CATEGORIES: MaybeUninit<ActiveMarkerCategories> =
 
 pub static mut API: MaybeUninit<api::AddonApiWrapper> = MaybeUninit::uninit();
 
+pub static mut MARKER_CATEGORY_HISTORY: MaybeUninit<MarkerCategoryHistory> = MaybeUninit::uninit();
+
 pub static mut MARKER_CATEGORY_TREE: MaybeUninit<BackgroundLoadable<MarkerCategoryTree>> =
     MaybeUninit::new(BackgroundLoadable::Loading);
 
+pub static mut MARKER_PRESET_RULE_TRACKER: MaybeUninit<MarkerPresetRuleTracker> =
+    MaybeUninit::uninit();
+
+/// Set by the loading thread, so the rules are evaluated again on the game thread.
+pub static MARKER_PRESET_RULES_OUTDATED: AtomicBool = AtomicBool::new(false);
+
 pub static mut MUMBLE_DATA: MaybeUninit<&api::Mumble_Data> = MaybeUninit::uninit();
 
 pub static mut MUMBLE_IDENTITY: Option<&api::Mumble_Identity> = None;
@@ -32,6 +40,9 @@ pub static mut SETTINGS_FILE_PATH: MaybeUninit<PathBuf> = MaybeUninit::uninit();
 
 pub static mut SETTINGS_SAVER: MaybeUninit<EventDebouncer<()>> = MaybeUninit::uninit();
 
+/// Set if the settings file could neither be read nor moved aside, so saving would overwrite it.
+pub static SETTINGS_READ_ONLY: AtomicBool = AtomicBool::new(false);
+
 pub static mut SETTINGS: MaybeUninit<Settings> = MaybeUninit::uninit();
 
 pub static mut UI_INPUT_MANAGER: MaybeUninit<InputManager> = MaybeUninit::uninit();
@@ -1,17 +1,33 @@
-use std::{fs::read_to_string, thread};
+use std::{
+    ffi::{CStr, CString},
+    fs::read,
+    io::{self, ErrorKind},
+    path::Path,
+    sync::atomic::Ordering,
+    thread,
+};
 
+use log::{debug, warn};
 use log_err::{LogErrOption, LogErrResult};
 use paths_core::{
     loadable::BackgroundLoadable,
+    localization::{set_current_language, tr, Language},
     markers::{MarkerCategoryTree, NodeId},
-    settings::{apply_marker_category_settings, backup_marker_category_settings, read_settings},
+    settings::{
+        apply_marker_category_settings, backup_marker_category_settings,
+        backup_unreadable_settings_file, marker_pack_dirs, read_settings, switch_marker_preset,
+        MapCategory, PlayerContext, Profession, Settings, UiSize,
+    },
     ui::UiActions,
 };
 
+use crate::{clipboard::get_clipboard_text, renderer::UiFrameData};
+
 use super::globals::{
-    ACTIVE_MARKER_CATEGORIES, API, MARKER_CATEGORY_TREE, MUMBLE_DATA, MUMBLE_IDENTITY,
-    NEXUS_LINK_DATA, RENDERER, SETTINGS, SETTINGS_FILE_PATH, SETTINGS_SAVER, UI_INPUT_MANAGER,
-    UI_STATE,
+    ACTIVE_MARKER_CATEGORIES, API, MARKER_CATEGORY_HISTORY, MARKER_CATEGORY_TREE,
+    MARKER_PRESET_RULES_OUTDATED, MARKER_PRESET_RULE_TRACKER, MUMBLE_DATA, MUMBLE_IDENTITY,
+    NEXUS_LINK_DATA, RENDERER, SETTINGS, SETTINGS_FILE_PATH, SETTINGS_READ_ONLY, SETTINGS_SAVER,
+    UI_INPUT_MANAGER, UI_STATE,
 };
 
 pub unsafe fn handle_wnd_proc(msg: api::UINT, w_param: api::WPARAM, l_param: api::LPARAM) -> u32 {
@@ -25,31 +41,145 @@ pub unsafe fn handle_wnd_proc(msg: api::UINT, w_param: api::WPARAM, l_param: api
 pub unsafe fn load_settings_in_background() {
     *MARKER_CATEGORY_TREE.assume_init_mut() = BackgroundLoadable::Loading;
 
+    UI_STATE.assume_init_mut().main_window.settings_error = None;
+
     thread::Builder::new()
         .name("load_in_background".to_owned())
         .spawn(move || {
-            let settings_json = read_to_string(SETTINGS_FILE_PATH.assume_init_ref())
-                .log_expect("could not open settings file for reading");
+            SETTINGS.write(read_settings_file(SETTINGS_FILE_PATH.assume_init_ref()));
+
+            load_marker_packs();
+        })
+        .log_unwrap();
+}
+
+/// Reloads the packs with the current settings, e.g. after the pack directories were changed.
+unsafe fn load_marker_packs_in_background() {
+    *MARKER_CATEGORY_TREE.assume_init_mut() = BackgroundLoadable::Loading;
+
+    thread::Builder::new()
+        .name("load_in_background".to_owned())
+        .spawn(|| load_marker_packs())
+        .log_unwrap();
+}
+
+unsafe fn load_marker_packs() {
+    let settings = SETTINGS.assume_init_ref();
+
+    let tree = MarkerCategoryTree::from_all_packs_in_dirs(&marker_pack_dirs(
+        settings,
+        &API.assume_init_ref().get_path_in_addon_directory("markers"),
+    ));
+
+    apply_marker_category_settings(settings, &tree);
+
+    let BackgroundLoadable::Loaded(ref tree) =
+        MARKER_CATEGORY_TREE.write(BackgroundLoadable::Loaded(tree))
+    else {
+        return;
+    };
 
-            let settings = SETTINGS.write(read_settings(settings_json.as_bytes()));
+    ACTIVE_MARKER_CATEGORIES
+        .assume_init_mut()
+        .read_from_tree(&tree);
+
+    // The rules have to be evaluated again for the freshly read settings. This changes the settings and the
+    // tree, so it has to happen on the game thread.
+    MARKER_PRESET_RULES_OUTDATED.store(true, Ordering::Release);
+}
+
+/// Falls back to the default settings if the file cannot be read. An unreadable file is kept as a backup. If
+/// that is not possible either, the settings are not saved until they are read successfully, since the
+/// defaults would overwrite the file.
+unsafe fn read_settings_file(file_path: &Path) -> Settings {
+    SETTINGS_READ_ONLY.store(false, Ordering::Release);
+
+    let bytes = match read(file_path) {
+        Ok(bytes) => bytes,
+
+        Err(err) if err.kind() == ErrorKind::NotFound => return Settings::default(),
+
+        Err(err) => {
+            SETTINGS_READ_ONLY.store(true, Ordering::Release);
+
+            report_settings_error(format!(
+                "{} {}",
+                tr("Could not open the settings file: {err}").replace("{err}", &err.to_string()),
+                tr("Changes are not saved until the settings are reloaded."),
+            ));
 
-            let mut tree = MarkerCategoryTree::from_all_packs_in_dir(
-                &API.assume_init_ref().get_path_in_addon_directory("markers"),
-            );
+            return Settings::default();
+        }
+    };
+
+    match read_settings(&bytes) {
+        Ok(settings) => settings,
+
+        Err(err) => {
+            let message = match backup_unreadable_settings_file(file_path) {
+                Ok(backup_file_path) => {
+                    tr("Could not read the settings ({err}). The old file was kept as {path}.")
+                        .replace("{err}", &err.to_string())
+                        .replace("{path}", &backup_file_path.display().to_string())
+                }
 
-            apply_marker_category_settings(settings, &mut tree);
+                Err(backup_err) => {
+                    SETTINGS_READ_ONLY.store(true, Ordering::Release);
 
-            let BackgroundLoadable::Loaded(ref tree) =
-                MARKER_CATEGORY_TREE.write(BackgroundLoadable::Loaded(tree))
-            else {
-                return;
+                    format!(
+                        "{} {}",
+                        tr("Could not read the settings ({err}) and could not keep a backup of them ({backup_err}).")
+                            .replace("{err}", &err.to_string())
+                            .replace("{backup_err}", &backup_err.to_string()),
+                        tr("Changes are not saved until the settings are reloaded."),
+                    )
+                }
             };
 
-            ACTIVE_MARKER_CATEGORIES
-                .assume_init_mut()
-                .read_from_tree(&tree);
+            report_settings_error(message);
+
+            Settings::default()
+        }
+    }
+}
+
+pub(super) unsafe fn report_settings_error(message: String) {
+    warn!("{message}");
+
+    UI_STATE.assume_init_mut().main_window.settings_error = Some(message);
+}
+
+pub(super) unsafe fn report_settings_write_error(err: io::Error) {
+    report_settings_error(
+        tr("Could not save the settings: {err}").replace("{err}", &err.to_string()),
+    );
+}
+
+/// Nexus translates this identifier to the code of its active language, which is usually the one of the game.
+const LANGUAGE_CODE_ID: &CStr = c"PATHS_LANGUAGE_CODE";
+
+pub unsafe fn register_language_codes() {
+    let api = API.assume_init_ref();
+
+    for language in Language::ALL {
+        let code = CString::new(language.code()).log_unwrap();
+
+        api.set_translation(LANGUAGE_CODE_ID, &code, &code);
+    }
+}
+
+unsafe fn update_language() {
+    let language = SETTINGS
+        .assume_init_ref()
+        .language
+        .or_else(|| {
+            API.assume_init_ref()
+                .translate(LANGUAGE_CODE_ID)
+                .and_then(|code| Language::from_code(&code))
         })
-        .log_unwrap();
+        .unwrap_or(Language::English);
+
+    set_current_language(language);
 }
 
 pub unsafe fn render() {
@@ -58,22 +188,38 @@ pub unsafe fn render() {
     let mumble_data = MUMBLE_DATA.assume_init_ref();
     let nexus_link_data = NEXUS_LINK_DATA.assume_init_ref();
 
+    update_language();
+
+    if MARKER_PRESET_RULES_OUTDATED.swap(false, Ordering::Acquire) {
+        MARKER_PRESET_RULE_TRACKER.assume_init_mut().reset();
+        apply_marker_preset_rules();
+    }
+
     // This is a stupid hack. It seems that some objects of the directx11 renderer are not initialized on the
     // start of rendering but only later on. With this condition the first render is deferred until the user
     // clicks the menu button. Apparently, this is enough to allow the initialization. Probably, this is
     // related to the bug that kills the UI on resizing the game window.
     if ui_state.ui_was_displayed_once {
-        renderer.render_ui(
-            ui_state,
-            UI_INPUT_MANAGER.assume_init_mut().get_events(),
+        let player = get_player_context();
+
+        let frame = UiFrameData {
             mumble_data,
             nexus_link_data,
-            MARKER_CATEGORY_TREE.assume_init_ref(),
+            tree: MARKER_CATEGORY_TREE.assume_init_ref(),
+            active_marker_categories: ACTIVE_MARKER_CATEGORIES.assume_init_ref(),
+            player: player.as_ref(),
+        };
+
+        renderer.render_ui(
+            ui_state,
+            UI_INPUT_MANAGER.assume_init_mut().take_input(),
+            &frame,
             SETTINGS.assume_init_mut(),
-            ACTIVE_MARKER_CATEGORIES.assume_init_ref(),
         );
     }
 
+    observe_marker_category_changes();
+
     if nexus_link_data.IsGameplay {
         if mumble_data.Context.IsMapOpen() == 0 {
             // renderer.render_world();
@@ -87,6 +233,28 @@ pub unsafe fn render() {
     }
 }
 
+/// Records the changes of the categories for undo and redo. A drag of a slider or color picker is recorded
+/// as a single change once it ends.
+unsafe fn observe_marker_category_changes() {
+    let history = MARKER_CATEGORY_HISTORY.assume_init_mut();
+
+    let BackgroundLoadable::Loaded(tree) = MARKER_CATEGORY_TREE.assume_init_ref() else {
+        history.clear();
+
+        return;
+    };
+
+    if UI_INPUT_MANAGER.assume_init_ref().is_pointer_down() {
+        return;
+    }
+
+    history.observe(
+        tree,
+        &SETTINGS.assume_init_ref().active_marker_preset,
+        ACTIVE_MARKER_CATEGORIES.assume_init_ref().generation(),
+    );
+}
+
 pub unsafe fn toggle_ui_visible() {
     let ui_state = UI_STATE.assume_init_mut();
 
@@ -104,11 +272,114 @@ pub unsafe fn update_mumble_identity(identity: &'static api::Mumble_Identity) {
         .config
         .lock()
         .log_unwrap()
-        .update_ui_size(identity.UISize);
+        .update_ui_size(ui_size_from_mumble(identity.UISize));
 
     ACTIVE_MARKER_CATEGORIES
         .assume_init_mut()
         .set_current_map(identity.MapID);
+
+    apply_marker_preset_rules();
+}
+
+/// Switches to the preset selected by the rules (if they select another one than last time).
+unsafe fn apply_marker_preset_rules() {
+    let BackgroundLoadable::Loaded(tree) = MARKER_CATEGORY_TREE.assume_init_ref() else {
+        return;
+    };
+
+    let Some(player) = get_player_context() else {
+        return;
+    };
+
+    let settings = SETTINGS.assume_init_mut();
+
+    let Some(preset) = MARKER_PRESET_RULE_TRACKER
+        .assume_init_mut()
+        .update(settings, &player)
+    else {
+        return;
+    };
+
+    debug!("switching to marker preset {preset} because of the preset rules");
+
+    match switch_marker_preset(settings, tree, &preset) {
+        Ok(()) => {
+            AddonUiActions.update_active_marker_categories();
+            AddonUiActions.save_settings();
+        }
+
+        Err(err) => warn!("could not switch to marker preset {preset}: {err:?}"),
+    }
+}
+
+unsafe fn get_player_context() -> Option<PlayerContext> {
+    let identity = MUMBLE_IDENTITY?;
+    let mumble_data = MUMBLE_DATA.assume_init_ref();
+
+    let character_name = identity
+        .Name
+        .iter()
+        .map(|c| *c as u8)
+        .take_while(|c| *c != 0)
+        .collect::<Vec<_>>();
+
+    Some(PlayerContext {
+        character_name: String::from_utf8_lossy(&character_name).into_owned(),
+        profession: profession_from_mumble(identity.Profession),
+        map_id: identity.MapID,
+        map_category: map_category_from_mumble(mumble_data.Context.MapType),
+    })
+}
+
+fn profession_from_mumble(profession: api::Mumble_EProfession) -> Option<Profession> {
+    match profession {
+        api::Mumble_EProfession_Guardian => Some(Profession::Guardian),
+        api::Mumble_EProfession_Warrior => Some(Profession::Warrior),
+        api::Mumble_EProfession_Engineer => Some(Profession::Engineer),
+        api::Mumble_EProfession_Ranger => Some(Profession::Ranger),
+        api::Mumble_EProfession_Thief => Some(Profession::Thief),
+        api::Mumble_EProfession_Elementalist => Some(Profession::Elementalist),
+        api::Mumble_EProfession_Mesmer => Some(Profession::Mesmer),
+        api::Mumble_EProfession_Necromancer => Some(Profession::Necromancer),
+        api::Mumble_EProfession_Revenant => Some(Profession::Revenant),
+        _ => None,
+    }
+}
+
+fn ui_size_from_mumble(ui_size: api::Mumble_EUIScale) -> UiSize {
+    match ui_size {
+        api::Mumble_EUIScale_Small => UiSize::Small,
+        api::Mumble_EUIScale_Large => UiSize::Large,
+        api::Mumble_EUIScale_Larger => UiSize::Larger,
+        _ => UiSize::Normal,
+    }
+}
+
+fn map_category_from_mumble(map_type: api::Mumble_EMapType) -> Option<MapCategory> {
+    match map_type {
+        api::Mumble_EMapType_Public
+        | api::Mumble_EMapType_Public_Mini
+        | api::Mumble_EMapType_Tutorial => Some(MapCategory::Pve),
+
+        api::Mumble_EMapType_Instance => Some(MapCategory::Instance),
+
+        api::Mumble_EMapType_PvP
+        | api::Mumble_EMapType_GvG
+        | api::Mumble_EMapType_Tournament
+        | api::Mumble_EMapType_UserTournament
+        | api::Mumble_EMapType_BigBattle => Some(MapCategory::Pvp),
+
+        api::Mumble_EMapType_WvW_EternalBattlegrounds
+        | api::Mumble_EMapType_WvW_BlueBorderlands
+        | api::Mumble_EMapType_WvW_GreenBorderlands
+        | api::Mumble_EMapType_WvW_RedBorderlands
+        | api::Mumble_EMapType_WVW_FortunesVale
+        | api::Mumble_EMapType_WvW_ObsidianSanctum
+        | api::Mumble_EMapType_WvW_EdgeOfTheMists
+        | api::Mumble_EMapType_WvW_Lounge => Some(MapCategory::Wvw),
+
+        _ => None,
+    }
 }
 
 pub unsafe fn update_window_size() {
@@ -134,6 +405,12 @@ impl UiActions for AddonUiActions {
         }
     }
 
+    fn reload_marker_packs(&self) {
+        unsafe {
+            load_marker_packs_in_background();
+        }
+    }
+
     fn save_settings(&self) {
         unsafe {
             if let BackgroundLoadable::Loaded(tree) = MARKER_CATEGORY_TREE.assume_init_ref() {
@@ -153,12 +430,79 @@ impl UiActions for AddonUiActions {
         }
     }
 
+    fn undo(&self) {
+        unsafe {
+            if let BackgroundLoadable::Loaded(tree) = MARKER_CATEGORY_TREE.assume_init_ref() {
+                if MARKER_CATEGORY_HISTORY.assume_init_mut().undo(tree) {
+                    self.update_active_marker_categories();
+                    self.save_settings();
+                }
+            }
+        }
+    }
+
+    fn redo(&self) {
+        unsafe {
+            if let BackgroundLoadable::Loaded(tree) = MARKER_CATEGORY_TREE.assume_init_ref() {
+                if MARKER_CATEGORY_HISTORY.assume_init_mut().redo(tree) {
+                    self.update_active_marker_categories();
+                    self.save_settings();
+                }
+            }
+        }
+    }
+
+    fn can_undo(&self) -> bool {
+        unsafe { MARKER_CATEGORY_HISTORY.assume_init_ref().can_undo() }
+    }
+
+    fn can_redo(&self) -> bool {
+        unsafe { MARKER_CATEGORY_HISTORY.assume_init_ref().can_redo() }
+    }
+
+    fn apply_marker_preset_rules(&self) {
+        unsafe {
+            MARKER_PRESET_RULE_TRACKER.assume_init_mut().reset();
+            apply_marker_preset_rules();
+        }
+    }
+
     fn display_marker_tree_window(&self) {
         unsafe {
             UI_STATE.assume_init_mut().marker_tree_window.open = true;
         }
     }
 
+    fn display_marker_preset_rules_window(&self) {
+        unsafe {
+            UI_STATE.assume_init_mut().marker_preset_rules_window.open = true;
+        }
+    }
+
+    fn display_overlay_import_window(&self) {
+        unsafe {
+            UI_STATE.assume_init_mut().overlay_import_window.open = true;
+        }
+    }
+
+    fn display_pack_directories_window(&self) {
+        unsafe {
+            UI_STATE.assume_init_mut().pack_directories_window.open = true;
+        }
+    }
+
+    fn display_appearance_window(&self) {
+        unsafe {
+            UI_STATE.assume_init_mut().appearance_window.open = true;
+        }
+    }
+
+    fn display_compass_calibration_window(&self) {
+        unsafe {
+            UI_STATE.assume_init_mut().compass_calibration_window.open = true;
+        }
+    }
+
     fn display_category_properties_window(&self, node_id: NodeId) {
         unsafe {
             if let BackgroundLoadable::Loaded(tree) = MARKER_CATEGORY_TREE.assume_init_ref() {
@@ -171,4 +515,8 @@ impl UiActions for AddonUiActions {
             }
         }
     }
+
+    fn clipboard_text(&self) -> Option<String> {
+        get_clipboard_text()
+    }
 }
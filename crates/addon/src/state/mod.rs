@@ -1,13 +1,17 @@
 mod globals;
 mod logic;
 
-use std::{fs::File, rc::Rc, sync::Mutex, time::Duration};
+use std::{
+    rc::Rc,
+    sync::{atomic::Ordering, Mutex},
+    time::Duration,
+};
 
 use debounce::EventDebouncer;
-use log_err::{LogErrOption, LogErrResult};
+use log_err::LogErrOption;
 use paths_core::{
-    markers::ActiveMarkerCategories,
-    settings::{write_settings, Settings},
+    markers::{ActiveMarkerCategories, MarkerCategoryHistory},
+    settings::{write_settings_file, MarkerPresetRuleTracker, Settings},
     ui::{prepare_egui_context, UiState},
 };
 use windows::{core::Interface, Win32::Graphics::Dxgi::IDXGISwapChain};
@@ -18,9 +22,9 @@ use crate::{
 };
 
 use self::globals::{
-    ACTIVE_MARKER_CATEGORIES, API, MARKER_CATEGORY_TREE, MUMBLE_DATA, MUMBLE_IDENTITY,
-    NEXUS_LINK_DATA, RENDERER, SETTINGS, SETTINGS_FILE_PATH, SETTINGS_SAVER, UI_INPUT_MANAGER,
-    UI_STATE,
+    ACTIVE_MARKER_CATEGORIES, API, MARKER_CATEGORY_HISTORY, MARKER_CATEGORY_TREE,
+    MARKER_PRESET_RULE_TRACKER, MUMBLE_DATA, MUMBLE_IDENTITY, NEXUS_LINK_DATA, RENDERER, SETTINGS,
+    SETTINGS_FILE_PATH, SETTINGS_READ_ONLY, SETTINGS_SAVER, UI_INPUT_MANAGER, UI_STATE,
 };
 pub use self::logic::*;
 
@@ -60,19 +64,31 @@ pub unsafe fn init_globals(api: &'static api::AddonAPI) -> &mut api::AddonApiWra
 
     ACTIVE_MARKER_CATEGORIES.write(ActiveMarkerCategories::new());
 
+    MARKER_PRESET_RULE_TRACKER.write(MarkerPresetRuleTracker::default());
+
+    MARKER_CATEGORY_HISTORY.write(MarkerCategoryHistory::default());
+
     {
         SETTINGS_FILE_PATH.write(api.get_path_in_addon_directory("settings.json"));
 
         SETTINGS.write(Settings::default());
 
         SETTINGS_SAVER.write(EventDebouncer::new(Duration::from_secs(1), |_| {
-            let mut file = File::create(SETTINGS_FILE_PATH.assume_init_ref())
-                .log_expect("could not open settings file for writing");
-
-            write_settings(&mut file, SETTINGS.assume_init_ref());
+            if SETTINGS_READ_ONLY.load(Ordering::Acquire) {
+                return;
+            }
+
+            if let Err(err) = write_settings_file(
+                SETTINGS_FILE_PATH.assume_init_ref(),
+                SETTINGS.assume_init_ref(),
+            ) {
+                report_settings_write_error(err);
+            }
         }));
     }
 
+    register_language_codes();
+
     api_wrapper
 }
 
@@ -83,6 +99,10 @@ pub unsafe fn uninit_globals() {
 
     SETTINGS_FILE_PATH.assume_init_drop();
 
+    MARKER_CATEGORY_HISTORY.assume_init_drop();
+
+    MARKER_PRESET_RULE_TRACKER.assume_init_drop();
+
     ACTIVE_MARKER_CATEGORIES.assume_init_drop();
 
     MARKER_CATEGORY_TREE.assume_init_drop();
@@ -35,6 +35,28 @@ impl AddonAPI {
         }
     }
 
+    /// Returns `None` if there is no text for the identifier in the active language of Nexus.
+    pub fn translate(&self, id: &CStr) -> Option<String> {
+        unsafe {
+            let text = self.Localization.Translate.log_unwrap()(id.as_ptr());
+
+            if text.is_null() {
+                return None;
+            }
+
+            let text = CStr::from_ptr(text);
+
+            // Nexus returns the identifier itself if there is no translation.
+            (text != id).then(|| text.to_string_lossy().into_owned())
+        }
+    }
+
+    pub fn set_translation(&self, id: &CStr, language: &CStr, text: &CStr) {
+        unsafe {
+            self.Localization.Set.log_unwrap()(id.as_ptr(), language.as_ptr(), text.as_ptr());
+        }
+    }
+
     pub fn get_path_in_addon_directory(&self, path: &str) -> PathBuf {
         unsafe {
             let path =
@@ -1,56 +1,300 @@
 use std::{
-    collections::BTreeMap,
-    fs::File,
-    io::{BufReader, Write},
+    collections::{BTreeMap, BTreeSet},
+    env,
+    fs::{read, remove_file, rename, File},
+    io::{self, BufReader, BufWriter, Write},
     path::{Path, PathBuf},
+    process::ExitCode,
     thread,
     time::Duration,
 };
 
 use paths_core::maps::{
-    fetch_map_dimensions, fetch_maps_index, FetchError, FetchResult, MapDimensions,
+    fetch_map_dimensions, fetch_maps_index, map_dimensions_from_dump, FetchError, FetchResult,
+    MapDimensions, GW2_MAPS_BASE_URL,
 };
+use serde::Serialize;
+use serde_json::ser::Formatter;
+
+const USAGE: &str = "\
+Usage: fetch-map-dimensions [OPTIONS]
+
+Options:
+  --only-missing      Only fetch maps that are not yet in map-dimensions.json
+  --ids <IDS>         Only fetch the given comma separated map ids
+  --base-url <URL>    Use another maps endpoint (default: https://api.guildwars2.com/v2/maps)
+  --dry-run           Print the changes instead of writing map-dimensions.json
+  --from-dump <FILE>  Read the maps from a saved response of `/v2/maps?ids=all` instead of fetching them
+  --help              Print this help";
+
+struct Options {
+    only_missing: bool,
+    ids: Option<Vec<u32>>,
+    base_url: String,
+    dry_run: bool,
+    from_dump: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    let options = match parse_options(env::args().skip(1)) {
+        Ok(Some(options)) => options,
+
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
 
-fn main() {
     let target_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("map-dimensions.json");
 
-    let mut existing = load_existing_data(&target_file_path);
+    let existing = load_existing_data(&target_file_path);
+
+    let updated = match &options.from_dump {
+        Some(dump_path) => load_from_dump(dump_path, &options, &existing),
+        None => fetch_from_api(&options, &existing),
+    };
+
+    let Some(updated) = updated else {
+        return ExitCode::FAILURE;
+    };
+
+    let changes = Changes::between(&existing, &updated);
+
+    if options.dry_run {
+        changes.print();
+        return ExitCode::SUCCESS;
+    }
+
+    eprintln!("{}", changes.summary());
+
+    if let Err(err) = write_data(&target_file_path, &updated) {
+        eprintln!("Could not write map dimensions: {err}");
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn parse_options<I: Iterator<Item = String>>(mut args: I) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        only_missing: false,
+        ids: None,
+        base_url: GW2_MAPS_BASE_URL.to_owned(),
+        dry_run: false,
+        from_dump: None,
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--only-missing" => options.only_missing = true,
+
+            "--ids" => {
+                let ids = args.next().ok_or("--ids needs a value")?;
+
+                options.ids = Some(
+                    ids.split(',')
+                        .map(|id| {
+                            id.trim()
+                                .parse()
+                                .map_err(|err| format!("invalid map id {id:?}: {err}"))
+                        })
+                        .collect::<Result<_, _>>()?,
+                );
+            }
+
+            "--base-url" => {
+                options.base_url = args.next().ok_or("--base-url needs a value")?;
+            }
+
+            "--dry-run" => options.dry_run = true,
 
-    let map_ids = fetch_maps_index().expect("could not load maps");
+            "--from-dump" => {
+                options.from_dump = Some(args.next().ok_or("--from-dump needs a value")?.into());
+            }
+
+            "--help" | "-h" => return Ok(None),
+
+            _ => return Err(format!("unknown argument: {arg}")),
+        }
+    }
+
+    Ok(Some(options))
+}
+
+/// Whether the given selection covers all maps. Only then maps that are not reported anymore
+/// are removed.
+fn selects_all_maps(options: &Options) -> bool {
+    !options.only_missing && options.ids.is_none()
+}
+
+fn is_selected(options: &Options, existing: &BTreeMap<u32, MapDimensions>, map_id: u32) -> bool {
+    if options.only_missing && existing.contains_key(&map_id) {
+        return false;
+    }
+
+    options
+        .ids
+        .as_ref()
+        .map_or(true, |ids| ids.contains(&map_id))
+}
+
+fn load_from_dump(
+    dump_path: &Path,
+    options: &Options,
+    existing: &BTreeMap<u32, MapDimensions>,
+) -> Option<BTreeMap<u32, MapDimensions>> {
+    let dimensions = match read(dump_path)
+        .map_err(|err| err.to_string())
+        .and_then(|bytes| map_dimensions_from_dump(&bytes).map_err(|err| err.to_string()))
+    {
+        Ok(dimensions) => dimensions,
+
+        Err(err) => {
+            eprintln!("Could not read dump {}: {err}", dump_path.display());
+            return None;
+        }
+    };
+
+    let dimensions = dimensions
+        .into_iter()
+        .filter(|dim| is_selected(options, existing, dim.map_id));
+
+    Some(merge(existing, dimensions, selects_all_maps(options)))
+}
+
+fn fetch_from_api(
+    options: &Options,
+    existing: &BTreeMap<u32, MapDimensions>,
+) -> Option<BTreeMap<u32, MapDimensions>> {
+    let map_ids = match &options.ids {
+        Some(ids) => ids.clone(),
+
+        None => match fetch_maps_index(&options.base_url) {
+            Ok(map_ids) => map_ids,
+
+            Err(err) => {
+                eprintln!("Could not load maps: {err}");
+                return None;
+            }
+        },
+    };
+
+    let map_ids = map_ids
+        .into_iter()
+        .filter(|map_id| is_selected(options, existing, *map_id))
+        .collect::<Vec<_>>();
+
+    let mut fetched = vec![];
+    let mut failed_chunks = 0;
 
     for map_ids in map_ids.chunks(30) {
-        match fetch_map_dimensions_with_retry(map_ids, 3) {
+        match fetch_map_dimensions_with_retry(&options.base_url, map_ids, 3) {
             Ok(dimensions) => {
-                for dim in dimensions {
-                    existing.insert(dim.map_id, dim);
-                }
+                fetched.extend(dimensions);
 
-                println!("Fetched map dimensions {:?}", map_ids);
+                eprintln!("Fetched map dimensions {:?}", map_ids);
             }
 
-            Err(err) => eprintln!("Could not load maps {:?}: {err}", map_ids),
+            Err(err) => {
+                eprintln!("Could not load maps {:?}: {err}", map_ids);
+
+                failed_chunks += 1;
+            }
         }
     }
 
-    let json = existing
-        .iter()
-        .filter_map(
-            |(map_id, dimensions)| match serde_json::to_string(dimensions) {
-                Ok(json) => Some(format!(r#""{map_id}":{json}"#)),
+    if failed_chunks > 0 {
+        eprintln!("Could not load {failed_chunks} chunk(s) of maps, not writing any changes");
+        return None;
+    }
+
+    Some(merge(existing, fetched, selects_all_maps(options)))
+}
+
+fn merge<I: IntoIterator<Item = MapDimensions>>(
+    existing: &BTreeMap<u32, MapDimensions>,
+    dimensions: I,
+    replace_all: bool,
+) -> BTreeMap<u32, MapDimensions> {
+    let mut result = if replace_all {
+        BTreeMap::new()
+    } else {
+        existing.clone()
+    };
+
+    for dim in dimensions {
+        result.insert(dim.map_id, dim);
+    }
+
+    result
+}
+
+struct Changes<'a> {
+    added: Vec<(u32, &'a MapDimensions)>,
+    changed: Vec<(u32, &'a MapDimensions, &'a MapDimensions)>,
+    removed: Vec<(u32, &'a MapDimensions)>,
+}
+
+impl<'a> Changes<'a> {
+    fn between(
+        existing: &'a BTreeMap<u32, MapDimensions>,
+        updated: &'a BTreeMap<u32, MapDimensions>,
+    ) -> Self {
+        let mut changes = Self {
+            added: vec![],
+            changed: vec![],
+            removed: vec![],
+        };
+
+        let all_map_ids = existing
+            .keys()
+            .chain(updated.keys())
+            .collect::<BTreeSet<_>>();
+
+        for map_id in all_map_ids {
+            match (existing.get(map_id), updated.get(map_id)) {
+                (None, Some(new)) => changes.added.push((*map_id, new)),
+                (Some(old), None) => changes.removed.push((*map_id, old)),
+                (Some(old), Some(new)) if old != new => changes.changed.push((*map_id, old, new)),
+                _ => {}
+            }
+        }
 
-                Err(err) => {
-                    eprintln!("Could not convert map dimensions {map_id} to json: {err}");
-                    None
-                }
-            },
+        changes
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "{} added, {} changed, {} removed",
+            self.added.len(),
+            self.changed.len(),
+            self.removed.len(),
         )
-        .collect::<Vec<_>>()
-        .join("\n,");
+    }
 
-    let target_file =
-        File::create(target_file_path).expect("could not open map dimensions file for writing");
+    fn print(&self) {
+        let to_json = |dim: &MapDimensions| serde_json::to_string(dim).unwrap();
+
+        for (map_id, dim) in &self.added {
+            println!("+ {map_id}: {}", to_json(dim));
+        }
+
+        for (map_id, old, new) in &self.changed {
+            println!("- {map_id}: {}", to_json(old));
+            println!("+ {map_id}: {}", to_json(new));
+        }
+
+        for (map_id, dim) in &self.removed {
+            println!("- {map_id}: {}", to_json(dim));
+        }
 
-    writeln!(&target_file, "{{{json}\n}}").expect("could not write json to file");
+        println!("{}", self.summary());
+    }
 }
 
 fn load_existing_data(file_path: &Path) -> BTreeMap<u32, MapDimensions> {
@@ -58,14 +302,84 @@ fn load_existing_data(file_path: &Path) -> BTreeMap<u32, MapDimensions> {
         .ok()
         .map(|file| BufReader::new(file))
         .and_then(|reader| serde_json::from_reader(reader).ok())
+        .map(|data: BTreeMap<u32, MapDimensions>| {
+            // The map id is not part of the json value.
+            data.into_iter()
+                .map(|(map_id, dim)| (map_id, MapDimensions { map_id, ..dim }))
+                .collect()
+        })
         .unwrap_or_default()
 }
 
+/// Writes to a temporary file first, so an interrupted run does not leave a truncated `map-dimensions.json`
+/// that breaks the build.
+fn write_data(file_path: &Path, data: &BTreeMap<u32, MapDimensions>) -> io::Result<()> {
+    let mut temp_file_path = file_path.as_os_str().to_owned();
+    temp_file_path.push(".tmp");
+    let temp_file_path = PathBuf::from(temp_file_path);
+
+    let mut writer = BufWriter::new(File::create(&temp_file_path)?);
+
+    let result = (|| {
+        let mut serializer = serde_json::Serializer::with_formatter(&mut writer, OneMapPerLine(0));
+        data.serialize(&mut serializer)?;
+
+        writeln!(writer)?;
+        writer.flush()?;
+        writer.get_ref().sync_all()
+    })();
+
+    drop(writer);
+
+    if let Err(err) = result {
+        let _ = remove_file(&temp_file_path);
+
+        return Err(err);
+    }
+
+    rename(&temp_file_path, file_path)
+}
+
+/// Keeps the format of `map-dimensions.json`: compact json with one map per line. This keeps the diffs
+/// readable.
+struct OneMapPerLine(usize);
+
+impl Formatter for OneMapPerLine {
+    fn begin_object<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0 += 1;
+
+        writer.write_all(b"{")
+    }
+
+    fn end_object<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0 -= 1;
+
+        if self.0 == 0 {
+            writer.write_all(b"\n}")
+        } else {
+            writer.write_all(b"}")
+        }
+    }
+
+    fn begin_object_key<W: ?Sized + Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        match (self.0, first) {
+            (_, true) => Ok(()),
+            (1, false) => writer.write_all(b"\n,"),
+            (_, false) => writer.write_all(b","),
+        }
+    }
+}
+
 fn fetch_map_dimensions_with_retry(
+    base_url: &str,
     map_ids: &[u32],
     attempts_left: u8,
 ) -> FetchResult<Vec<MapDimensions>> {
-    let result = fetch_map_dimensions(map_ids);
+    let result = fetch_map_dimensions(base_url, map_ids);
 
     if let Err(FetchError::NonOkStatus {
         status_code: 429, ..
@@ -75,9 +389,162 @@ fn fetch_map_dimensions_with_retry(
             // Wait 5 seconds.
             thread::sleep(Duration::from_secs(5));
 
-            return fetch_map_dimensions_with_retry(map_ids, attempts_left - 1);
+            return fetch_map_dimensions_with_retry(base_url, map_ids, attempts_left - 1);
         }
     }
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use paths_core::maps::MapRect;
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Options>, String> {
+        parse_options(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn dimensions(map_id: u32, width: f32) -> MapDimensions {
+        let rect = MapRect {
+            top_left: [0.0, 0.0],
+            width,
+            height: 10.0,
+        };
+
+        MapDimensions {
+            map_id,
+            continent_rect: rect.clone(),
+            map_rect: rect,
+        }
+    }
+
+    fn dimensions_by_id(dims: &[MapDimensions]) -> BTreeMap<u32, MapDimensions> {
+        dims.iter().map(|dim| (dim.map_id, dim.clone())).collect()
+    }
+
+    #[test]
+    fn parses_every_flag() {
+        let options = parse(&[
+            "--only-missing",
+            "--ids",
+            "15, 18,1155",
+            "--base-url",
+            "http://localhost/maps",
+            "--dry-run",
+            "--from-dump",
+            "maps.json",
+        ])
+        .unwrap()
+        .unwrap();
+
+        assert!(options.only_missing);
+        assert_eq!(options.ids, Some(vec![15, 18, 1155]));
+        assert_eq!(options.base_url, "http://localhost/maps");
+        assert!(options.dry_run);
+        assert_eq!(options.from_dump, Some(PathBuf::from("maps.json")));
+        assert!(!selects_all_maps(&options));
+    }
+
+    #[test]
+    fn uses_defaults_without_flags() {
+        let options = parse(&[]).unwrap().unwrap();
+
+        assert!(!options.only_missing);
+        assert_eq!(options.ids, None);
+        assert_eq!(options.base_url, GW2_MAPS_BASE_URL);
+        assert!(!options.dry_run);
+        assert_eq!(options.from_dump, None);
+        assert!(selects_all_maps(&options));
+    }
+
+    #[test]
+    fn rejects_invalid_arguments() {
+        assert!(parse(&["--help"]).unwrap().is_none());
+        assert!(parse(&["--dry-run", "-h"]).unwrap().is_none());
+
+        assert!(parse(&["--ids"]).is_err());
+        assert!(parse(&["--ids", "15,abc"]).is_err());
+        assert!(parse(&["--base-url"]).is_err());
+        assert!(parse(&["--from-dump"]).is_err());
+        assert!(parse(&["--unknown"]).is_err());
+    }
+
+    #[test]
+    fn selects_missing_and_given_maps() {
+        let existing = dimensions_by_id(&[dimensions(15, 1.0)]);
+
+        let mut options = parse(&["--only-missing"]).unwrap().unwrap();
+        assert!(!is_selected(&options, &existing, 15));
+        assert!(is_selected(&options, &existing, 18));
+
+        options.ids = Some(vec![15, 50]);
+        assert!(!is_selected(&options, &existing, 18));
+        assert!(is_selected(&options, &existing, 50));
+    }
+
+    #[test]
+    fn merges_over_existing_maps() {
+        let existing = dimensions_by_id(&[dimensions(15, 1.0), dimensions(18, 1.0)]);
+        let fetched = [dimensions(18, 2.0), dimensions(50, 1.0)];
+
+        let merged = merge(&existing, fetched.clone(), false);
+        assert_eq!(
+            merged,
+            dimensions_by_id(&[
+                dimensions(15, 1.0),
+                dimensions(18, 2.0),
+                dimensions(50, 1.0)
+            ])
+        );
+
+        // Maps that are not reported anymore are removed only if all maps were fetched.
+        let replaced = merge(&existing, fetched, true);
+        assert_eq!(
+            replaced,
+            dimensions_by_id(&[dimensions(18, 2.0), dimensions(50, 1.0)])
+        );
+    }
+
+    #[test]
+    fn lists_the_changes() {
+        let existing = dimensions_by_id(&[
+            dimensions(15, 1.0),
+            dimensions(18, 1.0),
+            dimensions(24, 1.0),
+        ]);
+        let updated = dimensions_by_id(&[
+            dimensions(15, 1.0),
+            dimensions(18, 2.0),
+            dimensions(50, 1.0),
+        ]);
+
+        let changes = Changes::between(&existing, &updated);
+
+        assert_eq!(changes.added, [(50, &updated[&50])]);
+        assert_eq!(changes.changed, [(18, &existing[&18], &updated[&18])]);
+        assert_eq!(changes.removed, [(24, &existing[&24])]);
+        assert_eq!(changes.summary(), "1 added, 1 changed, 1 removed");
+    }
+
+    #[test]
+    fn writes_one_map_per_line() {
+        let dir = env::temp_dir().join(format!("paths-map-dimensions-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let file_path = dir.join("map-dimensions.json");
+        let data = dimensions_by_id(&[dimensions(15, 1.0), dimensions(18, 2.0)]);
+
+        write_data(&file_path, &data).unwrap();
+
+        let json = std::fs::read_to_string(&file_path).unwrap();
+        assert_eq!(json.lines().count(), 3);
+        assert!(json.starts_with("{\"15\":{\"cr\":"));
+        assert!(json.contains("}\n,\"18\":{"));
+        assert_eq!(load_existing_data(&file_path), data);
+        assert!(!dir.join("map-dimensions.json.tmp").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
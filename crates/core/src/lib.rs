@@ -1,6 +1,7 @@
 #![deny(unsafe_code)]
 
 pub mod loadable;
+pub mod localization;
 pub mod maps;
 pub mod markers;
 pub mod points;
@@ -0,0 +1,225 @@
+pub fn translate(message: &str) -> Option<&'static str> {
+    let translation = match message {
+        // Plurals
+        "{num} category" => "{num} catégorie",
+        "{num} categories" => "{num} catégories",
+        "{num} point" => "{num} point",
+        "{num} points" => "{num} points",
+        "{num} route" => "{num} itinéraire",
+        "{num} routes" => "{num} itinéraires",
+
+        // Main window
+        "Active markers" => "Marqueurs actifs",
+        "change..." => "modifier...",
+        "limit to current map" => "limiter à la carte actuelle",
+        "Dismiss" => "Fermer",
+        "Language:" => "Langue :",
+        "Game" => "Jeu",
+        "Follow the language of the game as set in Nexus" => {
+            "Suivre la langue du jeu telle que définie dans Nexus"
+        }
+        "Preset:" => "Préréglage :",
+        "Delete" => "Supprimer",
+        "Delete the active preset" => "Supprimer le préréglage actif",
+        "rules..." => "règles...",
+        "Preset name" => "Nom du préréglage",
+        "New" => "Nouveau",
+        "Create an empty preset" => "Créer un préréglage vide",
+        "Duplicate" => "Dupliquer",
+        "Copy the active preset" => "Copier le préréglage actif",
+        "Rename" => "Renommer",
+        "Rename the active preset" => "Renommer le préréglage actif",
+        "Selection code:" => "Code de sélection :",
+        "Copy" => "Copier",
+        "Copy the active preset as a code to share it" => {
+            "Copier le préréglage actif sous forme de code pour le partager"
+        }
+        "Copied the code to the clipboard." => "Le code a été copié dans le presse-papiers.",
+        "Import" => "Importer",
+        "Create a new preset from the code in the clipboard" => {
+            "Créer un nouveau préréglage à partir du code du presse-papiers"
+        }
+        "other overlays..." => "autres overlays...",
+        "The clipboard does not contain text." => "Le presse-papiers ne contient pas de texte.",
+        "Imported" => "Importé",
+        "Could not import the code: {err}" => "Impossible d'importer le code : {err}",
+        "Imported the code as preset {name}." => {
+            "Le code a été importé comme préréglage {name}."
+        }
+        "These categories are not part of the installed marker packs: {ids}" => {
+            "Ces catégories ne font pas partie des packs de marqueurs installés : {ids}"
+        }
+        "Default route color:" => "Couleur par défaut des itinéraires :",
+        "Default route width:" => "Largeur par défaut des itinéraires :",
+
+        // Settings file
+        "Could not open the settings file: {err}" => {
+            "Impossible d'ouvrir le fichier des paramètres : {err}"
+        }
+        "Could not read the settings ({err}). The old file was kept as {path}." => {
+            "Impossible de lire les paramètres ({err}). L'ancien fichier a été conservé sous {path}."
+        }
+        "Could not read the settings ({err}) and could not keep a backup of them ({backup_err})." => {
+            "Impossible de lire les paramètres ({err}) et d'en conserver une sauvegarde ({backup_err})."
+        }
+        "Changes are not saved until the settings are reloaded." => {
+            "Les modifications ne sont pas enregistrées tant que les paramètres ne sont pas rechargés."
+        }
+        "Could not save the settings: {err}" => {
+            "Impossible d'enregistrer les paramètres : {err}"
+        }
+
+        // Marker tree window
+        "Total markers:" => "Total des marqueurs :",
+        "Reload" => "Recharger",
+        "Pack folders" => "Dossiers de packs",
+        "Deselect all" => "Tout désélectionner",
+        "Search" => "Rechercher",
+        "Clear search" => "Effacer la recherche",
+        "current map" => "carte actuelle",
+        "active" => "actif",
+        "customized" => "personnalisé",
+        "Edit route color and width" => "Modifier la couleur et la largeur des itinéraires",
+        "Enable all" => "Tout activer",
+        "Disable all" => "Tout désactiver",
+        "Reset overrides" => "Réinitialiser les exceptions",
+        "Undo" => "Annuler",
+        "Redo" => "Rétablir",
+        "{num} category selected" => "{num} catégorie sélectionnée",
+        "{num} categories selected" => "{num} catégories sélectionnées",
+        "Clear selection" => "Effacer la sélection",
+        "Enable" => "Activer",
+        "Disable" => "Désactiver",
+        "Let the selected categories inherit the active state, color, width and style of their parents" => {
+            "Les catégories sélectionnées héritent de l'état, de la couleur, de la largeur et du style de leurs parentes"
+        }
+        "Show this route" => "Afficher cet itinéraire",
+        "Route color" => "Couleur de l'itinéraire",
+        "Route width" => "Largeur de l'itinéraire",
+        "Reverse the direction of the route" => "Inverser le sens de l'itinéraire",
+        "Reset to the values of the category" => "Rétablir les valeurs de la catégorie",
+        "Let all subcategories inherit the state of this category" => {
+            "Toutes les sous-catégories héritent de l'état de cette catégorie"
+        }
+
+        // Category properties window
+        "Category properties" => "Propriétés de la catégorie",
+        "Route color:" => "Couleur de l'itinéraire :",
+        "Route width:" => "Largeur de l'itinéraire :",
+        "Route style:" => "Style de l'itinéraire :",
+        "Default route style:" => "Style par défaut des itinéraires :",
+        "Arrows" => "Flèches",
+        "spacing" => "espacement",
+        "Outline" => "Contour",
+        "own color" => "couleur propre",
+        "Otherwise black or white, depending on the route color" => {
+            "Sinon noir ou blanc, selon la couleur de l'itinéraire"
+        }
+        "solid" => "continu",
+        "dashed" => "tirets",
+        "dotted" => "pointillés",
+        "Reset to parent's value" => "Rétablir la valeur de la catégorie parente",
+        "Route simplification:" => "Simplification des itinéraires :",
+        "Smallest detail of routes that is kept. Zoomed out maps use coarser versions of the routes." => {
+            "Plus petit détail des itinéraires qui est conservé. Les cartes dézoomées utilisent des versions plus grossières des itinéraires."
+        }
+
+        // Preset rules window
+        "Preset rules" => "Règles des préréglages",
+        "The first matching rule selects the active preset." => {
+            "La première règle correspondante sélectionne le préréglage actif."
+        }
+        "No rules yet." => "Aucune règle pour l'instant.",
+        "Move up" => "Monter",
+        "Remove rule" => "Supprimer la règle",
+        "Character name" => "Nom du personnage",
+        "Add rule for the current" => "Ajouter une règle pour l'actuel(le)",
+        "character" => "personnage",
+        "profession" => "profession",
+        "map" => "carte",
+        "map type" => "type de carte",
+        "Otherwise:" => "Sinon :",
+        "keep the current preset" => "garder le préréglage actuel",
+        "Character" => "Personnage",
+        "Profession" => "Profession",
+        "Map" => "Carte",
+        "Map type" => "Type de carte",
+        "Guardian" => "Gardien",
+        "Warrior" => "Guerrier",
+        "Engineer" => "Ingénieur",
+        "Ranger" => "Rôdeur",
+        "Thief" => "Voleur",
+        "Elementalist" => "Élémentaliste",
+        "Mesmer" => "Envoûteur",
+        "Necromancer" => "Nécromant",
+        "Revenant" => "Revenant",
+        "Instance" => "Instance",
+        "WvW" => "McM",
+
+        // Other overlays window
+        "Other overlays" => "Autres overlays",
+        "Create a new preset from the categories of the overlay" => {
+            "Créer un nouveau préréglage à partir des catégories de l'overlay"
+        }
+        "Export" => "Exporter",
+        "Overwrite the categories of the overlay with the active preset" => {
+            "Remplacer les catégories de l'overlay par le préréglage actif"
+        }
+        "Exported the preset {name}." => "Le préréglage {name} a été exporté.",
+        "Could not export the preset: {err}" => "Impossible d'exporter le préréglage : {err}",
+        "Path to TacOConfig.xml" => "Chemin vers TacOConfig.xml",
+        "Path to the categories.txt of the pathing module" => {
+            "Chemin vers le fichier categories.txt du module Pathing"
+        }
+        "Paste" => "Coller",
+        "Use the path in the clipboard" => "Utiliser le chemin du presse-papiers",
+        "Could not import the categories: {err}" => {
+            "Impossible d'importer les catégories : {err}"
+        }
+        "Imported the categories as preset {name}." => {
+            "Les catégories ont été importées comme préréglage {name}."
+        }
+
+        // Pack folders window
+        "Packs are always loaded from the markers folder of the addon. Other folders, like the POIs folder of TacO, can be added here." => {
+            "Les packs sont toujours chargés depuis le dossier markers de l'addon. D'autres dossiers, comme le dossier POIs de TacO, peuvent être ajoutés ici."
+        }
+        "Remove folder" => "Supprimer le dossier",
+        "Folder with .taco, .zip or XML files" => "Dossier contenant des fichiers .taco, .zip ou XML",
+        "Add" => "Ajouter",
+
+        // Appearance window
+        "appearance..." => "apparence...",
+        "Appearance" => "Apparence",
+        "Theme:" => "Thème :",
+        "Light" => "Clair",
+        "Dark" => "Sombre",
+        "Custom" => "Personnalisé",
+        "Dark base" => "Base sombre",
+        "Background:" => "Arrière-plan :",
+        "Text:" => "Texte :",
+        "Accent:" => "Accentuation :",
+        "Text size:" => "Taille du texte :",
+        "High contrast" => "Contraste élevé",
+        "Reset" => "Réinitialiser",
+
+        // Compass calibration window
+        "minimap..." => "mini-carte...",
+        "Minimap calibration" => "Calibrage de la mini-carte",
+        "Close the map to calibrate the minimap." => "Fermez la carte pour calibrer la mini-carte.",
+        "Move the outline until it matches the minimap. The correction only applies to the current interface size." => "Déplacez le cadre jusqu'à ce qu'il corresponde à la mini-carte. La correction ne s'applique qu'à la taille d'interface actuelle.",
+        "Interface size:" => "Taille de l'interface :",
+        "Horizontal offset:" => "Décalage horizontal :",
+        "Vertical offset:" => "Décalage vertical :",
+        "Extra width:" => "Largeur supplémentaire :",
+        "Extra height:" => "Hauteur supplémentaire :",
+        "Small" => "Petite",
+        "Normal" => "Normale",
+        "Large" => "Grande",
+        "Larger" => "Très grande",
+
+        _ => return None,
+    };
+
+    Some(translation)
+}
@@ -0,0 +1,226 @@
+pub fn translate(message: &str) -> Option<&'static str> {
+    let translation = match message {
+        // Plurals
+        "{num} category" => "{num} Kategorie",
+        "{num} categories" => "{num} Kategorien",
+        "{num} point" => "{num} Punkt",
+        "{num} points" => "{num} Punkte",
+        "{num} route" => "{num} Route",
+        "{num} routes" => "{num} Routen",
+
+        // Main window
+        "Active markers" => "Aktive Markierungen",
+        "change..." => "ändern...",
+        "limit to current map" => "auf aktuelle Karte beschränken",
+        "Dismiss" => "Schließen",
+        "Language:" => "Sprache:",
+        "Game" => "Spiel",
+        "Follow the language of the game as set in Nexus" => {
+            "Der Sprache des Spiels folgen, wie sie in Nexus eingestellt ist"
+        }
+        "Preset:" => "Voreinstellung:",
+        "Delete" => "Löschen",
+        "Delete the active preset" => "Die aktive Voreinstellung löschen",
+        "rules..." => "Regeln...",
+        "Preset name" => "Name der Voreinstellung",
+        "New" => "Neu",
+        "Create an empty preset" => "Eine leere Voreinstellung erstellen",
+        "Duplicate" => "Duplizieren",
+        "Copy the active preset" => "Die aktive Voreinstellung kopieren",
+        "Rename" => "Umbenennen",
+        "Rename the active preset" => "Die aktive Voreinstellung umbenennen",
+        "Selection code:" => "Auswahlcode:",
+        "Copy" => "Kopieren",
+        "Copy the active preset as a code to share it" => {
+            "Die aktive Voreinstellung als Code kopieren, um sie zu teilen"
+        }
+        "Copied the code to the clipboard." => "Der Code wurde in die Zwischenablage kopiert.",
+        "Import" => "Importieren",
+        "Create a new preset from the code in the clipboard" => {
+            "Eine neue Voreinstellung aus dem Code in der Zwischenablage erstellen"
+        }
+        "other overlays..." => "andere Overlays...",
+        "The clipboard does not contain text." => "Die Zwischenablage enthält keinen Text.",
+        "Imported" => "Importiert",
+        "Could not import the code: {err}" => "Der Code konnte nicht importiert werden: {err}",
+        "Imported the code as preset {name}." => {
+            "Der Code wurde als Voreinstellung {name} importiert."
+        }
+        "These categories are not part of the installed marker packs: {ids}" => {
+            "Diese Kategorien sind nicht Teil der installierten Markierungspakete: {ids}"
+        }
+        "Default route color:" => "Standardfarbe der Routen:",
+        "Default route width:" => "Standardbreite der Routen:",
+
+        // Settings file
+        "Could not open the settings file: {err}" => {
+            "Die Einstellungsdatei konnte nicht geöffnet werden: {err}"
+        }
+        "Could not read the settings ({err}). The old file was kept as {path}." => {
+            "Die Einstellungen konnten nicht gelesen werden ({err}). Die alte Datei wurde als {path} behalten."
+        }
+        "Could not read the settings ({err}) and could not keep a backup of them ({backup_err})." => {
+            "Die Einstellungen konnten nicht gelesen werden ({err}) und es konnte keine Sicherung angelegt werden ({backup_err})."
+        }
+        "Changes are not saved until the settings are reloaded." => {
+            "Änderungen werden erst nach dem Neuladen der Einstellungen gespeichert."
+        }
+        "Could not save the settings: {err}" => {
+            "Die Einstellungen konnten nicht gespeichert werden: {err}"
+        }
+
+        // Marker tree window
+        "Total markers:" => "Markierungen insgesamt:",
+        "Reload" => "Neu laden",
+        "Pack folders" => "Paketordner",
+        "Deselect all" => "Alle abwählen",
+        "Search" => "Suchen",
+        "Clear search" => "Suche leeren",
+        "current map" => "aktuelle Karte",
+        "active" => "aktiv",
+        "customized" => "angepasst",
+        "Edit route color and width" => "Farbe und Breite der Routen bearbeiten",
+        "Enable all" => "Alle aktivieren",
+        "Disable all" => "Alle deaktivieren",
+        "Reset overrides" => "Abweichungen zurücksetzen",
+        "Undo" => "Rückgängig",
+        "Redo" => "Wiederholen",
+        "{num} category selected" => "{num} Kategorie ausgewählt",
+        "{num} categories selected" => "{num} Kategorien ausgewählt",
+        "Clear selection" => "Auswahl aufheben",
+        "Enable" => "Aktivieren",
+        "Disable" => "Deaktivieren",
+        "Let the selected categories inherit the active state, color, width and style of their parents" => {
+            "Die ausgewählten Kategorien übernehmen Zustand, Farbe, Breite und Stil ihrer übergeordneten Kategorien"
+        }
+        "Show this route" => "Diese Route anzeigen",
+        "Route color" => "Routenfarbe",
+        "Route width" => "Routenbreite",
+        "Reverse the direction of the route" => "Die Richtung der Route umkehren",
+        "Reset to the values of the category" => "Auf die Werte der Kategorie zurücksetzen",
+        "Let all subcategories inherit the state of this category" => {
+            "Alle Unterkategorien übernehmen den Zustand dieser Kategorie"
+        }
+
+        // Category properties window
+        "Category properties" => "Kategorieeigenschaften",
+        "Route color:" => "Routenfarbe:",
+        "Route width:" => "Routenbreite:",
+        "Route style:" => "Routenstil:",
+        "Default route style:" => "Standardstil der Routen:",
+        "Arrows" => "Pfeile",
+        "spacing" => "Abstand",
+        "Outline" => "Umriss",
+        "own color" => "eigene Farbe",
+        "Otherwise black or white, depending on the route color" => {
+            "Sonst schwarz oder weiß, je nach Farbe der Route"
+        }
+        "solid" => "durchgezogen",
+        "dashed" => "gestrichelt",
+        "dotted" => "gepunktet",
+        "Reset to parent's value" => "Auf den Wert der übergeordneten Kategorie zurücksetzen",
+        "Route simplification:" => "Vereinfachung der Routen:",
+        "Smallest detail of routes that is kept. Zoomed out maps use coarser versions of the routes." => {
+            "Kleinstes Detail der Routen, das erhalten bleibt. Herausgezoomte Karten verwenden gröbere Fassungen der Routen."
+        }
+
+        // Preset rules window
+        "Preset rules" => "Regeln für Voreinstellungen",
+        "The first matching rule selects the active preset." => {
+            "Die erste zutreffende Regel wählt die aktive Voreinstellung."
+        }
+        "No rules yet." => "Noch keine Regeln.",
+        "Move up" => "Nach oben",
+        "Remove rule" => "Regel entfernen",
+        "Character name" => "Charaktername",
+        "Add rule for the current" => "Regel hinzufügen für aktuelle(n)",
+        "character" => "Charakter",
+        "profession" => "Klasse",
+        "map" => "Karte",
+        "map type" => "Kartentyp",
+        "Otherwise:" => "Sonst:",
+        "keep the current preset" => "aktuelle Voreinstellung behalten",
+        "Character" => "Charakter",
+        "Profession" => "Klasse",
+        "Map" => "Karte",
+        "Map type" => "Kartentyp",
+        "Guardian" => "Wächter",
+        "Warrior" => "Krieger",
+        "Engineer" => "Ingenieur",
+        "Ranger" => "Waldläufer",
+        "Thief" => "Dieb",
+        "Elementalist" => "Elementarmagier",
+        "Mesmer" => "Mesmer",
+        "Necromancer" => "Nekromant",
+        "Revenant" => "Widergänger",
+        "Instance" => "Instanz",
+
+        // Other overlays window
+        "Other overlays" => "Andere Overlays",
+        "Create a new preset from the categories of the overlay" => {
+            "Eine neue Voreinstellung aus den Kategorien des Overlays erstellen"
+        }
+        "Export" => "Exportieren",
+        "Overwrite the categories of the overlay with the active preset" => {
+            "Die Kategorien des Overlays mit der aktiven Voreinstellung überschreiben"
+        }
+        "Exported the preset {name}." => "Die Voreinstellung {name} wurde exportiert.",
+        "Could not export the preset: {err}" => {
+            "Die Voreinstellung konnte nicht exportiert werden: {err}"
+        }
+        "Path to TacOConfig.xml" => "Pfad zur TacOConfig.xml",
+        "Path to the categories.txt of the pathing module" => {
+            "Pfad zur categories.txt des Pathing-Moduls"
+        }
+        "Paste" => "Einfügen",
+        "Use the path in the clipboard" => "Den Pfad aus der Zwischenablage verwenden",
+        "Could not import the categories: {err}" => {
+            "Die Kategorien konnten nicht importiert werden: {err}"
+        }
+        "Imported the categories as preset {name}." => {
+            "Die Kategorien wurden als Voreinstellung {name} importiert."
+        }
+
+        // Pack folders window
+        "Packs are always loaded from the markers folder of the addon. Other folders, like the POIs folder of TacO, can be added here." => {
+            "Pakete werden immer aus dem markers-Ordner des Addons geladen. Andere Ordner, wie der POIs-Ordner von TacO, können hier hinzugefügt werden."
+        }
+        "Remove folder" => "Ordner entfernen",
+        "Folder with .taco, .zip or XML files" => "Ordner mit .taco-, .zip- oder XML-Dateien",
+        "Add" => "Hinzufügen",
+
+        // Appearance window
+        "appearance..." => "Darstellung...",
+        "Appearance" => "Darstellung",
+        "Theme:" => "Farbschema:",
+        "Light" => "Hell",
+        "Dark" => "Dunkel",
+        "Custom" => "Eigenes",
+        "Dark base" => "Dunkle Grundlage",
+        "Background:" => "Hintergrund:",
+        "Text:" => "Text:",
+        "Accent:" => "Akzent:",
+        "Text size:" => "Textgröße:",
+        "High contrast" => "Hoher Kontrast",
+        "Reset" => "Zurücksetzen",
+
+        // Compass calibration window
+        "minimap..." => "Minikarte...",
+        "Minimap calibration" => "Kalibrierung der Minikarte",
+        "Close the map to calibrate the minimap." => "Schließe die Karte, um die Minikarte zu kalibrieren.",
+        "Move the outline until it matches the minimap. The correction only applies to the current interface size." => "Verschiebe den Rahmen, bis er mit der Minikarte übereinstimmt. Die Korrektur gilt nur für die aktuelle Größe der Benutzeroberfläche.",
+        "Interface size:" => "Größe der Benutzeroberfläche:",
+        "Horizontal offset:" => "Horizontale Verschiebung:",
+        "Vertical offset:" => "Vertikale Verschiebung:",
+        "Extra width:" => "Zusätzliche Breite:",
+        "Extra height:" => "Zusätzliche Höhe:",
+        "Small" => "Klein",
+        "Normal" => "Normal",
+        "Large" => "Groß",
+        "Larger" => "Größer",
+
+        _ => return None,
+    };
+
+    Some(translation)
+}
@@ -0,0 +1,110 @@
+mod french;
+mod german;
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+/// The languages of the UI. Messages are written in English and looked up in the catalog of the current
+/// language, falling back to English if there is no translation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Language {
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "de")]
+    German,
+    #[serde(rename = "fr")]
+    French,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PluralCategory {
+    One,
+    Other,
+}
+
+impl Language {
+    pub const ALL: [Self; 3] = [Self::English, Self::German, Self::French];
+
+    /// The two-letter code also used by the game and Nexus.
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::German => "de",
+            Language::French => "fr",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|language| language.code().eq_ignore_ascii_case(code.trim()))
+    }
+
+    /// The name of the language in the language itself.
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+            Language::French => "Français",
+        }
+    }
+
+    pub fn plural_category(self, num: usize) -> PluralCategory {
+        let is_one = match self {
+            Language::English | Language::German => num == 1,
+            // Zero is singular in French.
+            Language::French => num <= 1,
+        };
+
+        if is_one {
+            PluralCategory::One
+        } else {
+            PluralCategory::Other
+        }
+    }
+
+    fn translate(self, message: &'static str) -> &'static str {
+        let translation = match self {
+            Language::English => None,
+            Language::German => german::translate(message),
+            Language::French => french::translate(message),
+        };
+
+        translation.unwrap_or(message)
+    }
+}
+
+static CURRENT_LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+pub fn current_language() -> Language {
+    Language::ALL
+        .get(CURRENT_LANGUAGE.load(Ordering::Relaxed) as usize)
+        .copied()
+        .unwrap_or(Language::English)
+}
+
+pub fn set_current_language(language: Language) {
+    let idx = Language::ALL
+        .iter()
+        .position(|l| *l == language)
+        .unwrap_or_default();
+
+    CURRENT_LANGUAGE.store(idx as u8, Ordering::Relaxed);
+}
+
+/// Translates the message to the current language. Placeholders like `{name}` have to be replaced
+/// afterwards, so translations can move them.
+pub fn tr(message: &'static str) -> &'static str {
+    current_language().translate(message)
+}
+
+/// Picks the form for the number by the plural rules of the current language and replaces `{num}`.
+pub fn tr_plural(num: usize, one: &'static str, other: &'static str) -> String {
+    let message = match current_language().plural_category(num) {
+        PluralCategory::One => one,
+        PluralCategory::Other => other,
+    };
+
+    tr(message).replace("{num}", &num.to_string())
+}
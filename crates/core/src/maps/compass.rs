@@ -0,0 +1,42 @@
+use egui::{vec2, Pos2, Rect};
+
+use crate::settings::{CompassCalibration, UiSize};
+
+/// The gap between a compass at the bottom and the bottom of the screen, at the normal interface size.
+const DISTANCE_FROM_BOTTOM: f32 = 37.0;
+
+/// Where the game draws the compass, as far as MumbleLink tells.
+#[derive(Clone, Copy, Debug)]
+pub struct CompassLayout {
+    pub screen_width: f32,
+    pub screen_height: f32,
+    /// At the normal interface size.
+    pub compass_width: f32,
+    pub compass_height: f32,
+    pub is_top_right: bool,
+    pub ui_size: UiSize,
+}
+
+/// The compass is always at the right edge of the screen, either at the top or above the skill bar.
+pub fn compass_rect(layout: &CompassLayout, calibration: &CompassCalibration) -> Rect {
+    let scale_factor = layout.ui_size.scale_factor();
+
+    let width = layout.compass_width * scale_factor;
+    let height = layout.compass_height * scale_factor;
+
+    let left = layout.screen_width - width;
+
+    let top = if layout.is_top_right {
+        1.0
+    } else {
+        layout.screen_height - height - DISTANCE_FROM_BOTTOM * scale_factor
+    };
+
+    Rect::from_min_size(
+        Pos2::new(left + calibration.offset_x, top + calibration.offset_y),
+        vec2(
+            width + calibration.extra_width,
+            height + calibration.extra_height,
+        ),
+    )
+}
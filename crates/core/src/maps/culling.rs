@@ -0,0 +1,180 @@
+use nalgebra::{Matrix3, Point2};
+
+use crate::points::Point3;
+
+/// An axis-aligned rectangle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min: Point2<f32>,
+    pub max: Point2<f32>,
+}
+
+impl BoundingBox {
+    /// `None` without points.
+    pub fn from_points(points: impl IntoIterator<Item = Point2<f32>>) -> Option<Self> {
+        points.into_iter().fold(None, |bounds, point| {
+            Some(match bounds {
+                None => Self {
+                    min: point,
+                    max: point,
+                },
+                Some(Self { min, max }) => Self {
+                    min: min.inf(&point),
+                    max: max.sup(&point),
+                },
+            })
+        })
+    }
+
+    pub fn corners(&self) -> [Point2<f32>; 4] {
+        [
+            self.min,
+            Point2::new(self.max.x, self.min.y),
+            self.max,
+            Point2::new(self.min.x, self.max.y),
+        ]
+    }
+
+    /// The smallest box around the transformed corners, so rotations make it larger.
+    pub fn transform(&self, matrix: &Matrix3<f32>) -> Self {
+        Self::from_points(
+            self.corners()
+                .into_iter()
+                .map(|corner| matrix.transform_point(&corner)),
+        )
+        .unwrap_or(*self)
+    }
+
+    pub fn expand(&self, margin: f32) -> Self {
+        Self {
+            min: self.min.map(|c| c - margin),
+            max: self.max.map(|c| c + margin),
+        }
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+}
+
+/// The bounds of a trail in continent coordinates. Only the horizontal position of the points matters.
+pub fn trail_bounds(points: &[Point3], map_to_world: &Matrix3<f32>) -> Option<BoundingBox> {
+    BoundingBox::from_points(points.iter().map(|point| point.xy()))
+        .map(|bounds| bounds.transform(map_to_world))
+}
+
+/// The part of the continent shown in `screen_rect`, or `None` if the transformation cannot be inverted.
+pub fn visible_world_rect(
+    world_to_screen: &Matrix3<f32>,
+    screen_rect: &BoundingBox,
+) -> Option<BoundingBox> {
+    world_to_screen
+        .try_inverse()
+        .map(|screen_to_world| screen_rect.transform(&screen_to_world))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::maps::{world_to_screen_transformation, MapView};
+
+    use super::*;
+
+    fn bounding_box(min: (f32, f32), max: (f32, f32)) -> BoundingBox {
+        BoundingBox {
+            min: Point2::new(min.0, min.1),
+            max: Point2::new(max.0, max.1),
+        }
+    }
+
+    fn contains(bounds: &BoundingBox, point: &Point2<f32>) -> bool {
+        const TOLERANCE: f32 = 1e-2;
+
+        (bounds.min.x - TOLERANCE..=bounds.max.x + TOLERANCE).contains(&point.x)
+            && (bounds.min.y - TOLERANCE..=bounds.max.y + TOLERANCE).contains(&point.y)
+    }
+
+    #[test]
+    fn has_no_bounds_without_points() {
+        assert_eq!(BoundingBox::from_points([]), None);
+    }
+
+    #[test]
+    fn bounds_all_points() {
+        let bounds = BoundingBox::from_points([
+            Point2::new(3.0, -1.0),
+            Point2::new(-2.0, 4.0),
+            Point2::new(0.0, 0.0),
+        ]);
+
+        assert_eq!(bounds, Some(bounding_box((-2.0, -1.0), (3.0, 4.0))));
+    }
+
+    #[test]
+    fn touching_boxes_intersect() {
+        let bounds = bounding_box((0.0, 0.0), (1.0, 1.0));
+
+        for other in [
+            bounding_box((1.0, 0.0), (2.0, 1.0)),
+            bounding_box((0.0, 1.0), (1.0, 2.0)),
+            bounding_box((1.0, 1.0), (2.0, 2.0)),
+        ] {
+            assert!(bounds.intersects(&other));
+            assert!(other.intersects(&bounds));
+        }
+    }
+
+    #[test]
+    fn disjoint_boxes_do_not_intersect() {
+        let bounds = bounding_box((0.0, 0.0), (1.0, 1.0));
+
+        for other in [
+            bounding_box((1.5, 0.0), (2.0, 1.0)),
+            bounding_box((0.0, -2.0), (1.0, -0.5)),
+            // Overlapping on one axis only.
+            bounding_box((0.5, 3.0), (0.7, 4.0)),
+        ] {
+            assert!(!bounds.intersects(&other));
+            assert!(!other.intersects(&bounds));
+        }
+    }
+
+    #[test]
+    fn visible_world_rect_contains_the_rotated_screen() {
+        let screen_rect = bounding_box((0.0, 0.0), (800.0, 600.0));
+
+        for rotation in [0.0, 0.3, 1.2, 2.5, -0.8] {
+            let view = MapView {
+                center: Point2::new(1000.0, 2000.0),
+                scale: 3.0,
+                rotation,
+                rotation_center: Point2::new(1050.0, 1980.0),
+            };
+
+            let world_to_screen = world_to_screen_transformation(&view, Point2::new(400.0, 300.0));
+            let screen_to_world = world_to_screen.try_inverse().unwrap();
+
+            let visible = visible_world_rect(&world_to_screen, &screen_rect).unwrap();
+
+            for x in 0..=8 {
+                for y in 0..=6 {
+                    let screen_point = Point2::new(x as f32 * 100.0, y as f32 * 100.0);
+
+                    assert!(contains(
+                        &visible,
+                        &screen_to_world.transform_point(&screen_point)
+                    ));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn has_no_visible_world_rect_without_inverse() {
+        let screen_rect = bounding_box((0.0, 0.0), (800.0, 600.0));
+
+        assert_eq!(visible_world_rect(&Matrix3::zeros(), &screen_rect), None);
+    }
+}
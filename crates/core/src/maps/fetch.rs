@@ -4,10 +4,10 @@ pub use self::error::{FetchError, FetchResult};
 
 use super::{MapDimensions, MapRect};
 
-const GW2_MAPS_BASE_URL: &'static str = "https://api.guildwars2.com/v2/maps";
+pub const GW2_MAPS_BASE_URL: &'static str = "https://api.guildwars2.com/v2/maps";
 
-pub fn fetch_maps_index() -> FetchResult<Vec<u32>> {
-    let res = minreq::get(GW2_MAPS_BASE_URL).send()?;
+pub fn fetch_maps_index(base_url: &str) -> FetchResult<Vec<u32>> {
+    let res = minreq::get(base_url).send()?;
 
     if res.status_code != 200 {
         return Err(error::FetchError::NonOkStatus {
@@ -20,10 +20,10 @@ pub fn fetch_maps_index() -> FetchResult<Vec<u32>> {
     Ok(res.json::<Vec<u32>>()?)
 }
 
-pub fn fetch_map_dimensions(map_ids: &[u32]) -> FetchResult<Vec<MapDimensions>> {
+pub fn fetch_map_dimensions(base_url: &str, map_ids: &[u32]) -> FetchResult<Vec<MapDimensions>> {
     let res = minreq::get(&format!(
         "{}?ids={}",
-        GW2_MAPS_BASE_URL,
+        base_url,
         map_ids
             .iter()
             .map(|id| id.to_string())
@@ -45,6 +45,12 @@ pub fn fetch_map_dimensions(map_ids: &[u32]) -> FetchResult<Vec<MapDimensions>>
         .map(|maps| maps.iter().map(|map| map.to_dimensions()).collect())?)
 }
 
+/// Reads map dimensions from a saved response of the maps endpoint, e.g. `/v2/maps?ids=all`.
+pub fn map_dimensions_from_dump(bytes: &[u8]) -> serde_json::Result<Vec<MapDimensions>> {
+    serde_json::from_slice::<Vec<RawMap>>(bytes)
+        .map(|maps| maps.iter().map(|map| map.to_dimensions()).collect())
+}
+
 #[derive(Deserialize)]
 struct RawMap {
     id: u32,
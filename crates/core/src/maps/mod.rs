@@ -1,9 +1,15 @@
+mod compass;
+mod culling;
 #[cfg(windows)]
 mod dimensions;
 mod fetch;
+mod projection;
 mod shared_types;
 
+pub use self::compass::*;
+pub use self::culling::*;
 #[cfg(windows)]
 pub use self::dimensions::*;
 pub use self::fetch::*;
+pub use self::projection::*;
 pub use self::shared_types::*;
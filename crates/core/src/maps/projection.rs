@@ -0,0 +1,102 @@
+use nalgebra::{Matrix3, Point2, Vector2};
+
+/// The part of the continent shown by the map or the compass.
+#[derive(Clone, Copy, Debug)]
+pub struct MapView {
+    /// In continent coordinates.
+    pub center: Point2<f32>,
+    /// Continent units per pixel.
+    pub scale: f32,
+    /// The angle of a rotating compass in radians, 0 otherwise.
+    pub rotation: f32,
+    /// A rotating compass turns around the player, in continent coordinates.
+    pub rotation_center: Point2<f32>,
+}
+
+/// Maps continent coordinates to the screen, with the center of the view at `screen_center`. Rotations are
+/// clockwise on the screen, where y points down.
+pub fn world_to_screen_transformation(view: &MapView, screen_center: Point2<f32>) -> Matrix3<f32> {
+    let rotate_around_player = Matrix3::new_translation(&view.rotation_center.coords)
+        * Matrix3::new_rotation(view.rotation)
+        * Matrix3::new_translation(&-view.rotation_center.coords);
+
+    // Move map center to 0,0
+    let translate_map_center = Matrix3::new_translation(&-view.center.coords);
+
+    let scale = Matrix3::new_nonuniform_scaling(&Vector2::repeat(1.0 / view.scale));
+
+    let translate_to_screen = Matrix3::new_translation(&screen_center.coords);
+
+    translate_to_screen * scale * translate_map_center * rotate_around_player
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, TAU};
+
+    use super::*;
+
+    const SCREEN_CENTER: Point2<f32> = Point2::new(400.0, 300.0);
+
+    fn view(rotation: f32) -> MapView {
+        MapView {
+            center: Point2::new(1000.0, 2000.0),
+            scale: 4.0,
+            rotation,
+            rotation_center: Point2::new(1100.0, 1900.0),
+        }
+    }
+
+    fn to_screen(view: &MapView, point: Point2<f32>) -> Point2<f32> {
+        world_to_screen_transformation(view, SCREEN_CENTER).transform_point(&point)
+    }
+
+    fn assert_close(actual: Point2<f32>, expected: Point2<f32>) {
+        assert!(
+            (actual - expected).norm() < 1e-3,
+            "{actual} is not close to {expected}"
+        );
+    }
+
+    #[test]
+    fn without_rotation_only_moves_and_scales() {
+        let view = view(0.0);
+
+        for point in [
+            view.center,
+            view.rotation_center,
+            Point2::new(-500.0, 300.0),
+            Point2::new(5000.0, 7000.0),
+        ] {
+            let expected = SCREEN_CENTER + (point - view.center) / view.scale;
+
+            assert_close(to_screen(&view, point), expected);
+        }
+    }
+
+    #[test]
+    fn player_keeps_its_screen_position() {
+        let expected = to_screen(&view(0.0), view(0.0).rotation_center);
+
+        for step in 0..16 {
+            let view = view(step as f32 / 16.0 * TAU);
+
+            assert_close(to_screen(&view, view.rotation_center), expected);
+        }
+    }
+
+    #[test]
+    fn quarter_turn_moves_north_to_the_right() {
+        let view = view(FRAC_PI_2);
+
+        // Continent coordinates point south like the screen.
+        let north_of_player = view.rotation_center - Vector2::new(0.0, 100.0);
+
+        let player_on_screen = to_screen(&view, view.rotation_center);
+
+        assert_close(
+            to_screen(&view, north_of_player),
+            player_on_screen + Vector2::new(100.0 / view.scale, 0.0),
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct MapDimensions {
     #[serde(skip)]
     pub map_id: u32,
@@ -10,7 +10,7 @@ pub struct MapDimensions {
     pub map_rect: MapRect,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct MapRect {
     #[serde(rename = "tl")]
     pub top_left: [f32; 2],
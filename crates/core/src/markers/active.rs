@@ -1,8 +1,4 @@
-use std::{
-    collections::HashMap,
-    hash::{DefaultHasher, Hash, Hasher},
-    iter::once,
-};
+use std::{collections::HashMap, iter::once};
 
 #[cfg(debug_assertions)]
 use log::trace;
@@ -11,14 +7,15 @@ use log_err::LogErrOption;
 use crate::{
     markers::MarkerCategoryTreeNode,
     points::Point3,
-    settings::{TrailColor, TrailWidth},
+    settings::{TrailColor, TrailStyle, TrailWidth},
 };
 
-use super::MarkerCategoryTree;
+use super::{split_trail, MarkerCategoryTree, TrailId};
 
 #[derive(Debug)]
 pub struct ActiveMarkerCategories<'a> {
     pub active_category_count: usize,
+    generation: u64,
     current_map_id: u32,
     active_points_of_interest_by_map: HashMap<u32, Vec<ActivePointOfInterest<'a>>>,
     active_trails_by_map: HashMap<u32, Vec<ActiveTrail<'a>>>,
@@ -28,6 +25,7 @@ impl<'a> ActiveMarkerCategories<'a> {
     pub fn new() -> Self {
         Self {
             active_category_count: 0,
+            generation: 0,
             current_map_id: 0,
             active_points_of_interest_by_map: HashMap::default(),
             active_trails_by_map: HashMap::default(),
@@ -35,15 +33,16 @@ impl<'a> ActiveMarkerCategories<'a> {
     }
 
     pub fn read_from_tree(&mut self, tree: &'a MarkerCategoryTree) {
+        tree.update_subtree_active_states();
+
+        self.generation = self.generation.wrapping_add(1);
         self.active_category_count = 0;
         self.active_points_of_interest_by_map.clear();
         self.active_trails_by_map.clear();
 
         fn collect_active_categories<'a>(
             parent: &MarkerCategoryTreeNode<'a>,
-            parent_is_active: bool,
-            parent_trail_color: &TrailColor,
-            parent_trail_width: &TrailWidth,
+            inherited: &InheritedSettings,
             category_count: &mut usize,
             all_points_of_interest: &mut HashMap<u32, Vec<ActivePointOfInterest>>,
             all_trails: &mut HashMap<u32, Vec<ActiveTrail<'a>>>,
@@ -51,17 +50,24 @@ impl<'a> ActiveMarkerCategories<'a> {
             for child in parent.children() {
                 let category = child.data();
 
-                let child_is_active = category.is_active.borrow().unwrap_or(parent_is_active);
-                let child_trail_color = category
-                    .trail_color
-                    .borrow()
-                    .unwrap_or_else(|| *parent_trail_color);
-                let child_trail_width = category
-                    .trail_width
-                    .borrow()
-                    .unwrap_or_else(|| *parent_trail_width);
-
-                if child_is_active
+                let child_settings = InheritedSettings {
+                    is_active: category.is_active.borrow().unwrap_or(inherited.is_active),
+                    trail_color: category
+                        .trail_color
+                        .borrow()
+                        .unwrap_or(inherited.trail_color),
+                    trail_width: category
+                        .trail_width
+                        .borrow()
+                        .unwrap_or(inherited.trail_width),
+                    trail_style: category
+                        .trail_style
+                        .borrow()
+                        .unwrap_or(inherited.trail_style),
+                    alpha: category.alpha.or(inherited.alpha),
+                };
+
+                if child_settings.is_active
                     && (!category.points_of_interest.is_empty() || !category.trails.is_empty())
                 {
                     *category_count += 1;
@@ -70,20 +76,29 @@ impl<'a> ActiveMarkerCategories<'a> {
                         // TODO
                     }
 
-                    let mut hasher = DefaultHasher::new();
-                    category.identifier.hash(&mut hasher);
-                    let hash = hasher.finish();
-
                     for trail in &category.trails {
+                        let overrides = trail.overrides.borrow();
+
+                        if overrides.hidden {
+                            continue;
+                        }
+
                         all_trails
                             .entry(trail.map_id)
                             .or_default()
                             .push(ActiveTrail {
                                 #[cfg(debug_assertions)]
                                 id: &category.identifier,
-                                hash,
-                                width: child_trail_width,
-                                color: child_trail_color,
+                                trail_id: trail.id,
+                                width: overrides.width.unwrap_or(child_settings.trail_width),
+                                // The color picked for the trail itself is used as it is.
+                                color: overrides.color.unwrap_or_else(|| {
+                                    child_settings.trail_color.scale_alpha(
+                                        trail.alpha.or(child_settings.alpha).unwrap_or(1.0),
+                                    )
+                                }),
+                                style: child_settings.trail_style,
+                                reversed: overrides.reversed,
                                 points: &trail.points,
                             });
                     }
@@ -91,9 +106,7 @@ impl<'a> ActiveMarkerCategories<'a> {
 
                 collect_active_categories(
                     &child,
-                    child_is_active,
-                    &child_trail_color,
-                    &child_trail_width,
+                    &child_settings,
                     category_count,
                     all_points_of_interest,
                     all_trails,
@@ -106,9 +119,13 @@ impl<'a> ActiveMarkerCategories<'a> {
 
         collect_active_categories(
             &root,
-            false,
-            &root_category.trail_color.borrow().log_unwrap(),
-            &root_category.trail_width.borrow().log_unwrap(),
+            &InheritedSettings {
+                is_active: false,
+                trail_color: root_category.trail_color.borrow().log_unwrap(),
+                trail_width: root_category.trail_width.borrow().log_unwrap(),
+                trail_style: root_category.trail_style.borrow().log_unwrap(),
+                alpha: None,
+            },
             &mut self.active_category_count,
             &mut self.active_points_of_interest_by_map,
             &mut self.active_trails_by_map,
@@ -143,6 +160,15 @@ impl<'a> ActiveMarkerCategories<'a> {
         self.set_current_map(self.current_map_id);
     }
 
+    /// Changes whenever the categories are read from the tree again, so derived data can be cached.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn current_map_id(&self) -> u32 {
+        self.current_map_id
+    }
+
     pub fn set_current_map(&mut self, map_id: u32) {
         self.current_map_id = map_id;
 
@@ -219,16 +245,36 @@ impl<'a> ActiveMarkerCategories<'a> {
     }
 }
 
+/// The settings a category passes on to its subcategories.
+struct InheritedSettings {
+    is_active: bool,
+    trail_color: TrailColor,
+    trail_width: TrailWidth,
+    trail_style: TrailStyle,
+    /// The opacity from the pack.
+    alpha: Option<f32>,
+}
+
 #[derive(Debug)]
 pub struct ActiveTrail<'a> {
     #[cfg(debug_assertions)]
     pub id: &'a Vec<String>,
-    pub hash: u64,
+    pub trail_id: TrailId,
     pub width: TrailWidth,
     pub color: TrailColor,
+    pub style: TrailStyle,
+    /// The points have to be drawn from the last to the first one.
+    pub reversed: bool,
     pub points: &'a Vec<Point3>,
 }
 
+impl ActiveTrail<'_> {
+    /// The parts of the trail which are drawn as connected lines.
+    pub fn segments(&self, max_gap: f32) -> impl Iterator<Item = &[Point3]> {
+        split_trail(self.points, max_gap)
+    }
+}
+
 #[derive(Debug)]
 pub struct ActivePointOfInterest<'a> {
     #[cfg(debug_assertions)]
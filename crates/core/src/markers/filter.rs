@@ -0,0 +1,102 @@
+use std::collections::HashSet;
+
+use log_err::LogErrOption;
+
+use super::{MarkerCategory, MarkerCategoryTree, MarkerCategoryTreeNode, NodeId};
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MarkerCategoryFilter {
+    /// Matches labels and ids, ignoring case.
+    pub search: String,
+    pub only_current_map: bool,
+    pub only_active: bool,
+    pub only_customized: bool,
+}
+
+impl MarkerCategoryFilter {
+    pub fn is_empty(&self) -> bool {
+        self.search.trim().is_empty()
+            && !self.only_current_map
+            && !self.only_active
+            && !self.only_customized
+    }
+
+    /// The nodes to display: the matching categories together with their ancestors. The children of a
+    /// category matching the search are included if they pass the toggles.
+    pub fn visible_nodes(&self, tree: &MarkerCategoryTree, current_map_id: u32) -> HashSet<NodeId> {
+        let search = self.search.trim().to_lowercase();
+        let mut visible_nodes = HashSet::new();
+
+        self.collect_visible_nodes(
+            &tree.tree.root().log_unwrap(),
+            &search,
+            search.is_empty(),
+            false,
+            current_map_id,
+            &mut visible_nodes,
+        );
+
+        visible_nodes
+    }
+
+    fn collect_visible_nodes(
+        &self,
+        parent: &MarkerCategoryTreeNode,
+        search: &str,
+        parent_matches_search: bool,
+        parent_is_active: bool,
+        current_map_id: u32,
+        visible_nodes: &mut HashSet<NodeId>,
+    ) -> bool {
+        let mut any_visible = false;
+
+        for child in parent.children() {
+            let category = child.data();
+            let is_active = category.is_active.borrow().unwrap_or(parent_is_active);
+            let matches_search = parent_matches_search || matches_search(category, search);
+
+            let is_visible = self.collect_visible_nodes(
+                &child,
+                search,
+                matches_search,
+                is_active,
+                current_map_id,
+                visible_nodes,
+            ) || (matches_search
+                && self.matches_toggles(category, is_active, current_map_id));
+
+            if is_visible {
+                visible_nodes.insert(child.node_id());
+                any_visible = true;
+            }
+        }
+
+        any_visible
+    }
+
+    fn matches_toggles(
+        &self,
+        category: &MarkerCategory,
+        is_active: bool,
+        current_map_id: u32,
+    ) -> bool {
+        (!self.only_current_map
+            || category
+                .trails
+                .iter()
+                .any(|trail| trail.map_id == current_map_id))
+            && (!self.only_active || is_active)
+            && (!self.only_customized
+                || category.is_active.borrow().is_some()
+                || category.has_non_default_settings())
+    }
+}
+
+fn matches_search(category: &MarkerCategory, search: &str) -> bool {
+    category.label.to_lowercase().contains(search)
+        || category
+            .identifier
+            .join(".")
+            .to_lowercase()
+            .contains(search)
+}
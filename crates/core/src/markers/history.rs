@@ -0,0 +1,421 @@
+use std::collections::VecDeque;
+
+use log_err::LogErrOption;
+
+use crate::settings::{TrailColor, TrailOverride, TrailStyle, TrailWidth};
+
+use super::{MarkerCategory, MarkerCategoryTree};
+
+const MAX_UNDO_STEPS: usize = 100;
+
+#[derive(Clone, Debug, PartialEq)]
+struct MarkerCategoryState {
+    is_active: Option<bool>,
+    trail_color: Option<TrailColor>,
+    trail_width: Option<TrailWidth>,
+    trail_style: Option<TrailStyle>,
+    trail_overrides: Vec<TrailOverride>,
+}
+
+impl MarkerCategoryState {
+    fn take(category: &MarkerCategory) -> Self {
+        Self {
+            is_active: *category.is_active.borrow(),
+            trail_color: *category.trail_color.borrow(),
+            trail_width: *category.trail_width.borrow(),
+            trail_style: *category.trail_style.borrow(),
+            trail_overrides: category
+                .trails
+                .iter()
+                .map(|trail| *trail.overrides.borrow())
+                .collect(),
+        }
+    }
+
+    /// Compares without copying the overrides of the trails, since most categories do not change.
+    fn matches(&self, category: &MarkerCategory) -> bool {
+        self.is_active == *category.is_active.borrow()
+            && self.trail_color == *category.trail_color.borrow()
+            && self.trail_width == *category.trail_width.borrow()
+            && self.trail_style == *category.trail_style.borrow()
+            && self.trail_overrides.len() == category.trails.len()
+            && self
+                .trail_overrides
+                .iter()
+                .zip(&category.trails)
+                .all(|(overrides, trail)| *overrides == *trail.overrides.borrow())
+    }
+
+    fn restore(&self, category: &MarkerCategory) {
+        *category.is_active.borrow_mut() = self.is_active;
+        *category.trail_color.borrow_mut() = self.trail_color;
+        *category.trail_width.borrow_mut() = self.trail_width;
+        *category.trail_style.borrow_mut() = self.trail_style;
+
+        for (trail, overrides) in category.trails.iter().zip(&self.trail_overrides) {
+            *trail.overrides.borrow_mut() = *overrides;
+        }
+    }
+}
+
+/// A category changed by a single step, by its index in pre-order.
+#[derive(Debug)]
+struct MarkerCategoryChange {
+    idx: usize,
+    before: MarkerCategoryState,
+    after: MarkerCategoryState,
+}
+
+/// The settings of all categories of a tree in pre-order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarkerCategoryTreeSnapshot {
+    tree_id: u64,
+    states: Vec<MarkerCategoryState>,
+}
+
+impl MarkerCategoryTreeSnapshot {
+    pub fn take(tree: &MarkerCategoryTree) -> Self {
+        Self {
+            tree_id: tree.id(),
+            states: tree
+                .tree
+                .root()
+                .log_unwrap()
+                .traverse_pre_order()
+                .map(|node| MarkerCategoryState::take(node.data()))
+                .collect(),
+        }
+    }
+
+    /// Returns false if the snapshot was taken of a different tree.
+    pub fn restore(&self, tree: &MarkerCategoryTree) -> bool {
+        if tree.id() != self.tree_id {
+            return false;
+        }
+
+        for (node, state) in tree
+            .tree
+            .root()
+            .log_unwrap()
+            .traverse_pre_order()
+            .zip(&self.states)
+        {
+            state.restore(node.data());
+        }
+
+        true
+    }
+
+    /// Brings the snapshot up to date with the tree and returns the categories that changed, or `None` if
+    /// the snapshot was taken of a different tree.
+    fn update(&mut self, tree: &MarkerCategoryTree) -> Option<Vec<MarkerCategoryChange>> {
+        if tree.id() != self.tree_id {
+            return None;
+        }
+
+        let mut changes = vec![];
+
+        for (idx, (node, state)) in tree
+            .tree
+            .root()
+            .log_unwrap()
+            .traverse_pre_order()
+            .zip(&mut self.states)
+            .enumerate()
+        {
+            if !state.matches(node.data()) {
+                let after = MarkerCategoryState::take(node.data());
+                let before = std::mem::replace(state, after.clone());
+
+                changes.push(MarkerCategoryChange { idx, before, after });
+            }
+        }
+
+        Some(changes)
+    }
+
+    /// Applies the changes to the tree and the snapshot, backwards for undoing them. Returns false if the
+    /// snapshot was taken of a different tree.
+    fn apply(
+        &mut self,
+        tree: &MarkerCategoryTree,
+        changes: &[MarkerCategoryChange],
+        backwards: bool,
+    ) -> bool {
+        if tree.id() != self.tree_id {
+            return false;
+        }
+
+        // The changes are ordered by their index, so the tree is traversed once.
+        let mut nodes = tree
+            .tree
+            .root()
+            .log_unwrap()
+            .traverse_pre_order()
+            .enumerate();
+
+        for change in changes {
+            let Some((_, node)) = nodes.find(|(idx, _)| *idx == change.idx) else {
+                return false;
+            };
+
+            let state = if backwards {
+                &change.before
+            } else {
+                &change.after
+            };
+
+            state.restore(node.data());
+
+            if let Some(snapshot_state) = self.states.get_mut(change.idx) {
+                *snapshot_state = state.clone();
+            }
+        }
+
+        true
+    }
+}
+
+/// Undo and redo for the changes of the active states, colors, widths and styles of the categories and the
+/// overrides of their trails. Each change of the active preset starts a new history, because the restored
+/// states would end up in the wrong preset otherwise. Only the current state is kept for the whole tree,
+/// while each step keeps the categories it changed.
+#[derive(Debug, Default)]
+pub struct MarkerCategoryHistory {
+    preset: Option<String>,
+    generation: Option<u64>,
+    current: Option<MarkerCategoryTreeSnapshot>,
+    undo_stack: VecDeque<Vec<MarkerCategoryChange>>,
+    redo_stack: Vec<Vec<MarkerCategoryChange>>,
+}
+
+impl MarkerCategoryHistory {
+    /// Records the changes of the tree since the last call. The generation of the active marker categories
+    /// avoids comparing the whole tree if nothing changed.
+    pub fn observe(&mut self, tree: &MarkerCategoryTree, preset: &str, generation: u64) {
+        if self.generation == Some(generation) {
+            return;
+        }
+
+        self.generation = Some(generation);
+
+        let changes = match &mut self.current {
+            Some(current) if self.preset.as_deref() == Some(preset) => current.update(tree),
+            _ => None,
+        };
+
+        let Some(changes) = changes else {
+            self.clear();
+
+            self.generation = Some(generation);
+            self.preset = Some(preset.to_owned());
+            self.current = Some(MarkerCategoryTreeSnapshot::take(tree));
+
+            return;
+        };
+
+        if changes.is_empty() {
+            return;
+        }
+
+        if self.undo_stack.len() == MAX_UNDO_STEPS {
+            self.undo_stack.pop_front();
+        }
+
+        self.undo_stack.push_back(changes);
+        self.redo_stack.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Returns whether the tree was changed.
+    pub fn undo(&mut self, tree: &MarkerCategoryTree) -> bool {
+        let Some(changes) = self.undo_stack.pop_back() else {
+            return false;
+        };
+
+        if !self.apply(tree, &changes, true) {
+            return false;
+        }
+
+        self.redo_stack.push(changes);
+
+        true
+    }
+
+    /// Returns whether the tree was changed.
+    pub fn redo(&mut self, tree: &MarkerCategoryTree) -> bool {
+        let Some(changes) = self.redo_stack.pop() else {
+            return false;
+        };
+
+        if !self.apply(tree, &changes, false) {
+            return false;
+        }
+
+        self.undo_stack.push_back(changes);
+
+        true
+    }
+
+    fn apply(
+        &mut self,
+        tree: &MarkerCategoryTree,
+        changes: &[MarkerCategoryChange],
+        backwards: bool,
+    ) -> bool {
+        let applied = self
+            .current
+            .as_mut()
+            .is_some_and(|current| current.apply(tree, changes, backwards));
+
+        if !applied {
+            self.clear();
+        }
+
+        applied
+    }
+
+    /// Forgets all changes, for example because the tree is loaded again.
+    pub fn clear(&mut self) {
+        self.preset = None;
+        self.generation = None;
+        self.current = None;
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        markers::{set_category_active, MarkerCategoryTreeNode},
+        settings::TrailColor,
+    };
+
+    use super::*;
+
+    fn category<'a>(tree: &'a MarkerCategoryTree, id: &str) -> MarkerCategoryTreeNode<'a> {
+        let identifier = id.split('.').map(str::to_owned).collect::<Vec<_>>();
+
+        tree.find(&identifier).unwrap()
+    }
+
+    fn is_active(tree: &MarkerCategoryTree, id: &str) -> Option<bool> {
+        *category(tree, id).data().is_active.borrow()
+    }
+
+    #[test]
+    fn records_only_the_changed_categories() {
+        let tree = MarkerCategoryTree::with_categories(&["a.b", "a.c", "d"]);
+        let mut history = MarkerCategoryHistory::default();
+
+        history.observe(&tree, "Default", 0);
+        assert!(!history.can_undo());
+
+        set_category_active(&category(&tree, "a.b"), false);
+        *category(&tree, "d").data().trail_color.borrow_mut() =
+            Some(TrailColor::from_rgb([1, 2, 3]));
+        history.observe(&tree, "Default", 1);
+
+        let changed_ids = history.undo_stack[0]
+            .iter()
+            .map(|change| change.idx)
+            .collect::<Vec<_>>();
+
+        // The root, a, a.b, a.c and d in pre-order.
+        assert_eq!(changed_ids, [2, 4]);
+    }
+
+    #[test]
+    fn undoes_and_redoes_changes() {
+        let tree = MarkerCategoryTree::with_categories(&["a.b", "a.c", "d"]);
+        let mut history = MarkerCategoryHistory::default();
+
+        history.observe(&tree, "Default", 0);
+
+        set_category_active(&category(&tree, "a.b"), false);
+        history.observe(&tree, "Default", 1);
+
+        category(&tree, "d").data().trails[0]
+            .overrides
+            .borrow_mut()
+            .hidden = true;
+        history.observe(&tree, "Default", 2);
+
+        assert!(history.undo(&tree));
+        assert!(
+            !category(&tree, "d").data().trails[0]
+                .overrides
+                .borrow()
+                .hidden
+        );
+        assert_eq!(is_active(&tree, "a.b"), Some(false));
+
+        assert!(history.undo(&tree));
+        assert_eq!(is_active(&tree, "a.b"), None);
+        assert!(!history.can_undo());
+
+        // Observing the restored state does not record another step.
+        history.observe(&tree, "Default", 3);
+        assert!(history.can_redo());
+
+        assert!(history.redo(&tree));
+        assert!(history.redo(&tree));
+        assert_eq!(is_active(&tree, "a.b"), Some(false));
+        assert!(
+            category(&tree, "d").data().trails[0]
+                .overrides
+                .borrow()
+                .hidden
+        );
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn new_changes_discard_the_redo_steps() {
+        let tree = MarkerCategoryTree::with_categories(&["a", "b"]);
+        let mut history = MarkerCategoryHistory::default();
+
+        history.observe(&tree, "Default", 0);
+
+        set_category_active(&category(&tree, "a"), false);
+        history.observe(&tree, "Default", 1);
+
+        history.undo(&tree);
+
+        set_category_active(&category(&tree, "b"), false);
+        history.observe(&tree, "Default", 2);
+
+        assert!(!history.can_redo());
+        assert!(history.undo(&tree));
+        assert_eq!(is_active(&tree, "a"), None);
+        assert_eq!(is_active(&tree, "b"), None);
+    }
+
+    #[test]
+    fn starts_over_for_another_preset_or_tree() {
+        let tree = MarkerCategoryTree::with_categories(&["a"]);
+        let mut history = MarkerCategoryHistory::default();
+
+        history.observe(&tree, "Default", 0);
+        set_category_active(&category(&tree, "a"), false);
+        history.observe(&tree, "Default", 1);
+
+        history.observe(&tree, "Other", 2);
+        assert!(!history.can_undo());
+
+        set_category_active(&category(&tree, "a"), true);
+        history.observe(&tree, "Other", 3);
+
+        let reloaded = MarkerCategoryTree::with_categories(&["a"]);
+        assert!(!history.undo(&reloaded));
+        assert!(!history.can_undo());
+    }
+}
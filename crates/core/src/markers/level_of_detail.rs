@@ -0,0 +1,112 @@
+use crate::{
+    points::{Dimensions, Point3},
+    settings::TrailSimplifier,
+};
+
+use super::{simplify_line_string, simplify_line_string_by_area};
+
+pub const LEVEL_OF_DETAIL_COUNT: usize = 8;
+
+/// Each level allows deviations this many times as large as the level before.
+const LEVEL_OF_DETAIL_FACTOR: f32 = 4.0;
+
+pub fn simplify_trail(
+    points: &[Point3],
+    simplifier: TrailSimplifier,
+    epsilon: f32,
+    dimensions: Dimensions,
+) -> Vec<Point3> {
+    match simplifier {
+        TrailSimplifier::RamerDouglasPeucker => simplify_line_string(points, epsilon, dimensions),
+        TrailSimplifier::VisvalingamWhyatt => {
+            simplify_line_string_by_area(points, epsilon, dimensions)
+        }
+    }
+}
+
+/// The largest deviation from the original trail allowed on the level.
+pub fn level_of_detail_tolerance(epsilon: f32, level: usize) -> f32 {
+    epsilon * LEVEL_OF_DETAIL_FACTOR.powi(level as i32)
+}
+
+/// The trail simplified for each level, starting with the most detailed one. Each level is simplified from
+/// the original points, since the deviations would add up if it was simplified from the level before.
+pub fn build_levels_of_detail(
+    points: &[Point3],
+    simplifier: TrailSimplifier,
+    epsilon: f32,
+    dimensions: Dimensions,
+) -> Vec<Vec<Point3>> {
+    (0..LEVEL_OF_DETAIL_COUNT)
+        .map(|level| {
+            simplify_trail(
+                points,
+                simplifier,
+                level_of_detail_tolerance(epsilon, level),
+                dimensions,
+            )
+        })
+        .collect()
+}
+
+/// Picks the least detailed level whose deviations stay below half a pixel. `pixel_size` is the length of a
+/// pixel in the units of the trail.
+pub fn level_of_detail(pixel_size: f32, epsilon: f32) -> usize {
+    (0..LEVEL_OF_DETAIL_COUNT)
+        .rev()
+        .find(|level| level_of_detail_tolerance(epsilon, *level) <= pixel_size / 2.0)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn distance_to_segment(point: &Point3, start: &Point3, end: &Point3) -> f32 {
+        let segment = end - start;
+        let t = if segment.norm_squared() > 0.0 {
+            ((point - start).dot(&segment) / segment.norm_squared()).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        (point - (start + segment * t)).norm()
+    }
+
+    #[test]
+    fn every_level_stays_within_its_tolerance_of_the_trail() {
+        let points = (0..500)
+            .map(|i| {
+                let i = i as f32;
+                Point3::new(i * 0.4, (i * 0.2).sin() * 20.0, (i * 0.37).cos() * 0.5)
+            })
+            .collect::<Vec<_>>();
+
+        let epsilon = 0.01;
+
+        let levels = build_levels_of_detail(
+            &points,
+            TrailSimplifier::RamerDouglasPeucker,
+            epsilon,
+            Dimensions::Three,
+        );
+
+        assert_eq!(levels.len(), LEVEL_OF_DETAIL_COUNT);
+
+        for (level, level_points) in levels.iter().enumerate() {
+            let tolerance = level_of_detail_tolerance(epsilon, level);
+
+            for point in &points {
+                let distance = level_points
+                    .windows(2)
+                    .map(|segment| distance_to_segment(point, &segment[0], &segment[1]))
+                    .fold(f32::INFINITY, f32::min);
+
+                assert!(
+                    distance <= tolerance * 1.001,
+                    "level {level} deviates by {distance}, more than {tolerance}"
+                );
+            }
+        }
+    }
+}
@@ -1,19 +1,45 @@
 mod active;
+mod filter;
+mod history;
+mod level_of_detail;
 mod packs;
 mod parse_trail;
 mod ramer_douglas_peucker;
+mod segments;
+#[cfg(test)]
+mod test_trails;
+mod trail_id;
+mod trail_preparation;
 mod tree;
+mod visvalingam_whyatt;
 mod xml;
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
 use crate::points::Point3;
-use crate::settings::{TrailColor, TrailWidth};
+use crate::settings::{TrailColor, TrailOverride, TrailStyle, TrailWidth};
 
 pub use self::active::*;
+pub use self::filter::MarkerCategoryFilter;
+pub use self::history::{MarkerCategoryHistory, MarkerCategoryTreeSnapshot};
+pub use self::level_of_detail::{
+    build_levels_of_detail, level_of_detail, level_of_detail_tolerance, simplify_trail,
+    LEVEL_OF_DETAIL_COUNT,
+};
 pub use self::parse_trail::parse_trail;
 pub use self::ramer_douglas_peucker::simplify_line_string;
-pub use self::tree::{MarkerCategoryTree, MarkerCategoryTreeNode, NodeId};
+pub use self::segments::split_trail;
+pub use self::trail_id::TrailId;
+pub use self::trail_preparation::{
+    place_arrows, prepare_trail, ArrowPlacement, PreparedTrail, PreparedTrailLevel,
+    TrailPreparationParams, TrailPreparationWorker,
+};
+pub use self::tree::{
+    effective_trail_color, effective_trail_style, effective_trail_width, reset_category_settings,
+    reset_subtree_overrides, set_category_active, set_subtree_active, MarkerCategoryTree,
+    MarkerCategoryTreeNode, NodeId,
+};
+pub use self::visvalingam_whyatt::simplify_line_string_by_area;
 
 #[derive(Debug)]
 pub struct MarkerCategory {
@@ -25,6 +51,16 @@ pub struct MarkerCategory {
     pub trails: Vec<Trail>,
     pub trail_color: RefCell<Option<TrailColor>>,
     pub trail_width: RefCell<Option<TrailWidth>>,
+    pub trail_style: RefCell<Option<TrailStyle>>,
+    /// The opacity the pack gives to the trails of the category and its subcategories.
+    pub alpha: Option<f32>,
+
+    /// Including all descendants. Computed once after loading.
+    pub total_point_of_interest_count: usize,
+    pub total_trail_count: usize,
+
+    /// Updated by `MarkerCategoryTree::update_subtree_active_states`.
+    pub subtree_active_state: Cell<SubtreeActiveState>,
 }
 
 impl MarkerCategory {
@@ -38,6 +74,11 @@ impl MarkerCategory {
             trails: vec![],
             trail_color: RefCell::new(None),
             trail_width: RefCell::new(None),
+            trail_style: RefCell::new(None),
+            alpha: None,
+            total_point_of_interest_count: 0,
+            total_trail_count: 0,
+            subtree_active_state: Cell::new(SubtreeActiveState::Inactive),
         }
     }
 
@@ -46,10 +87,24 @@ impl MarkerCategory {
     }
 
     pub fn has_non_default_settings(&self) -> bool {
-        self.trail_color.borrow().is_some() || self.trail_width.borrow().is_some()
+        self.trail_color.borrow().is_some()
+            || self.trail_width.borrow().is_some()
+            || self.trail_style.borrow().is_some()
+    }
+
+    pub fn has_content(&self) -> bool {
+        !self.points_of_interest.is_empty() || !self.trails.is_empty()
     }
 }
 
+/// Whether the categories with content in a subtree are active.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SubtreeActiveState {
+    Active,
+    Inactive,
+    Mixed,
+}
+
 #[derive(Debug)]
 pub struct PointOfInterest {
     // TODO
@@ -57,12 +112,28 @@ pub struct PointOfInterest {
 
 #[derive(Debug)]
 pub struct Trail {
+    pub id: TrailId,
+    /// The normalized name of the file in the pack.
+    pub file_name: String,
     pub map_id: u32,
     pub points: Vec<Point3>,
+    /// The opacity the pack gives to the trail. Takes precedence over the one of its category.
+    pub alpha: Option<f32>,
+    pub overrides: RefCell<TrailOverride>,
+}
+
+impl Trail {
+    /// The name of the file without its directories.
+    pub fn label(&self) -> &str {
+        self.file_name
+            .rsplit_once('/')
+            .map_or(self.file_name.as_str(), |(_, name)| name)
+    }
 }
 
 #[derive(Debug)]
 pub struct TrailDescription {
     pub category_id_path: Vec<String>,
     pub binary_file_name: String,
+    pub alpha: Option<f32>,
 }
@@ -1,39 +1,56 @@
 #[cfg(debug_assertions)]
 use std::time::Instant;
 use std::{
+    cell::RefCell,
     collections::HashMap,
-    fs::File,
+    fs::{read, read_dir, File},
     io::{BufRead, BufReader, Read, Seek},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 use log::{debug, error, warn};
-use log_err::{LogErrOption, LogErrResult};
+use log_err::LogErrOption;
 use xml::{reader::XmlEvent, EventReader};
 use zip::ZipArchive;
 
+use crate::settings::TrailOverride;
+
 use super::{
     parse_trail,
     tree::{ensure_category_path, MarkerCategoryTree},
     xml::{marker_category_from_xml, trail_description_from_xml},
-    MarkerCategory, Trail,
+    MarkerCategory, Trail, TrailId,
 };
 
+/// Larger trails are read as well, but their memory is not reserved upfront.
+const MAX_RESERVED_TRAIL_SIZE: u64 = 16 * 1024 * 1024;
+
 impl MarkerCategoryTree {
+    /// Loads a zipped pack, like a `.taco` file.
     pub fn load_marker_pack_from_path(&mut self, path: &Path) {
-        debug!(
-            "loading marker categories from {}",
-            path.to_str().log_unwrap()
-        );
+        debug!("loading marker categories from {}", path.display());
+
+        // Pack folders are configured by the user and may contain other archives.
+        let mut zip = match File::open(path).map(BufReader::new).map(ZipArchive::new) {
+            Ok(Ok(zip)) => zip,
+
+            Ok(Err(err)) => {
+                warn!("could not read pack {}: {err}", path.display());
+                return;
+            }
 
-        let file = File::open(path).log_expect("could not open file");
-        let mut zip =
-            ZipArchive::new(BufReader::new(file)).log_expect("could not create zip reader");
+            Err(err) => {
+                warn!("could not open pack {}: {err}", path.display());
+                return;
+            }
+        };
 
         #[cfg(debug_assertions)]
         let now = Instant::now();
 
-        let mut trails = parse_all_trails(&mut zip);
+        let pack_name = pack_name(path);
+
+        let mut trails = parse_all_trails(&mut zip, &pack_name);
 
         #[cfg(debug_assertions)]
         debug!(
@@ -46,7 +63,14 @@ impl MarkerCategoryTree {
         let now = Instant::now();
 
         for i in 0..zip.len() {
-            let file = zip.by_index(i).log_unwrap();
+            let file = match zip.by_index(i) {
+                Ok(file) => file,
+
+                Err(err) => {
+                    warn!("could not read file {i} of pack {}: {err}", path.display());
+                    continue;
+                }
+            };
 
             if !file.name().ends_with(".xml") {
                 continue;
@@ -64,25 +88,121 @@ impl MarkerCategoryTree {
             now.elapsed().as_millis(),
         );
     }
+
+    /// Loads a pack whose files are not zipped, like the XML files in the POIs folder of TacO. The XML files
+    /// are read from the directory itself, while trails are also read from subdirectories which are not packs
+    /// on their own.
+    pub fn load_marker_pack_from_dir(&mut self, dir: &Path) {
+        debug!("loading marker categories from {}", dir.display());
+
+        let pack_name = pack_name(dir);
+
+        let mut trail_file_paths = vec![];
+        collect_trail_file_paths(dir, &mut trail_file_paths);
+
+        let mut trails = HashMap::new();
+
+        for path in trail_file_paths {
+            let Ok(relative_path) = path.strip_prefix(dir) else {
+                continue;
+            };
+
+            let normalized_name = normalize_file_name(&relative_path.to_string_lossy());
+
+            match read(&path) {
+                Ok(bytes) => {
+                    if let Some(trail) = parse_trail_file(&pack_name, &normalized_name, &bytes) {
+                        trails.insert(normalized_name, trail);
+                    }
+                }
+
+                Err(err) => warn!("could not read trail {}: {err}", path.display()),
+            }
+        }
+
+        for path in files_in_dir(dir) {
+            if !has_extension(&path, "xml") {
+                continue;
+            }
+
+            match File::open(&path) {
+                Ok(file) => {
+                    read_xml_file(EventReader::new(BufReader::new(file)), self, &mut trails)
+                }
+                Err(err) => warn!("could not open {}: {err}", path.display()),
+            }
+        }
+    }
+}
+
+/// Whether the directory directly contains XML files, which makes it an unzipped pack.
+pub fn is_marker_pack_dir(dir: &Path) -> bool {
+    files_in_dir(dir).any(|path| has_extension(&path, "xml"))
+}
+
+pub fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+fn files_in_dir(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+}
+
+fn collect_trail_file_paths(dir: &Path, paths: &mut Vec<PathBuf>) {
+    for entry in read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            if !is_marker_pack_dir(&path) {
+                collect_trail_file_paths(&path, paths);
+            }
+        } else if has_extension(&path, "trl") {
+            paths.push(path);
+        }
+    }
 }
 
-fn parse_all_trails<R: Read + Seek>(zip: &mut ZipArchive<R>) -> HashMap<String, Trail> {
+fn pack_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn parse_all_trails<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    pack_name: &str,
+) -> HashMap<String, Trail> {
     let mut trails = HashMap::new();
 
     for idx in 0..zip.len() {
-        let mut file = zip
-            .by_index(idx)
-            .log_expect("zip archive does not contain file by index???");
+        let mut file = match zip.by_index(idx) {
+            Ok(file) => file,
+
+            Err(err) => {
+                warn!("could not read file {idx} of pack {pack_name}: {err}");
+                continue;
+            }
+        };
 
         let normalized_name = normalize_file_name(file.name());
 
         if normalized_name.ends_with(".trl") {
             let mut bytes = Vec::new();
-            bytes.reserve_exact(file.size() as usize);
-            file.read_to_end(&mut bytes)
-                .log_expect("could not read binary trail data");
+            // The size is read from the archive, which may be damaged.
+            bytes.reserve_exact(file.size().min(MAX_RESERVED_TRAIL_SIZE) as usize);
 
-            if let Ok((_, trail)) = parse_trail(&bytes) {
+            if let Err(err) = file.read_to_end(&mut bytes) {
+                warn!("could not read trail {normalized_name} of pack {pack_name}: {err}");
+                continue;
+            }
+
+            if let Some(trail) = parse_trail_file(pack_name, &normalized_name, &bytes) {
                 trails.insert(normalized_name, trail);
             }
         }
@@ -91,6 +211,19 @@ fn parse_all_trails<R: Read + Seek>(zip: &mut ZipArchive<R>) -> HashMap<String,
     trails
 }
 
+fn parse_trail_file(pack_name: &str, normalized_name: &str, bytes: &[u8]) -> Option<Trail> {
+    let (_, (map_id, points)) = parse_trail(bytes).ok()?;
+
+    Some(Trail {
+        id: TrailId::new(pack_name, normalized_name, bytes),
+        file_name: normalized_name.to_owned(),
+        map_id,
+        points,
+        alpha: None,
+        overrides: RefCell::new(TrailOverride::default()),
+    })
+}
+
 fn read_xml_file<R: BufRead>(
     mut parser: EventReader<R>,
     tree: &mut MarkerCategoryTree,
@@ -123,13 +256,21 @@ fn read_xml_file<R: BufRead>(
                         let identifier = category.identifier.clone();
                         let label = category.label.clone();
                         let is_separator = category.is_separator;
+                        let alpha = category.alpha;
 
                         current_parent_node_id = ensure_category_path(
                             &mut tree.tree,
                             current_parent_node_id,
                             &[category.identifier.last().log_unwrap().clone()],
                             |_| {
-                                MarkerCategory::new(identifier.clone(), label.clone(), is_separator)
+                                let mut category = MarkerCategory::new(
+                                    identifier.clone(),
+                                    label.clone(),
+                                    is_separator,
+                                );
+                                category.alpha = alpha;
+
+                                category
                             },
                         );
                         current_parent_path = identifier;
@@ -145,9 +286,10 @@ fn read_xml_file<R: BufRead>(
                         // TODO: Is it ok to just skip this subtree?
                         // We could not create and thus insert a category. So we have nothing to insert to
                         // and cannot get the parent when visiting the end tag.
-                        parser
-                            .skip()
-                            .log_expect(&format!("error while skipping marker category sub tree (marker category tag was invalid: {:?})", err));
+                        if let Err(skip_err) = parser.skip() {
+                            warn!("could not skip invalid marker category ({err:?}): {skip_err}");
+                            break;
+                        }
 
                         go_to_parent = false;
                     }
@@ -186,7 +328,9 @@ fn read_xml_file<R: BufRead>(
                         let normalized_file_name =
                             normalize_file_name(&trail_description.binary_file_name);
 
-                        if let Some(trail) = trails.remove(&normalized_file_name) {
+                        if let Some(mut trail) = trails.remove(&normalized_file_name) {
+                            trail.alpha = trail_description.alpha;
+
                             let path = trail_description.category_id_path.as_slice();
 
                             let root_id = tree.tree.root_id().log_unwrap();
@@ -220,3 +364,118 @@ fn read_xml_file<R: BufRead>(
 fn normalize_file_name(file_name: &str) -> String {
     file_name.to_lowercase().replace('\\', "/")
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{create_dir_all, remove_dir_all, write},
+        io::{Cursor, Write},
+    };
+
+    use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+    use super::*;
+
+    fn trail_bytes(map_id: u32) -> Vec<u8> {
+        [
+            0,
+            map_id,
+            0,
+            0,
+            0,
+            1.0f32.to_bits(),
+            2.0f32.to_bits(),
+            3.0f32.to_bits(),
+        ]
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+    }
+
+    fn pack_xml(category: &str, trail_data: &str) -> String {
+        format!(
+            r#"<OverlayData><MarkerCategory name="{category}"/><POIs><Trail type="{category}" trailData="{trail_data}"/></POIs></OverlayData>"#
+        )
+    }
+
+    #[test]
+    fn loads_unzipped_packs() {
+        let dir = std::env::temp_dir().join(format!("paths-unzipped-packs-{}", std::process::id()));
+        create_dir_all(dir.join("Data")).unwrap();
+        create_dir_all(dir.join("Other")).unwrap();
+
+        write(dir.join("loose.xml"), pack_xml("loose", "Data\\Trail.trl")).unwrap();
+        write(dir.join("Data/Trail.trl"), trail_bytes(15)).unwrap();
+
+        write(dir.join("Other/other.xml"), pack_xml("other", "trail.trl")).unwrap();
+        write(dir.join("Other/trail.trl"), trail_bytes(50)).unwrap();
+
+        let tree = MarkerCategoryTree::from_all_packs_in_dirs(&[&dir]);
+
+        remove_dir_all(&dir).unwrap();
+
+        assert_eq!(tree.trail_count, 2);
+
+        let loose = tree.find(&["loose".to_owned()]).unwrap();
+        assert_eq!(loose.data().trails[0].map_id, 15);
+
+        let other = tree.find(&["other".to_owned()]).unwrap();
+        assert_eq!(other.data().trails[0].map_id, 50);
+    }
+
+    fn zipped_pack(category: &str, map_id: u32) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(vec![]));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+        zip.start_file("pack.xml", options).unwrap();
+        zip.write_all(pack_xml(category, "trail.trl").as_bytes())
+            .unwrap();
+
+        zip.start_file("trail.trl", options).unwrap();
+        zip.write_all(&trail_bytes(map_id)).unwrap();
+
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn skips_damaged_archives() {
+        let dir = std::env::temp_dir().join(format!("paths-damaged-packs-{}", std::process::id()));
+        create_dir_all(&dir).unwrap();
+
+        write(dir.join("valid.taco"), zipped_pack("valid", 15)).unwrap();
+
+        let truncated = zipped_pack("truncated", 20);
+        write(
+            dir.join("truncated.taco"),
+            &truncated[..truncated.len() / 2],
+        )
+        .unwrap();
+
+        // The checksum of the trail does not match anymore.
+        let mut corrupt = zipped_pack("corrupt", 30);
+        let trail = trail_bytes(30);
+        let trail_idx = corrupt
+            .windows(trail.len())
+            .position(|window| window == trail)
+            .unwrap();
+        corrupt[trail_idx + 4] ^= 0xFF;
+        write(dir.join("corrupt.zip"), corrupt).unwrap();
+
+        write(dir.join("not a pack.zip"), b"text").unwrap();
+
+        let tree = MarkerCategoryTree::from_all_packs_in_dirs(&[&dir]);
+
+        remove_dir_all(&dir).unwrap();
+
+        let valid = tree.find(&["valid".to_owned()]).unwrap();
+        assert_eq!(valid.data().trails[0].map_id, 15);
+
+        assert!(tree.find(&["truncated".to_owned()]).is_none());
+
+        // The categories of the damaged archive are still loaded, just without the trail.
+        let corrupt = tree.find(&["corrupt".to_owned()]).unwrap();
+        assert!(corrupt.data().trails.is_empty());
+
+        assert_eq!(tree.trail_count, 1);
+    }
+}
@@ -8,8 +8,6 @@ use nom::{
 
 use crate::points::Point3;
 
-use super::Trail;
-
 fn parse_u32(input: &[u8]) -> IResult<&[u8], u32> {
     le_u32(input)
 }
@@ -32,9 +30,7 @@ fn parse_point(input: &[u8]) -> IResult<&[u8], Point3> {
         .parse(input)
 }
 
-pub fn parse_trail(input: &[u8]) -> IResult<&[u8], Trail> {
-    parse_header
-        .and(many1(parse_point))
-        .map(|(map_id, points)| Trail { map_id, points })
-        .parse(input)
+/// Returns the map id and the points.
+pub fn parse_trail(input: &[u8]) -> IResult<&[u8], (u32, Vec<Point3>)> {
+    parse_header.and(many1(parse_point)).parse(input)
 }
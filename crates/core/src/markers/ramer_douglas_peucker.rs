@@ -1,60 +1,88 @@
-use crate::points::Point3;
+use crate::points::{distance_to_segment, Dimensions, Point3};
 
-pub fn simplify_line_string(points: &[Point3], epsilon: f32) -> Vec<Point3> {
-    let mut result = vec![points[0]];
+/// Keeps the points which deviate more than `epsilon` from the simplified line. Works without recursion, so
+/// long trails cannot overflow the stack.
+pub fn simplify_line_string(
+    points: &[Point3],
+    epsilon: f32,
+    dimensions: Dimensions,
+) -> Vec<Point3> {
+    if points.len() <= 2 {
+        return points.to_vec();
+    }
 
-    recurse(0, points.len() - 1, &points, epsilon, &mut result);
+    let projected_points = points
+        .iter()
+        .map(|point| dimensions.project(point))
+        .collect::<Vec<_>>();
 
-    result.push(points[points.len() - 1]);
+    let mut is_kept = vec![false; points.len()];
+    is_kept[0] = true;
+    is_kept[points.len() - 1] = true;
 
-    result
-}
+    let mut ranges = vec![(0, points.len() - 1)];
 
-fn recurse(
-    start_idx: usize,
-    end_idx: usize,
-    all_points: &[Point3],
-    epsilon: f32,
-    result: &mut Vec<Point3>,
-) {
-    if start_idx + 1 == end_idx {
-        // Only two points left. Skip.
-        return;
-    }
+    while let Some((start_idx, end_idx)) = ranges.pop() {
+        // Find the point farthest from the line between the start and end points.
+        let farthest = (start_idx + 1..end_idx)
+            .map(|idx| {
+                let dist = distance_to_segment(
+                    &projected_points[idx],
+                    &projected_points[start_idx],
+                    &projected_points[end_idx],
+                );
 
-    // Find point farthest from line between start and end points.
-    let mut farthest_idx = 0;
-    let mut farthest_distance = 0.0;
-    let line = (&all_points[start_idx], &all_points[end_idx]);
+                (idx, dist)
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
 
-    for idx in start_idx + 1..end_idx - 1 {
-        let dist = distance_between_point_and_line(&all_points[idx], line);
+        // Split the range if the farthest point is outside the epsilon.
+        if let Some((farthest_idx, farthest_distance)) = farthest {
+            if farthest_distance > epsilon {
+                is_kept[farthest_idx] = true;
 
-        if dist > farthest_distance {
-            farthest_idx = idx;
-            farthest_distance = dist;
+                ranges.push((start_idx, farthest_idx));
+                ranges.push((farthest_idx, end_idx));
+            }
         }
     }
 
-    // Recurse if the farthest point is outside the epsilon.
-    if farthest_distance > epsilon {
-        recurse(start_idx, farthest_idx, all_points, epsilon, result);
+    points
+        .iter()
+        .zip(is_kept)
+        .filter_map(|(point, is_kept)| is_kept.then_some(*point))
+        .collect()
+}
 
-        result.push(all_points[farthest_idx]);
+#[cfg(test)]
+mod tests {
+    use crate::markers::test_trails::check_simplifier_properties;
 
-        recurse(farthest_idx, end_idx, all_points, epsilon, result);
-    }
-}
+    use super::*;
 
-fn distance_between_point_and_line(point: &Point3, line: (&Point3, &Point3)) -> f32 {
-    // Thank you, wolfram alpha.
-    // https://mathworld.wolfram.com/Point-LineDistance3-Dimensional.html
+    #[test]
+    fn keeps_2d_properties() {
+        check_simplifier_properties(simplify_line_string, Dimensions::Two);
+    }
 
-    let x0 = point;
-    let (x1, x2) = line;
+    #[test]
+    fn keeps_3d_properties() {
+        check_simplifier_properties(simplify_line_string, Dimensions::Three);
+    }
 
-    let double_area = (x0 - x1).cross(&(x0 - x2)).norm();
-    let triangle_base_len = (x2 - x1).norm();
+    #[test]
+    fn keeps_the_farthest_point_of_loops() {
+        let points = [
+            Point3::origin(),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(5.0, 0.0, 0.0),
+            Point3::new(1.0, 0.1, 0.0),
+            Point3::origin(),
+        ];
 
-    double_area / triangle_base_len
+        assert_eq!(
+            simplify_line_string(&points, 0.5, Dimensions::Three),
+            [points[0], points[2], points[4]]
+        );
+    }
 }
@@ -0,0 +1,78 @@
+use nalgebra::distance;
+
+use crate::points::Point3;
+
+/// TacO separates the parts of a trail by a point at the origin.
+fn is_separator(point: &Point3) -> bool {
+    *point == Point3::origin()
+}
+
+/// Splits a trail at separators and wherever consecutive points are farther apart than `max_gap`, for
+/// example after a teleport. Only the horizontal distance counts, since a trail may climb or drop steeply
+/// without a jump, for example along a cliff or a jumping puzzle. Segments of a single point are dropped,
+/// since there is no line to draw.
+pub fn split_trail(points: &[Point3], max_gap: f32) -> impl Iterator<Item = &[Point3]> {
+    points
+        .split(is_separator)
+        .flat_map(move |part| part.chunk_by(move |a, b| distance(&a.xy(), &b.xy()) <= max_gap))
+        .filter(|segment| segment.len() >= 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(xs: &[f32]) -> Vec<Point3> {
+        xs.iter().map(|x| Point3::new(*x, 1.0, 0.0)).collect()
+    }
+
+    fn segments(points: &[Point3], max_gap: f32) -> Vec<Vec<Point3>> {
+        split_trail(points, max_gap).map(<[_]>::to_vec).collect()
+    }
+
+    #[test]
+    fn splits_at_separators() {
+        let mut points = line(&[1.0, 2.0]);
+        points.push(Point3::origin());
+        points.extend(line(&[3.0, 4.0, 5.0]));
+
+        assert_eq!(
+            segments(&points, 100.0),
+            [line(&[1.0, 2.0]), line(&[3.0, 4.0, 5.0])]
+        );
+    }
+
+    #[test]
+    fn splits_at_gaps() {
+        let points = line(&[1.0, 2.0, 50.0, 51.0, 52.0]);
+
+        assert_eq!(
+            segments(&points, 10.0),
+            [line(&[1.0, 2.0]), line(&[50.0, 51.0, 52.0])]
+        );
+    }
+
+    #[test]
+    fn keeps_points_exactly_at_the_threshold_together() {
+        let points = line(&[1.0, 11.0, 21.0]);
+
+        assert_eq!(segments(&points, 10.0), [points.clone()]);
+        assert_eq!(segments(&points, 9.99), Vec::<Vec<Point3>>::new());
+    }
+
+    #[test]
+    fn ignores_the_height_of_the_points() {
+        let points = [Point3::new(1.0, 1.0, 0.0), Point3::new(1.0, 2.0, 500.0)];
+
+        assert_eq!(segments(&points, 10.0), [points.to_vec()]);
+    }
+
+    #[test]
+    fn drops_single_points() {
+        let mut points = line(&[1.0]);
+        points.push(Point3::origin());
+        points.extend(line(&[2.0, 100.0, 101.0]));
+
+        assert_eq!(segments(&points, 10.0), [line(&[100.0, 101.0])]);
+    }
+}
@@ -0,0 +1,107 @@
+//! Generated trails and the properties every simplifier has to keep.
+
+use nalgebra::Vector3;
+
+use crate::points::{Dimensions, Point3};
+
+const EPSILONS: [f32; 4] = [0.0, 0.1, 1.0, 10.0];
+
+/// A linear congruential generator, so the generated trails are the same in every run.
+struct Random(u64);
+
+impl Random {
+    fn next_f32(&mut self) -> f32 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+
+        (self.0 >> 40) as f32 / (1 << 24) as f32
+    }
+
+    fn step(&mut self) -> f32 {
+        self.next_f32() * 10.0 - 5.0
+    }
+}
+
+/// Random walks of different lengths, which resemble trails more than scattered points.
+pub fn generated_trails() -> Vec<Vec<Point3>> {
+    let mut random = Random(42);
+
+    (0..200)
+        .map(|trail_idx| {
+            let mut point = Point3::origin();
+
+            (0..3 + trail_idx)
+                .map(|_| {
+                    point += Vector3::new(random.step(), random.step(), random.step());
+                    point
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn distance_to_segment(point: &Point3, start: &Point3, end: &Point3) -> f32 {
+    let segment = end - start;
+    let t = if segment.norm_squared() > 0.0 {
+        ((point - start).dot(&segment) / segment.norm_squared()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    (point - (start + segment * t)).norm()
+}
+
+/// The indices of the simplified points in the original ones, or `None` if they are not an ordered
+/// subsequence.
+fn kept_indices(points: &[Point3], simplified: &[Point3]) -> Option<Vec<usize>> {
+    let mut indices = vec![];
+    let mut remaining = points.iter().enumerate();
+
+    for kept in simplified {
+        let (idx, _) = remaining.find(|(_, point)| *point == kept)?;
+        indices.push(idx);
+    }
+
+    Some(indices)
+}
+
+/// The endpoints are kept, the result is an ordered subsequence of the points, and every dropped point lies
+/// within `epsilon` of the segment that replaces it. Up to two points are returned unchanged.
+pub fn check_simplifier_properties(
+    simplify: impl Fn(&[Point3], f32, Dimensions) -> Vec<Point3>,
+    dimensions: Dimensions,
+) {
+    let short = [Point3::new(1.0, 2.0, 3.0), Point3::new(4.0, 5.0, 6.0)];
+
+    for len in 0..=2 {
+        assert_eq!(simplify(&short[..len], 100.0, dimensions), &short[..len]);
+    }
+
+    for points in generated_trails() {
+        for epsilon in EPSILONS {
+            let simplified = simplify(&points, epsilon, dimensions);
+
+            assert_eq!(simplified.first(), points.first());
+            assert_eq!(simplified.last(), points.last());
+
+            let indices = kept_indices(&points, &simplified)
+                .expect("the simplified points are an ordered subsequence");
+
+            for kept in indices.windows(2) {
+                let start = dimensions.project(&points[kept[0]]);
+                let end = dimensions.project(&points[kept[1]]);
+
+                for point in &points[kept[0] + 1..kept[1]] {
+                    let distance = distance_to_segment(&dimensions.project(point), &start, &end);
+
+                    assert!(
+                        distance <= epsilon,
+                        "dropped point is {distance} away, more than {epsilon}"
+                    );
+                }
+            }
+        }
+    }
+}
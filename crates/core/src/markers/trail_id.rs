@@ -0,0 +1,53 @@
+use std::fmt::{self, Display};
+use std::num::ParseIntError;
+
+use serde::{Deserialize, Serialize};
+
+/// Identifies a trail by its pack, its file and its content. Unlike the identifier of its category, it
+/// differs between the trails of a category and stays the same across sessions, so settings can refer to it.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct TrailId(pub u64);
+
+impl TrailId {
+    pub fn new(pack_name: &str, file_name: &str, content: &[u8]) -> Self {
+        // FNV-1a, because the hashers of the standard library may change between releases.
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        let hash = [
+            pack_name.as_bytes(),
+            &[0],
+            file_name.as_bytes(),
+            &[0],
+            content,
+        ]
+        .into_iter()
+        .flatten()
+        .fold(OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+        });
+
+        Self(hash)
+    }
+}
+
+impl Display for TrailId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl From<TrailId> for String {
+    fn from(id: TrailId) -> Self {
+        id.to_string()
+    }
+}
+
+impl TryFrom<String> for TrailId {
+    type Error = ParseIntError;
+
+    fn try_from(id: String) -> Result<Self, Self::Error> {
+        u64::from_str_radix(&id, 16).map(Self)
+    }
+}
@@ -0,0 +1,321 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use log::warn;
+use log_err::LogErrResult;
+use nalgebra::{distance, Point2};
+
+use crate::{
+    points::{Dimensions, Point3},
+    settings::TrailSimplifier,
+};
+
+use super::{build_levels_of_detail, split_trail, TrailId, LEVEL_OF_DETAIL_COUNT};
+
+/// Everything besides the points that changes how a trail is prepared.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrailPreparationParams {
+    pub simplifier: TrailSimplifier,
+    pub epsilon: f32,
+    pub max_gap: f32,
+    pub dimensions: Dimensions,
+    pub reversed: bool,
+    pub arrow_spacing: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArrowPlacement {
+    pub position: Point2<f32>,
+    /// The direction the arrow points to in radians.
+    pub direction: f32,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PreparedTrailLevel {
+    pub segments: Vec<Vec<Point3>>,
+    pub arrows: Vec<ArrowPlacement>,
+}
+
+/// A trail ready to be turned into geometries.
+#[derive(Debug)]
+pub struct PreparedTrail {
+    pub params: TrailPreparationParams,
+    /// From the most to the least detailed level.
+    pub levels: Vec<PreparedTrailLevel>,
+}
+
+/// Each segment is simplified on its own, so the gaps between them stay open. The arrows point in the
+/// direction of the points, so the segments of reversed trails and their points are reversed afterwards.
+pub fn prepare_trail(points: &[Point3], params: TrailPreparationParams) -> PreparedTrail {
+    let mut levels_by_segment = split_trail(points, params.max_gap)
+        .map(|segment| {
+            build_levels_of_detail(
+                segment,
+                params.simplifier,
+                params.epsilon,
+                params.dimensions,
+            )
+            .into_iter()
+        })
+        .collect::<Vec<_>>();
+
+    if params.reversed {
+        levels_by_segment.reverse();
+    }
+
+    let levels = (0..LEVEL_OF_DETAIL_COUNT)
+        .map(|_| {
+            let segments = levels_by_segment
+                .iter_mut()
+                .filter_map(|levels| levels.next())
+                .map(|mut points| {
+                    if params.reversed {
+                        points.reverse();
+                    }
+
+                    points
+                })
+                .collect::<Vec<_>>();
+
+            PreparedTrailLevel {
+                arrows: place_arrows(&segments, params.arrow_spacing),
+                segments,
+            }
+        })
+        .collect();
+
+    PreparedTrail { params, levels }
+}
+
+/// Each segment starts with an arrow, as if it was a trail of its own.
+pub fn place_arrows(segments: &[Vec<Point3>], arrow_spacing: f32) -> Vec<ArrowPlacement> {
+    let mut arrows = Vec::new();
+
+    for points in segments {
+        let mut last_point = None;
+        let mut distance_to_next_arrow = 0.0;
+
+        for (idx, point) in points.iter().enumerate() {
+            // Subtract the distance between the last and the current point.
+            if let Some(last_point) = last_point.replace(point) {
+                distance_to_next_arrow -= distance(last_point, point);
+            }
+
+            if distance_to_next_arrow <= 0.0 {
+                distance_to_next_arrow = arrow_spacing;
+
+                // Only place an arrow if there is another point left.
+                let Some(next_point) = points.get(idx + 1) else {
+                    continue;
+                };
+
+                arrows.push(ArrowPlacement {
+                    position: point.xy(),
+                    direction: (next_point.y - point.y).atan2(next_point.x - point.x),
+                });
+            }
+        }
+    }
+
+    arrows
+}
+
+struct TrailPreparationJob {
+    trail_id: TrailId,
+    points: Vec<Point3>,
+    params: TrailPreparationParams,
+}
+
+/// Prepares trails on a thread of its own, so enabling many trails at once does not stall rendering.
+pub struct TrailPreparationWorker {
+    jobs: Option<Sender<TrailPreparationJob>>,
+    results: Receiver<(TrailId, PreparedTrail)>,
+    /// The params of the latest job per trail which did not finish yet.
+    pending: HashMap<TrailId, TrailPreparationParams>,
+    /// Set when dropping the worker, since jobs that are already sent would still be received.
+    cancelled: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl TrailPreparationWorker {
+    pub fn new() -> Self {
+        let (jobs, job_receiver) = channel::<TrailPreparationJob>();
+        let (result_sender, results) = channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        let thread_cancelled = cancelled.clone();
+
+        let thread = thread::Builder::new()
+            .name("prepare_trails".to_owned())
+            .spawn(move || {
+                for job in job_receiver {
+                    if thread_cancelled.load(Ordering::Acquire) {
+                        break;
+                    }
+
+                    let prepared = prepare_trail(&job.points, job.params);
+
+                    if result_sender.send((job.trail_id, prepared)).is_err() {
+                        break;
+                    }
+                }
+            })
+            .log_unwrap();
+
+        Self {
+            jobs: Some(jobs),
+            results,
+            pending: HashMap::new(),
+            cancelled,
+            thread: Some(thread),
+        }
+    }
+
+    /// Does nothing if the same preparation is still pending.
+    pub fn request(
+        &mut self,
+        trail_id: TrailId,
+        points: &[Point3],
+        params: TrailPreparationParams,
+    ) {
+        if self.pending.get(&trail_id) == Some(&params) {
+            return;
+        }
+
+        let Some(jobs) = &self.jobs else {
+            return;
+        };
+
+        let job = TrailPreparationJob {
+            trail_id,
+            points: points.to_vec(),
+            params,
+        };
+
+        if jobs.send(job).is_ok() {
+            self.pending.insert(trail_id, params);
+        }
+    }
+
+    /// The trails prepared since the last call, including outdated ones whose params changed meanwhile.
+    pub fn finished(&mut self) -> Vec<(TrailId, PreparedTrail)> {
+        let finished = self.results.try_iter().collect::<Vec<_>>();
+
+        for (trail_id, prepared) in &finished {
+            if self.pending.get(trail_id) == Some(&prepared.params) {
+                self.pending.remove(trail_id);
+            }
+        }
+
+        finished
+    }
+}
+
+impl Default for TrailPreparationWorker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for TrailPreparationWorker {
+    fn drop(&mut self) {
+        // Ends the thread after its current job, without preparing the remaining ones.
+        self.cancelled.store(true, Ordering::Release);
+        self.jobs = None;
+
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                warn!("trail preparation thread panicked");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    fn params(reversed: bool) -> TrailPreparationParams {
+        TrailPreparationParams {
+            simplifier: TrailSimplifier::RamerDouglasPeucker,
+            epsilon: 0.01,
+            max_gap: 10.0,
+            dimensions: Dimensions::Three,
+            reversed,
+            arrow_spacing: 5.0,
+        }
+    }
+
+    /// Starts at x = 1, since points at the origin separate trails.
+    fn zigzag(start_x: f32) -> Vec<Point3> {
+        (1..6)
+            .map(|i| Point3::new(start_x + i as f32, (i % 2) as f32, 0.0))
+            .collect()
+    }
+
+    #[test]
+    fn reverses_segments_and_their_points() {
+        // Two segments, separated by a gap larger than `max_gap`.
+        let first = zigzag(0.0);
+        let second = zigzag(100.0);
+        let points = [first.clone(), second.clone()].concat();
+
+        let prepared = prepare_trail(&points, params(true));
+
+        let reversed = |points: &[Point3]| points.iter().rev().copied().collect::<Vec<_>>();
+
+        assert_eq!(
+            prepared.levels[0].segments,
+            [reversed(&second), reversed(&first)]
+        );
+        assert_eq!(prepared.levels.len(), LEVEL_OF_DETAIL_COUNT);
+    }
+
+    #[test]
+    fn prepares_requested_trails() {
+        let mut worker = TrailPreparationWorker::new();
+        let trail_id = TrailId(1);
+
+        worker.request(trail_id, &zigzag(0.0), params(false));
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut finished = vec![];
+
+        while finished.is_empty() && Instant::now() < deadline {
+            finished = worker.finished();
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].0, trail_id);
+        assert_eq!(finished[0].1.levels[0].segments, [zigzag(0.0)]);
+        assert!(worker.pending.is_empty());
+    }
+
+    #[test]
+    fn dropping_skips_queued_jobs() {
+        let mut worker = TrailPreparationWorker::new();
+        let points = (1..2000)
+            .map(|i| Point3::new(i as f32, (i as f32 * 0.1).sin(), 0.0))
+            .collect::<Vec<_>>();
+
+        for id in 0..2000 {
+            worker.request(TrailId(id), &points, params(false));
+        }
+
+        // Preparing all of them would take minutes.
+        let now = Instant::now();
+        drop(worker);
+
+        assert!(now.elapsed() < Duration::from_secs(10));
+    }
+}
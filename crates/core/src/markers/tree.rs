@@ -1,19 +1,32 @@
-use std::{fs::read_dir, path::Path};
+use std::{
+    fs::read_dir,
+    iter::once,
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
-use log::trace;
-use log_err::{LogErrOption, LogErrResult};
+use log::{trace, warn};
+use log_err::LogErrOption;
 pub use nary_tree::NodeId;
 use nary_tree::{NodeRef, Tree};
 
-use super::MarkerCategory;
+use crate::settings::{TrailColor, TrailStyle, TrailWidth};
+
+use super::{
+    packs::{has_extension, is_marker_pack_dir},
+    MarkerCategory, SubtreeActiveState,
+};
 
 pub type MarkerCategoryTreeNode<'a> = NodeRef<'a, MarkerCategory>;
 
+static NEXT_TREE_ID: AtomicU64 = AtomicU64::new(0);
+
 pub struct MarkerCategoryTree {
     pub tree: Tree<MarkerCategory>,
     pub category_count: usize,
     pub point_of_interest_count: usize,
     pub trail_count: usize,
+    id: u64,
 }
 
 impl MarkerCategoryTree {
@@ -27,32 +40,270 @@ impl MarkerCategoryTree {
             category_count: 0,
             point_of_interest_count: 0,
             trail_count: 0,
+            id: NEXT_TREE_ID.fetch_add(1, Ordering::Relaxed),
         }
     }
 
-    pub fn from_all_packs_in_dir(dir: &Path) -> Self {
+    /// Differs for every tree, so node ids of a tree that was replaced are not used with the new one.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn from_all_packs_in_dirs<P: AsRef<Path>>(dirs: &[P]) -> Self {
         let mut tree = Self::new();
 
-        if dir.exists() {
-            for entry in read_dir(dir).log_expect("could not read dir contents") {
-                if let Ok(entry) = entry {
-                    let path = entry.path();
-
-                    if path.is_file()
-                        && path
-                            .to_str()
-                            .is_some_and(|p| p.to_lowercase().ends_with(".taco"))
-                    {
-                        tree.load_marker_pack_from_path(&path);
+        for dir in dirs {
+            tree.load_all_packs_in_dir(dir.as_ref());
+        }
+
+        tree.update_content_counts();
+        tree.update_subtree_active_states();
+
+        tree
+    }
+
+    fn update_content_counts(&mut self) {
+        fn update(tree: &mut Tree<MarkerCategory>, node_id: NodeId) -> (usize, usize) {
+            let child_ids = tree
+                .get(node_id)
+                .log_unwrap()
+                .children()
+                .map(|child| child.node_id())
+                .collect::<Vec<_>>();
+
+            let (mut point_of_interest_count, mut trail_count) = (0, 0);
+
+            for child_id in child_ids {
+                let (child_point_of_interest_count, child_trail_count) = update(tree, child_id);
+
+                point_of_interest_count += child_point_of_interest_count;
+                trail_count += child_trail_count;
+            }
+
+            let mut node = tree.get_mut(node_id).log_unwrap();
+            let category = node.data();
+
+            category.total_point_of_interest_count =
+                point_of_interest_count + category.points_of_interest.len();
+            category.total_trail_count = trail_count + category.trails.len();
+
+            (
+                category.total_point_of_interest_count,
+                category.total_trail_count,
+            )
+        }
+
+        let root_id = self.tree.root_id().log_unwrap();
+
+        update(&mut self.tree, root_id);
+    }
+
+    /// Has to be called after the active state of categories was changed.
+    pub fn update_subtree_active_states(&self) {
+        // Returns whether there are active and inactive categories with content.
+        fn update(node: &MarkerCategoryTreeNode, parent_is_active: bool) -> (bool, bool) {
+            let category = node.data();
+            let is_active = category.is_active.borrow().unwrap_or(parent_is_active);
+
+            let mut has_active = category.has_content() && is_active;
+            let mut has_inactive = category.has_content() && !is_active;
+
+            for child in node.children() {
+                let (child_has_active, child_has_inactive) = update(&child, is_active);
+
+                has_active |= child_has_active;
+                has_inactive |= child_has_inactive;
+            }
+
+            category
+                .subtree_active_state
+                .set(match (has_active, has_inactive) {
+                    (true, true) => SubtreeActiveState::Mixed,
+                    (true, false) => SubtreeActiveState::Active,
+                    (false, true) => SubtreeActiveState::Inactive,
+                    (false, false) if is_active => SubtreeActiveState::Active,
+                    (false, false) => SubtreeActiveState::Inactive,
+                });
+
+            (has_active, has_inactive)
+        }
+
+        update(&self.tree.root().log_unwrap(), false);
+    }
+
+    pub fn find(&self, identifier: &[String]) -> Option<MarkerCategoryTreeNode> {
+        match traverse_path(self.tree.root().log_unwrap(), identifier) {
+            TraverseResult::Found(node_id) => self.tree.get(node_id),
+            TraverseResult::NotFound { .. } => None,
+        }
+    }
+
+    fn load_all_packs_in_dir(&mut self, dir: &Path) {
+        if !dir.exists() {
+            return;
+        }
+
+        // Other directories are configured by the user and could be inaccessible.
+        let entries = match read_dir(dir) {
+            Ok(entries) => entries,
+
+            Err(err) => {
+                warn!("could not read pack directory {}: {err}", dir.display());
+                return;
+            }
+        };
+
+        // Like in the POIs folder of TacO, unzipped packs may lie directly in the directory.
+        if is_marker_pack_dir(dir) {
+            self.load_marker_pack_from_dir(dir);
+        }
+
+        for entry in entries {
+            if let Ok(entry) = entry {
+                let path = entry.path();
+
+                if path.is_dir() {
+                    if is_marker_pack_dir(&path) {
+                        self.load_marker_pack_from_dir(&path);
                     }
+                } else if has_extension(&path, "taco") || has_extension(&path, "zip") {
+                    self.load_marker_pack_from_path(&path);
                 }
             }
         }
+    }
+}
+
+#[cfg(test)]
+impl MarkerCategoryTree {
+    /// A tree with the categories of the dot-separated ids. Each of them gets a trail on map 15, so they are
+    /// displayed.
+    pub fn with_categories(ids: &[&str]) -> Self {
+        use std::cell::RefCell;
+
+        use crate::settings::TrailOverride;
+
+        use super::{Trail, TrailId};
+
+        let mut tree = Self::new();
+        let root_id = tree.tree.root_id().log_unwrap();
+
+        for (idx, id) in ids.iter().enumerate() {
+            let path = id.split('.').map(str::to_owned).collect::<Vec<_>>();
+            let mut node_id = root_id;
+
+            for len in 1..=path.len() {
+                node_id = ensure_category_path(&mut tree.tree, root_id, &path[..len], |id| {
+                    MarkerCategory::new(path[..len].to_vec(), id.clone(), false)
+                });
+            }
+
+            tree.tree
+                .get_mut(node_id)
+                .log_unwrap()
+                .data()
+                .trails
+                .push(Trail {
+                    id: TrailId(idx as u64),
+                    file_name: format!("{id}.trl"),
+                    map_id: 15,
+                    points: vec![],
+                    alpha: None,
+                    overrides: RefCell::new(TrailOverride::default()),
+                });
+
+            tree.category_count += 1;
+            tree.trail_count += 1;
+        }
+
+        tree.update_content_counts();
+        tree.update_subtree_active_states();
 
         tree
     }
 }
 
+/// The color of the category or the closest ancestor which has one.
+pub fn effective_trail_color(node: &MarkerCategoryTreeNode) -> TrailColor {
+    once(node.data())
+        .chain(node.ancestors().map(|n| n.data()))
+        .filter_map(|n| *n.trail_color.borrow())
+        .next()
+        .log_expect("the root always has the default color")
+}
+
+/// The width of the category or the closest ancestor which has one.
+pub fn effective_trail_width(node: &MarkerCategoryTreeNode) -> TrailWidth {
+    once(node.data())
+        .chain(node.ancestors().map(|n| n.data()))
+        .filter_map(|n| *n.trail_width.borrow())
+        .next()
+        .log_expect("the root always has the default width")
+}
+
+/// The style of the category or the closest ancestor which has one.
+pub fn effective_trail_style(node: &MarkerCategoryTreeNode) -> TrailStyle {
+    once(node.data())
+        .chain(node.ancestors().map(|n| n.data()))
+        .filter_map(|n| *n.trail_style.borrow())
+        .next()
+        .log_expect("the root always has the default style")
+}
+
+/// Activates or deactivates the category. Descendants which now have the same state as their parent inherit it
+/// again, while the others keep their state.
+pub fn set_category_active(node: &MarkerCategoryTreeNode, is_active: bool) {
+    *node.data().is_active.borrow_mut() = Some(is_active);
+
+    fn inherit_active_state_if_possible(parent: &MarkerCategoryTreeNode, is_active: bool) {
+        for child in parent.children() {
+            let category = child.data();
+            let mut child_is_active = category.is_active.borrow_mut();
+
+            if let Some(child_is_active_) = *child_is_active {
+                // This category is explicitly enabled or disabled.
+
+                if child_is_active_ == is_active {
+                    // Now it has the same state as its parent's.
+                    *child_is_active = None;
+
+                    inherit_active_state_if_possible(&child, is_active);
+                } else {
+                    // The active state is different than its parent's. Skip this sub tree.
+                }
+            } else {
+                inherit_active_state_if_possible(&child, is_active);
+            }
+        }
+    }
+
+    inherit_active_state_if_possible(node, is_active);
+}
+
+/// Lets the category inherit its active state, color, width and style from its parent.
+pub fn reset_category_settings(node: &MarkerCategoryTreeNode) {
+    let category = node.data();
+
+    *category.is_active.borrow_mut() = None;
+    *category.trail_color.borrow_mut() = None;
+    *category.trail_width.borrow_mut() = None;
+    *category.trail_style.borrow_mut() = None;
+}
+
+/// Activates or deactivates the category together with all its descendants.
+pub fn set_subtree_active(node: &MarkerCategoryTreeNode, is_active: bool) {
+    *node.data().is_active.borrow_mut() = Some(is_active);
+
+    reset_subtree_overrides(node);
+}
+
+/// Lets all descendants inherit the active state of the category.
+pub fn reset_subtree_overrides(node: &MarkerCategoryTreeNode) {
+    for descendant in node.traverse_pre_order().skip(1) {
+        *descendant.data().is_active.borrow_mut() = None;
+    }
+}
+
 pub fn ensure_category_path<F: Fn(&String) -> MarkerCategory>(
     tree: &mut Tree<MarkerCategory>,
     start_node_id: NodeId,
@@ -0,0 +1,184 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use crate::points::{distance_to_segment, Dimensions, Point3};
+
+/// Removes the points which form the smallest triangles with their neighbors until each remaining triangle
+/// has an area of at least `epsilon²`. Compared to `simplify_line_string`, it drops small zigzags while
+/// keeping the overall shape of a line. Like there, removed points stay within `epsilon` of the simplified
+/// line, since a long and thin triangle has a small area even if its point is far away.
+pub fn simplify_line_string_by_area(
+    points: &[Point3],
+    epsilon: f32,
+    dimensions: Dimensions,
+) -> Vec<Point3> {
+    if points.len() <= 2 {
+        return points.to_vec();
+    }
+
+    let projected_points = points
+        .iter()
+        .map(|point| dimensions.project(point))
+        .collect::<Vec<_>>();
+
+    let min_area = epsilon * epsilon;
+    let last_idx = points.len() - 1;
+
+    let mut previous = (0..points.len())
+        .map(|idx| idx.saturating_sub(1))
+        .collect::<Vec<_>>();
+    let mut next = (0..points.len())
+        .map(|idx| (idx + 1).min(last_idx))
+        .collect::<Vec<_>>();
+
+    let mut areas = vec![f32::INFINITY; points.len()];
+    let mut is_removed = vec![false; points.len()];
+    let mut candidates = BinaryHeap::new();
+
+    for idx in 1..last_idx {
+        areas[idx] = triangle_area(
+            &projected_points[idx - 1],
+            &projected_points[idx],
+            &projected_points[idx + 1],
+        );
+
+        candidates.push(Candidate {
+            idx,
+            area: areas[idx],
+        });
+    }
+
+    while let Some(Candidate { idx, area }) = candidates.pop() {
+        // The area changed after a neighbor was removed.
+        if is_removed[idx] || area != areas[idx] {
+            continue;
+        }
+
+        if area >= min_area {
+            break;
+        }
+
+        let (previous_idx, next_idx) = (previous[idx], next[idx]);
+
+        // The point and the ones removed before between its neighbors would be replaced by a single segment.
+        let deviates = (previous_idx + 1..next_idx).any(|removed_idx| {
+            distance_to_segment(
+                &projected_points[removed_idx],
+                &projected_points[previous_idx],
+                &projected_points[next_idx],
+            ) > epsilon
+        });
+
+        if deviates {
+            // Tried again once a neighbor is removed.
+            areas[idx] = f32::INFINITY;
+            continue;
+        }
+
+        is_removed[idx] = true;
+
+        next[previous_idx] = next_idx;
+        previous[next_idx] = previous_idx;
+
+        for neighbor_idx in [previous_idx, next_idx] {
+            if neighbor_idx == 0 || neighbor_idx == last_idx {
+                continue;
+            }
+
+            // Otherwise, the neighbor could be removed before points that were already removed.
+            areas[neighbor_idx] = triangle_area(
+                &projected_points[previous[neighbor_idx]],
+                &projected_points[neighbor_idx],
+                &projected_points[next[neighbor_idx]],
+            )
+            .max(area);
+
+            candidates.push(Candidate {
+                idx: neighbor_idx,
+                area: areas[neighbor_idx],
+            });
+        }
+    }
+
+    points
+        .iter()
+        .zip(is_removed)
+        .filter_map(|(point, is_removed)| (!is_removed).then_some(*point))
+        .collect()
+}
+
+fn triangle_area(a: &Point3, b: &Point3, c: &Point3) -> f32 {
+    (b - a).cross(&(c - a)).norm() / 2.0
+}
+
+/// Ordered by the smallest area first.
+struct Candidate {
+    idx: usize,
+    area: f32,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .area
+            .total_cmp(&self.area)
+            .then_with(|| other.idx.cmp(&self.idx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::markers::test_trails::check_simplifier_properties;
+
+    use super::*;
+
+    #[test]
+    fn keeps_2d_properties() {
+        check_simplifier_properties(simplify_line_string_by_area, Dimensions::Two);
+    }
+
+    #[test]
+    fn keeps_3d_properties() {
+        check_simplifier_properties(simplify_line_string_by_area, Dimensions::Three);
+    }
+
+    #[test]
+    fn keeps_far_points_of_thin_triangles() {
+        // The triangle of the middle point has an area of 0.5, but the point is 10 away from the line.
+        let points = [
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(-10.0, 0.05, 0.0),
+            Point3::new(0.0, 0.1, 0.0),
+        ];
+
+        assert_eq!(
+            simplify_line_string_by_area(&points, 1.0, Dimensions::Two),
+            points
+        );
+    }
+
+    #[test]
+    fn removes_small_zigzags() {
+        let points = (0..10)
+            .map(|i| Point3::new(i as f32, (i % 2) as f32 * 0.01, 0.0))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            simplify_line_string_by_area(&points, 0.5, Dimensions::Two),
+            [points[0], points[9]]
+        );
+    }
+}
@@ -14,6 +14,7 @@ pub fn marker_category_from_xml(
     let mut name = None;
     let mut label = None;
     let mut is_separator = false;
+    let mut alpha = None;
 
     for attr in attributes {
         if attr.name.local_name.eq_ignore_ascii_case("Name") {
@@ -22,6 +23,8 @@ pub fn marker_category_from_xml(
             label = Some(attr.value.clone());
         } else if attr.name.local_name.eq_ignore_ascii_case("IsSeparator") {
             is_separator = attr.value == "1";
+        } else if attr.name.local_name.eq_ignore_ascii_case("alpha") {
+            alpha = parse_alpha(&attr.value);
         }
     }
 
@@ -32,7 +35,10 @@ pub fn marker_category_from_xml(
 
     let label = label.unwrap_or(name);
 
-    Ok(MarkerCategory::new(identifier, label, is_separator))
+    let mut category = MarkerCategory::new(identifier, label, is_separator);
+    category.alpha = alpha;
+
+    Ok(category)
 }
 
 #[derive(Debug)]
@@ -46,12 +52,15 @@ pub fn trail_description_from_xml(
 ) -> Result<TrailDescription, ParseTrailDescriptionError> {
     let mut identifier = None;
     let mut binary_file_name = None;
+    let mut alpha = None;
 
     for attr in attributes {
         if attr.name.local_name.eq_ignore_ascii_case("Type") {
             identifier = Some(attr.value);
         } else if attr.name.local_name.eq_ignore_ascii_case("TrailData") {
             binary_file_name = Some(attr.value);
+        } else if attr.name.local_name.eq_ignore_ascii_case("alpha") {
+            alpha = parse_alpha(&attr.value);
         }
     }
 
@@ -64,5 +73,16 @@ pub fn trail_description_from_xml(
     Ok(TrailDescription {
         category_id_path: ids,
         binary_file_name,
+        alpha,
     })
 }
+
+/// Invalid values are ignored like in other overlays.
+fn parse_alpha(value: &str) -> Option<f32> {
+    value
+        .trim()
+        .parse::<f32>()
+        .ok()
+        .filter(|alpha| alpha.is_finite())
+        .map(|alpha| alpha.clamp(0.0, 1.0))
+}
@@ -1 +1,35 @@
 pub type Point3 = nalgebra::Point3<f32>;
+
+/// Which coordinates count when comparing points. Maps only show the horizontal position, so the height of
+/// points does not matter there.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Dimensions {
+    /// Only x and y, as drawn on maps.
+    Two,
+    /// Including the height, as drawn in the world.
+    Three,
+}
+
+impl Dimensions {
+    pub fn project(self, point: &Point3) -> Point3 {
+        match self {
+            Self::Two => Point3::new(point.x, point.y, 0.0),
+            Self::Three => *point,
+        }
+    }
+}
+
+/// The distance to the closest point of the line segment. Unlike the distance to the infinite line, this also
+/// works for loops, which start and end at the same point.
+pub fn distance_to_segment(point: &Point3, start: &Point3, end: &Point3) -> f32 {
+    let direction = end - start;
+    let length_squared = direction.norm_squared();
+
+    if length_squared == 0.0 {
+        return nalgebra::distance(point, start);
+    }
+
+    let t = ((point - start).dot(&direction) / length_squared).clamp(0.0, 1.0);
+
+    nalgebra::distance(point, &(start + direction * t))
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+/// The interface size selected in the options of the game.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UiSize {
+    Small,
+    Normal,
+    Large,
+    Larger,
+}
+
+impl UiSize {
+    /// How much larger the interface is drawn than with the normal size.
+    pub fn scale_factor(self) -> f32 {
+        match self {
+            Self::Small => 0.9,
+            Self::Normal => 1.0,
+            Self::Large => 1.11,
+            Self::Larger => 1.22,
+        }
+    }
+}
+
+/// Corrects the assumed position and size of the compass in pixels, if it does not line up with the game.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct CompassCalibration {
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub extra_width: f32,
+    pub extra_height: f32,
+}
+
+impl CompassCalibration {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
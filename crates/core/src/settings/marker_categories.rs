@@ -14,7 +14,7 @@ use super::Settings;
 pub fn backup_marker_category_settings(tree: &MarkerCategoryTree, settings: &mut Settings) {
     let preset = settings
         .marker_presets
-        .entry("Default".to_owned())
+        .entry(settings.active_marker_preset.clone())
         .or_default();
 
     // Read the default values from the root category.
@@ -22,6 +22,7 @@ pub fn backup_marker_category_settings(tree: &MarkerCategoryTree, settings: &mut
     let root_category = root_node.data();
     settings.default_trail_color = root_category.trail_color.borrow().log_unwrap();
     settings.default_trail_width = root_category.trail_width.borrow().log_unwrap();
+    settings.default_trail_style = root_category.trail_style.borrow().log_unwrap();
 
     fn persist_non_default_categories(
         parent: &MarkerCategoryTreeNode,
@@ -49,6 +50,7 @@ pub fn backup_marker_category_settings(tree: &MarkerCategoryTree, settings: &mut
                 entry.active = *category.is_active.borrow();
                 entry.trail_color = *category.trail_color.borrow();
                 entry.trail_width = *category.trail_width.borrow();
+                entry.trail_style = *category.trail_style.borrow();
             } else {
                 preset.remove(&id);
             }
@@ -58,18 +60,34 @@ pub fn backup_marker_category_settings(tree: &MarkerCategoryTree, settings: &mut
     }
 
     persist_non_default_categories(&root_node, false, preset);
+
+    for node in root_node.traverse_pre_order() {
+        for trail in &node.data().trails {
+            let overrides = trail.overrides.borrow();
+
+            if overrides.is_default() {
+                settings.trail_overrides.remove(&trail.id);
+            } else {
+                settings.trail_overrides.insert(trail.id, *overrides);
+            }
+        }
+    }
 }
 
-pub fn apply_marker_category_settings(settings: &Settings, tree: &mut MarkerCategoryTree) {
+pub fn apply_marker_category_settings(settings: &Settings, tree: &MarkerCategoryTree) {
     let empty_map = HashMap::new();
-    let preset = settings.marker_presets.get("Default").unwrap_or(&empty_map);
+    let preset = settings
+        .marker_presets
+        .get(&settings.active_marker_preset)
+        .unwrap_or(&empty_map);
 
     // Set the default values to the root category. This way all categories inherit the default values automatically.
-    let mut root_node = tree.tree.root_mut().log_unwrap();
+    let root_node = tree.tree.root().log_unwrap();
     let root_category = root_node.data();
-    *root_category.is_active.get_mut() = Some(false);
-    *root_category.trail_color.get_mut() = Some(settings.default_trail_color);
-    *root_category.trail_width.get_mut() = Some(settings.default_trail_width);
+    *root_category.is_active.borrow_mut() = Some(false);
+    *root_category.trail_color.borrow_mut() = Some(settings.default_trail_color);
+    *root_category.trail_width.borrow_mut() = Some(settings.default_trail_width);
+    *root_category.trail_style.borrow_mut() = Some(settings.default_trail_style);
 
     for node in tree
         .tree
@@ -79,16 +97,14 @@ pub fn apply_marker_category_settings(settings: &Settings, tree: &mut MarkerCate
         // Skip the root node itself as it does not represent a real category and will never be persisted.
         .skip(1)
     {
-        let id = node.data().identifier.join(".");
+        let category = node.data();
+        let id = category.identifier.join(".");
 
         if let Some(setting) = preset.get(&id) {
-            (setting.active, setting.trail_color, setting.trail_width);
-
-            let category = node.data();
-
             *category.is_active.borrow_mut() = setting.active;
             *category.trail_color.borrow_mut() = setting.trail_color;
             *category.trail_width.borrow_mut() = setting.trail_width;
+            *category.trail_style.borrow_mut() = setting.trail_style;
 
             #[cfg(debug_assertions)]
             {
@@ -99,7 +115,22 @@ pub fn apply_marker_category_settings(settings: &Settings, tree: &mut MarkerCate
                 trace!("  active: {:?}", setting.active);
                 trace!("  trail color: {:?}", setting.trail_color);
                 trace!("  trail width: {:?}", setting.trail_width);
+                trace!("  trail style: {:?}", setting.trail_style);
             }
+        } else {
+            // Another preset might have been applied before.
+            *category.is_active.borrow_mut() = None;
+            *category.trail_color.borrow_mut() = None;
+            *category.trail_width.borrow_mut() = None;
+            *category.trail_style.borrow_mut() = None;
+        }
+
+        for trail in &category.trails {
+            *trail.overrides.borrow_mut() = settings
+                .trail_overrides
+                .get(&trail.id)
+                .copied()
+                .unwrap_or_default();
         }
     }
 }
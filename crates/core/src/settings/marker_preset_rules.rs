@@ -0,0 +1,251 @@
+use serde::{Deserialize, Serialize};
+
+use super::{marker_preset_exists, Settings};
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Profession {
+    Guardian,
+    Warrior,
+    Engineer,
+    Ranger,
+    Thief,
+    Elementalist,
+    Mesmer,
+    Necromancer,
+    Revenant,
+}
+
+impl Profession {
+    pub const ALL: [Self; 9] = [
+        Self::Guardian,
+        Self::Warrior,
+        Self::Engineer,
+        Self::Ranger,
+        Self::Thief,
+        Self::Elementalist,
+        Self::Mesmer,
+        Self::Necromancer,
+        Self::Revenant,
+    ];
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum MapCategory {
+    Pve,
+    Instance,
+    Pvp,
+    Wvw,
+}
+
+impl MapCategory {
+    pub const ALL: [Self; 4] = [Self::Pve, Self::Instance, Self::Pvp, Self::Wvw];
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum MarkerPresetRuleCondition {
+    CharacterName(String),
+    Profession(Profession),
+    MapId(u32),
+    MapCategory(MapCategory),
+}
+
+impl MarkerPresetRuleCondition {
+    pub fn matches(&self, player: &PlayerContext) -> bool {
+        match self {
+            Self::CharacterName(name) => player.character_name == *name,
+            Self::Profession(profession) => player.profession == Some(*profession),
+            Self::MapId(map_id) => player.map_id == *map_id,
+            Self::MapCategory(category) => player.map_category == Some(*category),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MarkerPresetRule {
+    pub condition: MarkerPresetRuleCondition,
+    pub preset: String,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct MarkerPresetRules {
+    /// The first matching rule selects the preset.
+    #[serde(default)]
+    pub rules: Vec<MarkerPresetRule>,
+
+    /// Used if no rule matches. If there is none, the current preset is kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<String>,
+}
+
+impl MarkerPresetRules {
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty() && self.fallback.is_none()
+    }
+}
+
+/// What is known about the player from the mumble link.
+#[derive(Clone, Debug, Default)]
+pub struct PlayerContext {
+    pub character_name: String,
+    pub profession: Option<Profession>,
+    pub map_id: u32,
+    pub map_category: Option<MapCategory>,
+}
+
+pub fn select_marker_preset<'a>(settings: &'a Settings, player: &PlayerContext) -> Option<&'a str> {
+    let rules = &settings.marker_preset_rules;
+
+    rules
+        .rules
+        .iter()
+        .filter(|rule| rule.condition.matches(player))
+        .map(|rule| &rule.preset)
+        .chain(rules.fallback.iter())
+        // Presets could have been deleted after the rule was created.
+        .find(|preset| marker_preset_exists(settings, preset))
+        .map(|preset| preset.as_str())
+}
+
+/// Remembers the last preset selected by the rules. This way a preset that was chosen manually is only
+/// replaced if another rule matches.
+#[derive(Debug, Default)]
+pub struct MarkerPresetRuleTracker {
+    last_selection: Option<String>,
+}
+
+impl MarkerPresetRuleTracker {
+    pub fn reset(&mut self) {
+        self.last_selection = None;
+    }
+
+    /// Returns the preset to switch to if the rules select another preset than last time.
+    pub fn update(&mut self, settings: &Settings, player: &PlayerContext) -> Option<String> {
+        let selection = select_marker_preset(settings, player).map(|preset| preset.to_owned());
+
+        if selection == self.last_selection {
+            return None;
+        }
+
+        self.last_selection.clone_from(&selection);
+
+        selection.filter(|preset| *preset != settings.active_marker_preset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn rule(condition: MarkerPresetRuleCondition, preset: &str) -> MarkerPresetRule {
+        MarkerPresetRule {
+            condition,
+            preset: preset.to_owned(),
+        }
+    }
+
+    /// Raids on map 1155, Thief for thieves and Other for everything else.
+    fn settings_with_rules() -> Settings {
+        let mut settings = Settings::default();
+
+        for preset in ["Raids", "Thief", "Other"] {
+            settings
+                .marker_presets
+                .insert(preset.to_owned(), HashMap::new());
+        }
+
+        settings.marker_preset_rules = MarkerPresetRules {
+            rules: vec![
+                rule(MarkerPresetRuleCondition::MapId(1155), "Raids"),
+                rule(
+                    MarkerPresetRuleCondition::Profession(Profession::Thief),
+                    "Thief",
+                ),
+            ],
+            fallback: Some("Other".to_owned()),
+        };
+
+        settings
+    }
+
+    fn player(profession: Profession, map_id: u32) -> PlayerContext {
+        PlayerContext {
+            character_name: "Name".to_owned(),
+            profession: Some(profession),
+            map_id,
+            map_category: Some(MapCategory::Pve),
+        }
+    }
+
+    #[test]
+    fn selects_the_first_matching_rule() {
+        let settings = settings_with_rules();
+
+        assert_eq!(
+            select_marker_preset(&settings, &player(Profession::Thief, 1155)),
+            Some("Raids")
+        );
+        assert_eq!(
+            select_marker_preset(&settings, &player(Profession::Thief, 15)),
+            Some("Thief")
+        );
+    }
+
+    #[test]
+    fn falls_back_if_no_rule_matches() {
+        let mut settings = settings_with_rules();
+        let player = player(Profession::Guardian, 15);
+
+        assert_eq!(select_marker_preset(&settings, &player), Some("Other"));
+
+        settings.marker_preset_rules.fallback = None;
+        assert_eq!(select_marker_preset(&settings, &player), None);
+    }
+
+    #[test]
+    fn skips_rules_of_deleted_presets() {
+        let mut settings = settings_with_rules();
+        settings.marker_presets.remove("Raids");
+
+        assert_eq!(
+            select_marker_preset(&settings, &player(Profession::Thief, 1155)),
+            Some("Thief")
+        );
+        assert_eq!(
+            select_marker_preset(&settings, &player(Profession::Guardian, 1155)),
+            Some("Other")
+        );
+
+        settings.marker_presets.remove("Other");
+        assert_eq!(
+            select_marker_preset(&settings, &player(Profession::Guardian, 1155)),
+            None
+        );
+    }
+
+    #[test]
+    fn switches_only_when_the_selection_changes() {
+        let mut settings = settings_with_rules();
+        let mut tracker = MarkerPresetRuleTracker::default();
+
+        assert_eq!(
+            tracker.update(&settings, &player(Profession::Thief, 1155)),
+            Some("Raids".to_owned())
+        );
+        settings.active_marker_preset = "Raids".to_owned();
+
+        // A preset chosen manually is kept while the same rule matches.
+        settings.active_marker_preset = "Other".to_owned();
+        assert_eq!(
+            tracker.update(&settings, &player(Profession::Thief, 1155)),
+            None
+        );
+
+        assert_eq!(
+            tracker.update(&settings, &player(Profession::Thief, 15)),
+            Some("Thief".to_owned())
+        );
+    }
+}
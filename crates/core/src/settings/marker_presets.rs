@@ -0,0 +1,326 @@
+use std::fmt::Display;
+
+use log_err::LogErrOption;
+
+use crate::markers::MarkerCategoryTree;
+
+use super::{apply_marker_category_settings, Settings};
+
+pub const DEFAULT_MARKER_PRESET_NAME: &str = "Default";
+
+#[derive(Debug)]
+pub enum MarkerPresetError {
+    AlreadyExists,
+    NotFound,
+    LastPreset,
+}
+
+impl Display for MarkerPresetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MarkerPresetError::AlreadyExists => write!(f, "a preset with this name already exists"),
+            MarkerPresetError::NotFound => write!(f, "the preset does not exist"),
+            MarkerPresetError::LastPreset => write!(f, "the last preset cannot be removed"),
+        }
+    }
+}
+
+pub fn marker_preset_names(settings: &Settings) -> Vec<String> {
+    let mut names = settings.marker_presets.keys().cloned().collect::<Vec<_>>();
+
+    // The active preset is only persisted after the first change.
+    if !settings
+        .marker_presets
+        .contains_key(&settings.active_marker_preset)
+    {
+        names.push(settings.active_marker_preset.clone());
+    }
+
+    names.sort_by_key(|name| name.to_lowercase());
+
+    names
+}
+
+pub fn marker_preset_exists(settings: &Settings, name: &str) -> bool {
+    settings.marker_presets.contains_key(name) || settings.active_marker_preset == name
+}
+
+/// Appends a number to the name if it is already taken.
+pub fn unused_marker_preset_name(settings: &Settings, name: &str) -> String {
+    (1..)
+        .map(|n| match n {
+            1 => name.to_owned(),
+            _ => format!("{name} {n}"),
+        })
+        .find(|name| !marker_preset_exists(settings, name))
+        .log_unwrap()
+}
+
+pub fn create_marker_preset(settings: &mut Settings, name: &str) -> Result<(), MarkerPresetError> {
+    if marker_preset_exists(settings, name) {
+        return Err(MarkerPresetError::AlreadyExists);
+    }
+
+    settings
+        .marker_presets
+        .insert(name.to_owned(), Default::default());
+
+    Ok(())
+}
+
+pub fn duplicate_marker_preset(
+    settings: &mut Settings,
+    source_name: &str,
+    name: &str,
+) -> Result<(), MarkerPresetError> {
+    if marker_preset_exists(settings, name) {
+        return Err(MarkerPresetError::AlreadyExists);
+    }
+
+    if !marker_preset_exists(settings, source_name) {
+        return Err(MarkerPresetError::NotFound);
+    }
+
+    let preset = settings
+        .marker_presets
+        .get(source_name)
+        .cloned()
+        .unwrap_or_default();
+
+    settings.marker_presets.insert(name.to_owned(), preset);
+
+    Ok(())
+}
+
+pub fn rename_marker_preset(
+    settings: &mut Settings,
+    old_name: &str,
+    new_name: &str,
+) -> Result<(), MarkerPresetError> {
+    if marker_preset_exists(settings, new_name) {
+        return Err(MarkerPresetError::AlreadyExists);
+    }
+
+    if !marker_preset_exists(settings, old_name) {
+        return Err(MarkerPresetError::NotFound);
+    }
+
+    let preset = settings.marker_presets.remove(old_name).unwrap_or_default();
+
+    settings.marker_presets.insert(new_name.to_owned(), preset);
+
+    if settings.active_marker_preset == old_name {
+        settings.active_marker_preset = new_name.to_owned();
+    }
+
+    let rules = &mut settings.marker_preset_rules;
+
+    for preset in rules
+        .rules
+        .iter_mut()
+        .map(|rule| &mut rule.preset)
+        .chain(rules.fallback.iter_mut())
+    {
+        if preset == old_name {
+            *preset = new_name.to_owned();
+        }
+    }
+
+    Ok(())
+}
+
+/// Deletes the preset. If it was the active one, another preset is activated but not yet applied to the tree.
+pub fn delete_marker_preset(settings: &mut Settings, name: &str) -> Result<(), MarkerPresetError> {
+    if !marker_preset_exists(settings, name) {
+        return Err(MarkerPresetError::NotFound);
+    }
+
+    let names = marker_preset_names(settings);
+
+    if names.len() == 1 {
+        return Err(MarkerPresetError::LastPreset);
+    }
+
+    settings.marker_presets.remove(name);
+
+    if settings.active_marker_preset == name {
+        settings.active_marker_preset = names
+            .into_iter()
+            .find(|n| n != name)
+            .log_expect("there is at least one other preset");
+    }
+
+    Ok(())
+}
+
+pub fn switch_marker_preset(
+    settings: &mut Settings,
+    tree: &MarkerCategoryTree,
+    name: &str,
+) -> Result<(), MarkerPresetError> {
+    if !marker_preset_exists(settings, name) {
+        return Err(MarkerPresetError::NotFound);
+    }
+
+    settings.active_marker_preset = name.to_owned();
+
+    apply_marker_category_settings(settings, tree);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        markers::MarkerCategoryTreeNode,
+        settings::{
+            MarkerCategorySetting, MarkerPresetRule, MarkerPresetRuleCondition, TrailColor,
+        },
+    };
+
+    use super::*;
+
+    fn setting(active: bool) -> MarkerCategorySetting {
+        MarkerCategorySetting {
+            active: Some(active),
+            ..Default::default()
+        }
+    }
+
+    /// "Default" is active and empty, "Raids" activates a.b and colors d.
+    fn settings_with_raids() -> Settings {
+        let mut settings = Settings::default();
+
+        settings
+            .marker_presets
+            .insert(DEFAULT_MARKER_PRESET_NAME.to_owned(), HashMap::new());
+
+        settings.marker_presets.insert(
+            "Raids".to_owned(),
+            HashMap::from([
+                ("a.b".to_owned(), setting(true)),
+                (
+                    "d".to_owned(),
+                    MarkerCategorySetting {
+                        trail_color: Some(TrailColor::from_rgb([1, 2, 3])),
+                        ..Default::default()
+                    },
+                ),
+            ]),
+        );
+
+        settings
+    }
+
+    fn category<'a>(tree: &'a MarkerCategoryTree, id: &str) -> MarkerCategoryTreeNode<'a> {
+        let identifier = id.split('.').map(str::to_owned).collect::<Vec<_>>();
+
+        tree.find(&identifier).unwrap()
+    }
+
+    #[test]
+    fn applies_the_preset_to_the_tree() {
+        let tree = MarkerCategoryTree::with_categories(&["a.b", "a.c", "d"]);
+        let mut settings = settings_with_raids();
+
+        switch_marker_preset(&mut settings, &tree, "Raids").unwrap();
+
+        assert_eq!(settings.active_marker_preset, "Raids");
+        assert_eq!(
+            *category(&tree, "a.b").data().is_active.borrow(),
+            Some(true)
+        );
+        assert_eq!(*category(&tree, "a.c").data().is_active.borrow(), None);
+        assert_eq!(
+            *category(&tree, "d").data().trail_color.borrow(),
+            Some(TrailColor::from_rgb([1, 2, 3]))
+        );
+
+        // The settings of the previous preset do not stay behind.
+        switch_marker_preset(&mut settings, &tree, DEFAULT_MARKER_PRESET_NAME).unwrap();
+
+        assert_eq!(*category(&tree, "a.b").data().is_active.borrow(), None);
+        assert_eq!(*category(&tree, "d").data().trail_color.borrow(), None);
+
+        assert!(matches!(
+            switch_marker_preset(&mut settings, &tree, "Missing"),
+            Err(MarkerPresetError::NotFound)
+        ));
+        assert_eq!(settings.active_marker_preset, DEFAULT_MARKER_PRESET_NAME);
+    }
+
+    #[test]
+    fn deletes_the_active_preset() {
+        let mut settings = settings_with_raids();
+        settings.active_marker_preset = "Raids".to_owned();
+
+        delete_marker_preset(&mut settings, "Raids").unwrap();
+
+        assert!(!settings.marker_presets.contains_key("Raids"));
+        assert_eq!(settings.active_marker_preset, DEFAULT_MARKER_PRESET_NAME);
+        assert_eq!(marker_preset_names(&settings), [DEFAULT_MARKER_PRESET_NAME]);
+
+        assert!(matches!(
+            delete_marker_preset(&mut settings, DEFAULT_MARKER_PRESET_NAME),
+            Err(MarkerPresetError::LastPreset)
+        ));
+        assert!(matches!(
+            delete_marker_preset(&mut settings, "Raids"),
+            Err(MarkerPresetError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn renames_the_active_preset_and_its_rules() {
+        let mut settings = settings_with_raids();
+        settings.active_marker_preset = "Raids".to_owned();
+        settings.marker_preset_rules.rules.push(MarkerPresetRule {
+            condition: MarkerPresetRuleCondition::MapId(1155),
+            preset: "Raids".to_owned(),
+        });
+        settings.marker_preset_rules.fallback = Some("Raids".to_owned());
+
+        rename_marker_preset(&mut settings, "Raids", "Wings").unwrap();
+
+        assert_eq!(settings.active_marker_preset, "Wings");
+        assert_eq!(settings.marker_presets["Wings"]["a.b"].active, Some(true));
+        assert!(!marker_preset_exists(&settings, "Raids"));
+        assert_eq!(settings.marker_preset_rules.rules[0].preset, "Wings");
+        assert_eq!(
+            settings.marker_preset_rules.fallback.as_deref(),
+            Some("Wings")
+        );
+    }
+
+    #[test]
+    fn renames_the_active_preset_before_it_is_persisted() {
+        let mut settings = Settings::default();
+
+        rename_marker_preset(&mut settings, DEFAULT_MARKER_PRESET_NAME, "Mine").unwrap();
+
+        assert_eq!(settings.active_marker_preset, "Mine");
+        assert_eq!(marker_preset_names(&settings), ["Mine"]);
+    }
+
+    #[test]
+    fn rejects_taken_and_missing_names() {
+        let mut settings = settings_with_raids();
+
+        assert!(matches!(
+            rename_marker_preset(&mut settings, "Raids", DEFAULT_MARKER_PRESET_NAME),
+            Err(MarkerPresetError::AlreadyExists)
+        ));
+        assert!(matches!(
+            rename_marker_preset(&mut settings, "Missing", "Other"),
+            Err(MarkerPresetError::NotFound)
+        ));
+        assert!(matches!(
+            create_marker_preset(&mut settings, "Raids"),
+            Err(MarkerPresetError::AlreadyExists)
+        ));
+
+        assert_eq!(unused_marker_preset_name(&settings, "Raids"), "Raids 2");
+    }
+}
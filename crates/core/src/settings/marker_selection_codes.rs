@@ -0,0 +1,170 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Display,
+    io::{self, Read},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use log_err::{LogErrOption, LogErrResult};
+
+use crate::markers::MarkerCategoryTree;
+
+use super::{create_marker_preset, MarkerCategorySetting, MarkerPresetError, Settings};
+
+/// Identifies the format of the code. A new format gets a new prefix.
+const MARKER_SELECTION_CODE_PREFIX: &str = "paths1:";
+
+/// Far more than the selection of every category in the large packs. Deflate compresses repetitive input by
+/// a factor of about 1000, so a short code could otherwise take up a lot of memory.
+const MAX_DECODED_LEN: u64 = 4 * 1024 * 1024;
+
+#[derive(Debug)]
+pub enum MarkerSelectionCodeError {
+    UnknownFormat,
+    InvalidBase64(base64::DecodeError),
+    InvalidCompression(io::Error),
+    TooLarge,
+    InvalidJson(serde_json::Error),
+    Preset(MarkerPresetError),
+}
+
+impl Display for MarkerSelectionCodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MarkerSelectionCodeError::UnknownFormat => write!(f, "not a selection code"),
+            MarkerSelectionCodeError::InvalidBase64(err) => write!(f, "damaged code: {err}"),
+            MarkerSelectionCodeError::InvalidCompression(err) => write!(f, "damaged code: {err}"),
+            MarkerSelectionCodeError::TooLarge => write!(f, "the code is too large"),
+            MarkerSelectionCodeError::InvalidJson(err) => write!(f, "damaged code: {err}"),
+            MarkerSelectionCodeError::Preset(err) => write!(f, "could not create preset: {err}"),
+        }
+    }
+}
+
+/// Encodes the persisted settings of the active preset. Call `backup_marker_category_settings` before to
+/// include the latest changes.
+pub fn export_marker_selection_code(settings: &Settings) -> String {
+    let preset = settings
+        .marker_presets
+        .get(&settings.active_marker_preset)
+        .map(|preset| preset.iter().collect::<BTreeMap<_, _>>())
+        .unwrap_or_default();
+
+    let mut encoder = DeflateEncoder::new(vec![], Compression::best());
+
+    serde_json::to_writer(&mut encoder, &preset).log_expect("could not convert preset to json");
+
+    let compressed = encoder.finish().log_expect("could not compress preset");
+
+    format!(
+        "{MARKER_SELECTION_CODE_PREFIX}{}",
+        URL_SAFE_NO_PAD.encode(compressed)
+    )
+}
+
+/// Creates a new preset from the code. Returns the ids of categories that are not part of the loaded marker
+/// packs. They are kept in the preset, so they apply once the corresponding pack is installed.
+pub fn import_marker_selection_code(
+    settings: &mut Settings,
+    tree: &MarkerCategoryTree,
+    name: &str,
+    code: &str,
+) -> Result<Vec<String>, MarkerSelectionCodeError> {
+    let preset = decode_marker_selection_code(code)?;
+
+    create_marker_preset(settings, name).map_err(MarkerSelectionCodeError::Preset)?;
+
+    let known_ids = tree
+        .tree
+        .root()
+        .log_unwrap()
+        .traverse_pre_order()
+        .skip(1)
+        .map(|node| node.data().identifier.join("."))
+        .collect::<HashSet<_>>();
+
+    let mut unknown_ids = preset
+        .keys()
+        .filter(|id| !known_ids.contains(*id))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    unknown_ids.sort();
+
+    settings.marker_presets.insert(name.to_owned(), preset);
+
+    Ok(unknown_ids)
+}
+
+fn decode_marker_selection_code(
+    code: &str,
+) -> Result<HashMap<String, MarkerCategorySetting>, MarkerSelectionCodeError> {
+    // Codes pasted from chats often contain surrounding whitespace.
+    let encoded = code
+        .trim()
+        .strip_prefix(MARKER_SELECTION_CODE_PREFIX)
+        .ok_or(MarkerSelectionCodeError::UnknownFormat)?;
+
+    let compressed = URL_SAFE_NO_PAD
+        .decode(encoded)
+        .map_err(MarkerSelectionCodeError::InvalidBase64)?;
+
+    let mut json = vec![];
+
+    DeflateDecoder::new(compressed.as_slice())
+        .take(MAX_DECODED_LEN + 1)
+        .read_to_end(&mut json)
+        .map_err(MarkerSelectionCodeError::InvalidCompression)?;
+
+    if json.len() as u64 > MAX_DECODED_LEN {
+        return Err(MarkerSelectionCodeError::TooLarge);
+    }
+
+    serde_json::from_slice(&json).map_err(MarkerSelectionCodeError::InvalidJson)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn rejects_codes_that_decode_to_too_much_data() {
+        let mut encoder = DeflateEncoder::new(vec![], Compression::best());
+        encoder
+            .write_all(&vec![b' '; MAX_DECODED_LEN as usize + 1])
+            .unwrap();
+
+        let code = format!(
+            "{MARKER_SELECTION_CODE_PREFIX}{}",
+            URL_SAFE_NO_PAD.encode(encoder.finish().unwrap())
+        );
+
+        assert!(matches!(
+            decode_marker_selection_code(&code),
+            Err(MarkerSelectionCodeError::TooLarge)
+        ));
+    }
+
+    #[test]
+    fn decodes_exported_codes() {
+        let mut settings = Settings::default();
+        settings.marker_presets.insert(
+            settings.active_marker_preset.clone(),
+            HashMap::from([(
+                "tw.raids".to_owned(),
+                MarkerCategorySetting {
+                    active: Some(false),
+                    ..Default::default()
+                },
+            )]),
+        );
+
+        let preset =
+            decode_marker_selection_code(&export_marker_selection_code(&settings)).unwrap();
+
+        assert_eq!(preset["tw.raids"].active, Some(false));
+    }
+}
@@ -1,16 +1,35 @@
+mod compass_calibration;
 mod marker_categories;
+mod marker_preset_rules;
+mod marker_presets;
+mod marker_selection_codes;
+mod overlay_category_state;
+mod pack_directories;
 mod trails;
+mod ui_appearance;
 mod versions;
 
-use std::{fmt::Debug, io::Write};
+use std::{
+    fmt::{Debug, Display},
+    fs::{remove_file, rename, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use log::{debug, warn};
-use log_err::LogErrResult;
+use log::debug;
 use serde::Deserialize;
 
+pub use self::compass_calibration::*;
 pub use self::marker_categories::*;
+pub use self::marker_preset_rules::*;
+pub use self::marker_presets::*;
+pub use self::marker_selection_codes::*;
+pub use self::overlay_category_state::*;
+pub use self::pack_directories::*;
 pub use self::trails::*;
-use self::versions::v1::SettingsV1;
+pub use self::ui_appearance::*;
+use self::versions::{v1::SettingsV1, v2::SettingsV2};
 pub use self::versions::{MarkerCategorySetting, Settings};
 
 #[derive(Deserialize)]
@@ -18,46 +37,211 @@ struct OnlyVersion {
     version: usize,
 }
 
-pub fn read_settings(bytes: &[u8]) -> Settings {
-    match serde_json::from_slice::<OnlyVersion>(bytes) {
-        Ok(OnlyVersion { version }) => match version {
-            1 => parse_and_unwrap_settings::<SettingsV1>(bytes),
-
-            _ => {
-                warn!("got settings with unrecognized version: {version}");
+#[derive(Debug)]
+pub enum ReadSettingsError {
+    InvalidJson(serde_json::Error),
+    UnknownVersion(usize),
+}
 
-                Settings::default()
+impl Display for ReadSettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadSettingsError::InvalidJson(err) => write!(f, "invalid settings: {err}"),
+            ReadSettingsError::UnknownVersion(version) => {
+                write!(f, "unknown settings version {version}")
             }
-        },
+        }
+    }
+}
 
-        Err(err) => {
-            debug!("could not read settings version: {err}");
+/// Reads settings of any known version and migrates them to the current one.
+pub fn read_settings(bytes: &[u8]) -> Result<Settings, ReadSettingsError> {
+    let OnlyVersion { version } =
+        serde_json::from_slice(bytes).map_err(ReadSettingsError::InvalidJson)?;
 
-            Settings::default()
-        }
+    match version {
+        1 => parse_settings::<SettingsV1>(bytes),
+        2 => parse_settings::<SettingsV2>(bytes),
+
+        _ => Err(ReadSettingsError::UnknownVersion(version)),
     }
 }
 
-fn parse_and_unwrap_settings<'de, S: Debug + Deserialize<'de> + Into<Settings>>(
+fn parse_settings<'de, S: Debug + Deserialize<'de> + Into<Settings>>(
     bytes: &'de [u8],
-) -> Settings {
-    let res = serde_json::from_slice::<S>(bytes);
+) -> Result<Settings, ReadSettingsError> {
+    let settings = serde_json::from_slice::<S>(bytes).map_err(ReadSettingsError::InvalidJson)?;
 
-    match res {
-        Ok(settings) => {
-            debug!("got settings: {settings:?}");
+    debug!("got settings: {settings:?}");
 
-            settings.into()
-        }
+    Ok(settings.into())
+}
 
-        Err(err) => {
-            debug!("could not read settings: {err}");
+/// The settings file is never left half-written, see `write_file_atomically`.
+pub fn write_settings_file(file_path: &Path, settings: &Settings) -> io::Result<()> {
+    write_file_atomically(file_path, |writer| {
+        serde_json::to_writer_pretty(writer, settings).map_err(io::Error::from)
+    })
+}
 
-            Settings::default()
-        }
+/// Writes to a temporary file first and replaces the file afterwards. This way the file is never left
+/// half-written, and it keeps its previous content if writing fails.
+fn write_file_atomically<E: From<io::Error>>(
+    file_path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<(), E>,
+) -> Result<(), E> {
+    let mut temp_file_path = file_path.as_os_str().to_owned();
+    temp_file_path.push(".tmp");
+    let temp_file_path = PathBuf::from(temp_file_path);
+
+    let mut writer = BufWriter::new(File::create(&temp_file_path)?);
+
+    let result = write(&mut writer)
+        .and_then(|()| Ok(writer.flush()?))
+        .and_then(|()| Ok(writer.get_ref().sync_all()?));
+
+    drop(writer);
+
+    if let Err(err) = result {
+        let _ = remove_file(&temp_file_path);
+
+        return Err(err);
     }
+
+    Ok(rename(&temp_file_path, file_path)?)
 }
 
-pub fn write_settings<W: Write>(writer: &mut W, settings: &Settings) {
-    serde_json::to_writer_pretty(writer, settings).log_expect("could not convert settings to json");
+/// Moves an unreadable settings file out of the way, so it is not overwritten by the next save.
+pub fn backup_unreadable_settings_file(file_path: &Path) -> io::Result<PathBuf> {
+    let backup_file_path = unreadable_settings_backup_path(file_path, SystemTime::now());
+
+    rename(file_path, &backup_file_path)?;
+
+    Ok(backup_file_path)
+}
+
+pub fn unreadable_settings_backup_path(file_path: &Path, time: SystemTime) -> PathBuf {
+    let timestamp = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+
+    file_path.with_extension(format!("unreadable-{timestamp}.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETTINGS_V1: &str = r#"{
+        "version": 1,
+        "default_trail_color": [10, 20, 30],
+        "default_trail_width": 4.5,
+        "trail_simplify_epsilon": 0.25,
+        "limit_markers_to_current_map": true,
+        "active_marker_preset": "Raids",
+        "marker_presets": {
+            "Raids": {
+                "tw.raids": { "active": false, "trail_color": [1, 2, 3], "trail_width": 3.0 }
+            },
+            "Default": {}
+        },
+        "marker_preset_rules": {
+            "rules": [{ "condition": { "type": "map_id", "value": 1155 }, "preset": "Raids" }],
+            "fallback": "Default"
+        }
+    }"#;
+
+    #[test]
+    fn migrates_v1_to_v2_with_every_field() {
+        let settings = read_settings(SETTINGS_V1.as_bytes()).unwrap();
+
+        assert_eq!(
+            settings.default_trail_color,
+            TrailColor::from_rgb([10, 20, 30])
+        );
+        assert_eq!(settings.default_trail_width, TrailWidth(4.5));
+        assert_eq!(settings.trail_simplify_epsilon.0, 0.25);
+        assert!(settings.limit_markers_to_current_map);
+        assert_eq!(settings.active_marker_preset, "Raids");
+
+        assert_eq!(settings.marker_presets.len(), 2);
+        assert!(settings.marker_presets["Default"].is_empty());
+
+        let category = &settings.marker_presets["Raids"]["tw.raids"];
+        assert_eq!(category.active, Some(false));
+        assert_eq!(category.trail_color, Some(TrailColor::from_rgb([1, 2, 3])));
+        assert_eq!(category.trail_width, Some(TrailWidth(3.0)));
+        assert_eq!(category.trail_style, None);
+
+        let rules = &settings.marker_preset_rules;
+        assert_eq!(rules.rules.len(), 1);
+        assert_eq!(
+            rules.rules[0].condition,
+            MarkerPresetRuleCondition::MapId(1155)
+        );
+        assert_eq!(rules.rules[0].preset, "Raids");
+        assert_eq!(rules.fallback.as_deref(), Some("Default"));
+
+        let written = serde_json::to_value(&settings).unwrap();
+        assert_eq!(written["version"], 2);
+    }
+
+    #[test]
+    fn rejects_unknown_version() {
+        let result = read_settings(br#"{ "version": 3, "marker_presets": {} }"#);
+
+        assert!(matches!(result, Err(ReadSettingsError::UnknownVersion(3))));
+    }
+
+    #[test]
+    fn rejects_missing_version() {
+        let result = read_settings(br#"{ "marker_presets": {} }"#);
+
+        assert!(matches!(result, Err(ReadSettingsError::InvalidJson(_))));
+    }
+
+    #[test]
+    fn round_trips_v2() {
+        let mut settings = read_settings(SETTINGS_V1.as_bytes()).unwrap();
+        settings.default_trail_color = TrailColor([10, 20, 30, 128]);
+        settings.trail_gap_distance = TrailGapDistance(50.0);
+        settings.pack_directories.push(PackDirectory {
+            path: PathBuf::from("packs"),
+            enabled: false,
+        });
+
+        let written = serde_json::to_vec(&settings).unwrap();
+        let read = read_settings(&written).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&read).unwrap(),
+            serde_json::from_slice::<serde_json::Value>(&written).unwrap(),
+        );
+    }
+
+    #[test]
+    fn keeps_the_previous_content_if_writing_fails() {
+        let file_path =
+            std::env::temp_dir().join(format!("paths-atomic-{}.txt", std::process::id()));
+        std::fs::write(&file_path, "previous").unwrap();
+
+        let result = write_file_atomically(&file_path, |writer| {
+            writer.write_all(b"partial")?;
+
+            Err(io::Error::other("failed"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "previous");
+
+        let mut temp_file_path = file_path.clone().into_os_string();
+        temp_file_path.push(".tmp");
+        assert!(!Path::new(&temp_file_path).exists());
+
+        write_file_atomically(&file_path, |writer| writer.write_all(b"new")).unwrap();
+        assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "new");
+
+        std::fs::remove_file(&file_path).unwrap();
+    }
 }
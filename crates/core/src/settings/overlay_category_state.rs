@@ -0,0 +1,510 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Display,
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Write},
+    path::Path,
+};
+
+use log_err::LogErrOption;
+use xml::{
+    reader::{self, XmlEvent},
+    writer::{self, EmitterConfig},
+    EventReader,
+};
+
+use crate::markers::{MarkerCategoryTree, MarkerCategoryTreeNode};
+
+use super::{
+    create_marker_preset, write_file_atomically, MarkerCategorySetting, MarkerPresetError, Settings,
+};
+
+const TACO_CATEGORY_VISIBLE_KEY_PREFIX: &str = "CategoryVisible_";
+
+/// Other overlays whose category states can be imported and exported.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Overlay {
+    /// Uses the `CategoryVisible_<id>` entries of `TacOConfig.xml`.
+    TacO,
+    /// Uses the inactive categories listed in the `categories.txt` of the pathing module.
+    BlishHud,
+}
+
+impl Overlay {
+    pub const ALL: [Self; 2] = [Self::TacO, Self::BlishHud];
+}
+
+#[derive(Debug)]
+pub enum OverlayCategoryStateError {
+    Io(io::Error),
+    ReadXml(reader::Error),
+    WriteXml(writer::Error),
+    Preset(MarkerPresetError),
+}
+
+impl Display for OverlayCategoryStateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OverlayCategoryStateError::Io(err) => write!(f, "{err}"),
+            OverlayCategoryStateError::ReadXml(err) => write!(f, "invalid xml: {err}"),
+            OverlayCategoryStateError::WriteXml(err) => write!(f, "could not write xml: {err}"),
+            OverlayCategoryStateError::Preset(err) => write!(f, "could not create preset: {err}"),
+        }
+    }
+}
+
+impl From<io::Error> for OverlayCategoryStateError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// Category visibilities as stored by another overlay. The ids are lowercase. Categories that are not
+/// contained are visible.
+pub type OverlayCategoryState = HashMap<String, bool>;
+
+/// Creates a new preset from the state file of the overlay. Returns the ids of categories that are not part of
+/// the loaded marker packs.
+pub fn import_overlay_category_state(
+    settings: &mut Settings,
+    tree: &MarkerCategoryTree,
+    overlay: Overlay,
+    file_path: &Path,
+    name: &str,
+) -> Result<Vec<String>, OverlayCategoryStateError> {
+    let reader = BufReader::new(File::open(file_path)?);
+
+    let state = match overlay {
+        Overlay::TacO => read_taco_category_state(reader)?,
+        Overlay::BlishHud => read_blish_hud_category_state(reader)?,
+    };
+
+    create_marker_preset(settings, name).map_err(OverlayCategoryStateError::Preset)?;
+
+    let (preset, unknown_ids) = marker_preset_from_overlay_category_state(tree, &state);
+
+    settings.marker_presets.insert(name.to_owned(), preset);
+
+    Ok(unknown_ids)
+}
+
+/// Writes the current state of the tree to the state file of the overlay. Other settings of TacO are kept. The
+/// file is replaced only once it is written completely, since the overlays may not cope with a damaged one.
+pub fn export_overlay_category_state(
+    tree: &MarkerCategoryTree,
+    overlay: Overlay,
+    file_path: &Path,
+) -> Result<(), OverlayCategoryStateError> {
+    match overlay {
+        Overlay::TacO => {
+            let existing = match File::open(file_path) {
+                Ok(file) => read_taco_config_values(BufReader::new(file))?,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => vec![],
+                Err(err) => return Err(err.into()),
+            };
+
+            write_file_atomically(file_path, |writer| {
+                write_taco_category_state(writer, existing, tree)
+            })
+        }
+
+        Overlay::BlishHud => write_file_atomically(file_path, |writer| {
+            write_blish_hud_category_state(writer, tree).map_err(OverlayCategoryStateError::Io)
+        }),
+    }
+}
+
+pub fn read_taco_category_state<R: Read>(
+    reader: R,
+) -> Result<OverlayCategoryState, OverlayCategoryStateError> {
+    Ok(read_taco_config_values(reader)?
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let id = key.strip_prefix(TACO_CATEGORY_VISIBLE_KEY_PREFIX)?;
+
+            Some((id.to_lowercase(), value != "0"))
+        })
+        .collect())
+}
+
+fn read_taco_config_values<R: Read>(
+    reader: R,
+) -> Result<Vec<(String, String)>, OverlayCategoryStateError> {
+    let mut values = vec![];
+
+    for event in EventReader::new(reader) {
+        match event.map_err(OverlayCategoryStateError::ReadXml)? {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } if name.local_name.eq_ignore_ascii_case("ConfigValue") => {
+                let attribute = |attribute_name: &str| {
+                    attributes
+                        .iter()
+                        .find(|attr| attr.name.local_name.eq_ignore_ascii_case(attribute_name))
+                        .map(|attr| attr.value.clone())
+                };
+
+                if let (Some(key), Some(value)) = (attribute("Key"), attribute("Value")) {
+                    values.push((key, value));
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    Ok(values)
+}
+
+fn write_taco_category_state<W: Write>(
+    writer: W,
+    existing_values: Vec<(String, String)>,
+    tree: &MarkerCategoryTree,
+) -> Result<(), OverlayCategoryStateError> {
+    let category_values = overlay_category_state_from_tree(tree)
+        .into_iter()
+        .collect::<BTreeMap<_, _>>()
+        .into_iter()
+        .map(|(id, is_visible)| {
+            (
+                format!("{TACO_CATEGORY_VISIBLE_KEY_PREFIX}{id}"),
+                if is_visible { "1" } else { "0" }.to_owned(),
+            )
+        });
+
+    let values = existing_values
+        .into_iter()
+        .filter(|(key, _)| !key.starts_with(TACO_CATEGORY_VISIBLE_KEY_PREFIX))
+        .chain(category_values);
+
+    let mut writer = EmitterConfig::new()
+        .perform_indent(true)
+        .create_writer(writer);
+
+    let mut write = |event: writer::XmlEvent| {
+        writer
+            .write(event)
+            .map_err(OverlayCategoryStateError::WriteXml)
+    };
+
+    write(writer::XmlEvent::start_element("TacOConfig").into())?;
+
+    for (key, value) in values {
+        write(
+            writer::XmlEvent::start_element("ConfigValue")
+                .attr("Key", &key)
+                .attr("Value", &value)
+                .into(),
+        )?;
+        write(writer::XmlEvent::end_element().into())?;
+    }
+
+    write(writer::XmlEvent::end_element().into())
+}
+
+pub fn read_blish_hud_category_state<R: BufRead>(reader: R) -> io::Result<OverlayCategoryState> {
+    reader
+        .lines()
+        .filter(|line| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+        .map(|line| line.map(|line| (line.trim().to_lowercase(), false)))
+        .collect()
+}
+
+/// Lists the inactive categories whose parent is active. Their children are hidden by Blish HUD anyway.
+pub fn write_blish_hud_category_state<W: Write>(
+    mut writer: W,
+    tree: &MarkerCategoryTree,
+) -> io::Result<()> {
+    let state = overlay_category_state_from_tree(tree);
+
+    let mut inactive_ids = state
+        .iter()
+        .filter(|(id, is_visible)| {
+            let parent_is_visible = id
+                .rsplit_once('.')
+                .and_then(|(parent_id, _)| state.get(parent_id))
+                .map_or(true, |parent_is_visible| *parent_is_visible);
+
+            !**is_visible && parent_is_visible
+        })
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+
+    inactive_ids.sort();
+
+    for id in inactive_ids {
+        writeln!(writer, "{id}")?;
+    }
+
+    writer.flush()
+}
+
+/// Other overlays show categories by default and hide all children of hidden categories. The preset
+/// contains only the categories whose state differs from the one they inherit.
+pub fn marker_preset_from_overlay_category_state(
+    tree: &MarkerCategoryTree,
+    state: &OverlayCategoryState,
+) -> (HashMap<String, MarkerCategorySetting>, Vec<String>) {
+    let mut preset = HashMap::new();
+    let mut known_ids = HashSet::new();
+
+    fn visit(
+        parent: &MarkerCategoryTreeNode,
+        parent_is_visible: bool,
+        inherited_is_active: bool,
+        state: &OverlayCategoryState,
+        preset: &mut HashMap<String, MarkerCategorySetting>,
+        known_ids: &mut HashSet<String>,
+    ) {
+        for child in parent.children() {
+            let id = child.data().identifier.join(".");
+            let lowercase_id = id.to_lowercase();
+
+            let is_visible = parent_is_visible && state.get(&lowercase_id).copied().unwrap_or(true);
+
+            if is_visible != inherited_is_active {
+                preset.entry(id).or_default().active = Some(is_visible);
+            }
+
+            known_ids.insert(lowercase_id);
+
+            visit(&child, is_visible, is_visible, state, preset, known_ids);
+        }
+    }
+
+    // In contrast to the other overlays, categories are inactive by default here.
+    visit(
+        &tree.tree.root().log_unwrap(),
+        true,
+        false,
+        state,
+        &mut preset,
+        &mut known_ids,
+    );
+
+    let mut unknown_ids = state
+        .keys()
+        .filter(|id| !known_ids.contains(*id))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    unknown_ids.sort();
+
+    (preset, unknown_ids)
+}
+
+/// The effective visibility of every category with lowercase ids.
+pub fn overlay_category_state_from_tree(tree: &MarkerCategoryTree) -> OverlayCategoryState {
+    let mut state = HashMap::new();
+
+    fn visit(
+        parent: &MarkerCategoryTreeNode,
+        parent_is_active: bool,
+        state: &mut OverlayCategoryState,
+    ) {
+        for child in parent.children() {
+            let category = child.data();
+            let is_active = category.is_active.borrow().unwrap_or(parent_is_active);
+
+            state.insert(category.identifier.join(".").to_lowercase(), is_active);
+
+            visit(&child, is_active, state);
+        }
+    }
+
+    visit(&tree.tree.root().log_unwrap(), false, &mut state);
+
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{markers::set_category_active, settings::apply_marker_category_settings};
+
+    use super::*;
+
+    const IDS: [&str; 5] = [
+        "Tw.Raids",
+        "Tw.Fractals",
+        "Tw.Fractals.Cm",
+        "Hp",
+        "Hp.Maguuma",
+    ];
+
+    fn set_active(tree: &MarkerCategoryTree, id: &str, is_active: bool) {
+        let identifier = id.split('.').map(str::to_owned).collect::<Vec<_>>();
+
+        set_category_active(&tree.find(&identifier).unwrap(), is_active);
+    }
+
+    /// Tw is active, Tw.Fractals is not, so Tw.Fractals.Cm inherits being inactive like Hp and its children. The
+    /// overlays hide all children of hidden categories, so active children of inactive ones cannot round-trip.
+    fn configured_tree() -> MarkerCategoryTree {
+        let tree = MarkerCategoryTree::with_categories(&IDS);
+
+        set_active(&tree, "Tw", true);
+        set_active(&tree, "Tw.Fractals", false);
+
+        tree
+    }
+
+    fn state_after_import(state: &OverlayCategoryState) -> OverlayCategoryState {
+        let tree = MarkerCategoryTree::with_categories(&IDS);
+        let (preset, unknown_ids) = marker_preset_from_overlay_category_state(&tree, state);
+
+        assert_eq!(unknown_ids, Vec::<String>::new());
+
+        let mut settings = Settings::default();
+        settings
+            .marker_presets
+            .insert("Imported".to_owned(), preset);
+        settings.active_marker_preset = "Imported".to_owned();
+        apply_marker_category_settings(&settings, &tree);
+
+        overlay_category_state_from_tree(&tree)
+    }
+
+    #[test]
+    fn round_trips_taco_category_state() {
+        let tree = configured_tree();
+        let existing_values = vec![
+            ("MapTransparency".to_owned(), "0.5".to_owned()),
+            (
+                "CategoryVisible_removed.category".to_owned(),
+                "0".to_owned(),
+            ),
+        ];
+
+        let mut bytes = vec![];
+        write_taco_category_state(&mut bytes, existing_values, &tree).unwrap();
+
+        let values = read_taco_config_values(bytes.as_slice()).unwrap();
+        assert!(values.contains(&("MapTransparency".to_owned(), "0.5".to_owned())));
+        assert!(values.contains(&("CategoryVisible_tw.fractals".to_owned(), "0".to_owned())));
+        assert!(!values.iter().any(|(key, _)| key.contains("removed")));
+
+        let state = read_taco_category_state(bytes.as_slice()).unwrap();
+        let expected = overlay_category_state_from_tree(&tree);
+
+        assert_eq!(state, expected);
+        assert_eq!(state_after_import(&state), expected);
+    }
+
+    #[test]
+    fn round_trips_blish_hud_category_state() {
+        let tree = configured_tree();
+
+        let mut bytes = vec![];
+        write_blish_hud_category_state(&mut bytes, &tree).unwrap();
+
+        // The children of inactive categories are hidden anyway.
+        assert_eq!(
+            String::from_utf8(bytes.clone()).unwrap(),
+            "hp\ntw.fractals\n"
+        );
+
+        let state = read_blish_hud_category_state(bytes.as_slice()).unwrap();
+
+        assert_eq!(
+            state_after_import(&state),
+            overlay_category_state_from_tree(&tree)
+        );
+    }
+
+    #[test]
+    fn round_trips_through_the_files_of_the_overlays() {
+        let dir = std::env::temp_dir().join(format!(
+            "paths-overlay-category-state-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let tree = configured_tree();
+        let expected = overlay_category_state_from_tree(&tree);
+
+        for (overlay, file_name) in [
+            (Overlay::TacO, "TacOConfig.xml"),
+            (Overlay::BlishHud, "categories.txt"),
+        ] {
+            let file_path = dir.join(file_name);
+            export_overlay_category_state(&tree, overlay, &file_path).unwrap();
+
+            let imported = MarkerCategoryTree::with_categories(&IDS);
+            let mut settings = Settings::default();
+            let unknown_ids =
+                import_overlay_category_state(&mut settings, &imported, overlay, &file_path, "New")
+                    .unwrap();
+
+            assert_eq!(unknown_ids, Vec::<String>::new());
+
+            settings.active_marker_preset = "New".to_owned();
+            apply_marker_category_settings(&settings, &imported);
+
+            assert_eq!(overlay_category_state_from_tree(&imported), expected);
+
+            assert!(matches!(
+                import_overlay_category_state(&mut settings, &imported, overlay, &file_path, "New"),
+                Err(OverlayCategoryStateError::Preset(
+                    MarkerPresetError::AlreadyExists
+                ))
+            ));
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reads_malformed_taco_config_values() {
+        let xml = r#"<TacOConfig>
+            <ConfigValue Key="CategoryVisible_Tw.Raids" Value="0"/>
+            <configvalue key="CategoryVisible_Hp" value="yes"/>
+            <ConfigValue Key="CategoryVisible_Tw.Fractals"/>
+            <ConfigValue Value="0"/>
+            <ConfigValue Key="MapTransparency" Value="0"/>
+        </TacOConfig>"#;
+
+        let state = read_taco_category_state(xml.as_bytes()).unwrap();
+
+        assert_eq!(
+            state,
+            HashMap::from([("tw.raids".to_owned(), false), ("hp".to_owned(), true)])
+        );
+
+        assert!(matches!(
+            read_taco_category_state("<TacOConfig><ConfigValue".as_bytes()),
+            Err(OverlayCategoryStateError::ReadXml(_))
+        ));
+    }
+
+    #[test]
+    fn reads_malformed_blish_hud_lines() {
+        let state =
+            read_blish_hud_category_state("  Tw.Raids \n\n   \r\nHp\r\n".as_bytes()).unwrap();
+
+        assert_eq!(
+            state,
+            HashMap::from([("tw.raids".to_owned(), false), ("hp".to_owned(), false)])
+        );
+
+        assert!(read_blish_hud_category_state([b'a', 0xff, b'\n'].as_slice()).is_err());
+    }
+
+    #[test]
+    fn reports_unknown_ids() {
+        let tree = MarkerCategoryTree::with_categories(&IDS);
+        let state = HashMap::from([
+            ("tw.raids".to_owned(), false),
+            ("unknown.b".to_owned(), false),
+            ("unknown.a".to_owned(), true),
+        ]);
+
+        let (preset, unknown_ids) = marker_preset_from_overlay_category_state(&tree, &state);
+
+        assert_eq!(unknown_ids, ["unknown.a", "unknown.b"]);
+
+        // The other categories are visible like in the overlays, only the raids are not.
+        assert_eq!(preset["Tw"].active, Some(true));
+        assert_eq!(preset["Tw.Raids"].active, Some(false));
+        assert!(!preset.contains_key("Tw.Fractals"));
+    }
+}
@@ -0,0 +1,33 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::Settings;
+
+/// A directory with marker packs in addition to the one in the addon directory. For example TacO's `POIs`
+/// folder.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PackDirectory {
+    pub path: PathBuf,
+
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// All directories to load packs from. The addon's own directory is always included.
+pub fn marker_pack_dirs(settings: &Settings, addon_pack_dir: &Path) -> Vec<PathBuf> {
+    let mut dirs = vec![addon_pack_dir.to_owned()];
+
+    for dir in settings.pack_directories.iter().filter(|dir| dir.enabled) {
+        // Loading the same packs twice would duplicate all markers.
+        if !dirs.contains(&dir.path) {
+            dirs.push(dir.path.clone());
+        }
+    }
+
+    dirs
+}
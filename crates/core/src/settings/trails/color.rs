@@ -1,14 +1,31 @@
 use std::ops::Deref;
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+/// An sRGB color with unmultiplied alpha.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 #[repr(transparent)]
-#[serde(transparent)]
-pub struct TrailColor(pub [u8; 3]);
+pub struct TrailColor(pub [u8; 4]);
+
+impl TrailColor {
+    pub const fn from_rgb([r, g, b]: [u8; 3]) -> Self {
+        Self([r, g, b, u8::MAX])
+    }
+
+    pub fn alpha(&self) -> u8 {
+        self.0[3]
+    }
+
+    /// Multiplies the alpha with the factor between 0 and 1, for example with the alpha of a pack.
+    pub fn scale_alpha(self, factor: f32) -> Self {
+        let [r, g, b, a] = self.0;
+
+        Self([r, g, b, (a as f32 * factor.clamp(0.0, 1.0)).round() as u8])
+    }
+}
 
 impl Deref for TrailColor {
-    type Target = [u8; 3];
+    type Target = [u8; 4];
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -17,6 +34,35 @@ impl Deref for TrailColor {
 
 impl Default for TrailColor {
     fn default() -> Self {
-        Self([255, 0, 200])
+        Self::from_rgb([255, 0, 200])
+    }
+}
+
+/// Opaque colors are written as RGB like before alpha was supported, so older versions can still read them.
+impl Serialize for TrailColor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let [r, g, b, a] = self.0;
+
+        if a == u8::MAX {
+            [r, g, b].serialize(serializer)
+        } else {
+            self.0.serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TrailColor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Components {
+            Rgb([u8; 3]),
+            Rgba([u8; 4]),
+        }
+
+        Ok(match Components::deserialize(deserializer)? {
+            Components::Rgb(rgb) => Self::from_rgb(rgb),
+            Components::Rgba(rgba) => Self(rgba),
+        })
     }
 }
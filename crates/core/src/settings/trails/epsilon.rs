@@ -7,6 +7,11 @@ use serde::{Deserialize, Serialize};
 #[serde(transparent)]
 pub struct TrailSimplifyEpsilon(pub f32);
 
+impl TrailSimplifyEpsilon {
+    pub const MIN: f32 = 0.0001;
+    pub const MAX: f32 = 1.0;
+}
+
 impl Default for TrailSimplifyEpsilon {
     fn default() -> Self {
         Self(0.001)
@@ -0,0 +1,24 @@
+use std::ops::Deref;
+
+use serde::{Deserialize, Serialize};
+
+/// Trails are split where consecutive points are horizontally farther apart than this. In the coordinates of
+/// the trail files, which are meters in the game world.
+#[derive(Debug, Deserialize, Serialize)]
+#[repr(transparent)]
+#[serde(transparent)]
+pub struct TrailGapDistance(pub f32);
+
+impl Default for TrailGapDistance {
+    fn default() -> Self {
+        Self(100.0)
+    }
+}
+
+impl Deref for TrailGapDistance {
+    type Target = f32;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
@@ -1,7 +1,15 @@
 mod color;
 mod epsilon;
+mod gap;
+mod overrides;
+mod simplifier;
+mod style;
 mod width;
 
 pub use self::color::TrailColor;
 pub use self::epsilon::TrailSimplifyEpsilon;
+pub use self::gap::TrailGapDistance;
+pub use self::overrides::TrailOverride;
+pub use self::simplifier::TrailSimplifier;
+pub use self::style::{TrailLineStyle, TrailStyle};
 pub use self::width::TrailWidth;
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+use super::{TrailColor, TrailWidth};
+
+/// The settings of a single trail. The color and width are inherited from its category if not set.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct TrailOverride {
+    #[serde(skip_serializing_if = "is_false")]
+    pub hidden: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<TrailColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<TrailWidth>,
+    /// Draws the arrows in the opposite direction.
+    #[serde(skip_serializing_if = "is_false")]
+    pub reversed: bool,
+}
+
+impl TrailOverride {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+/// The algorithm used to reduce the points of trails before drawing them.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrailSimplifier {
+    /// Keeps the points farthest from the simplified line.
+    #[default]
+    RamerDouglasPeucker,
+    /// Removes the points with the least visible effect first.
+    VisvalingamWhyatt,
+}
+
+impl TrailSimplifier {
+    pub const ALL: [Self; 2] = [Self::RamerDouglasPeucker, Self::VisvalingamWhyatt];
+}
@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+use super::TrailColor;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrailLineStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+impl TrailLineStyle {
+    pub const ALL: [Self; 3] = [Self::Solid, Self::Dashed, Self::Dotted];
+}
+
+/// How the trails of a category are drawn besides their color and width.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct TrailStyle {
+    pub line: TrailLineStyle,
+    pub arrows: bool,
+    /// The distance between two arrows in map units.
+    pub arrow_spacing: f32,
+    pub outline: bool,
+    /// Black or white, depending on the brightness of the trail, if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outline_color: Option<TrailColor>,
+}
+
+impl TrailStyle {
+    pub const MIN_ARROW_SPACING: f32 = 100.0;
+    pub const MAX_ARROW_SPACING: f32 = 2000.0;
+}
+
+impl Default for TrailStyle {
+    fn default() -> Self {
+        Self {
+            line: TrailLineStyle::Solid,
+            arrows: true,
+            arrow_spacing: 500.0,
+            outline: true,
+            outline_color: None,
+        }
+    }
+}
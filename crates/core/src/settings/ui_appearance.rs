@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UiTheme {
+    #[default]
+    Light,
+    Dark,
+    Custom,
+}
+
+/// Colors that replace the ones of the light or dark theme.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct CustomTheme {
+    pub dark: bool,
+    pub background_color: [u8; 3],
+    pub text_color: [u8; 3],
+    pub accent_color: [u8; 3],
+}
+
+impl Default for CustomTheme {
+    fn default() -> Self {
+        Self {
+            dark: true,
+            background_color: [27, 27, 27],
+            text_color: [220, 220, 220],
+            accent_color: [0, 92, 128],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct UiAppearance {
+    pub theme: UiTheme,
+    pub custom_theme: CustomTheme,
+    /// Scales the text and the widgets with it, independent of the interface size of the game.
+    pub font_scale: f32,
+    pub high_contrast: bool,
+}
+
+impl UiAppearance {
+    pub const MIN_FONT_SCALE: f32 = 0.75;
+    pub const MAX_FONT_SCALE: f32 = 2.5;
+}
+
+impl Default for UiAppearance {
+    fn default() -> Self {
+        Self {
+            theme: UiTheme::default(),
+            custom_theme: CustomTheme::default(),
+            font_scale: 1.0,
+            high_contrast: false,
+        }
+    }
+}
+
+/// The last state of a window, restored on the next start.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct WindowSetting {
+    pub open: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<[f32; 2]>,
+}
@@ -1,4 +1,6 @@
 pub mod v1;
+pub mod v2;
 
-pub type Settings = v1::SettingsV1;
-pub type MarkerCategorySetting = v1::MarkerCategorySettingV1;
+// Older versions are only read. They are migrated step by step to the current version with `From` impls.
+pub type Settings = v2::SettingsV2;
+pub type MarkerCategorySetting = v2::MarkerCategorySettingV2;
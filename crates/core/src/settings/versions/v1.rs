@@ -1,16 +1,16 @@
 use std::collections::HashMap;
 
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 
-use crate::settings::{TrailColor, TrailSimplifyEpsilon, TrailWidth};
+use crate::settings::{
+    MarkerPresetRules, TrailColor, TrailSimplifyEpsilon, TrailWidth, DEFAULT_MARKER_PRESET_NAME,
+};
 
 type Name = String;
 type CategoryId = String;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Deserialize)]
 pub struct SettingsV1 {
-    version: usize,
-
     #[serde(default)]
     pub default_trail_color: TrailColor,
 
@@ -23,32 +23,22 @@ pub struct SettingsV1 {
     #[serde(default)]
     pub limit_markers_to_current_map: bool,
 
-    #[serde(skip_serializing_if = "HashMap::is_empty")]
-    pub marker_presets: HashMap<Name, HashMap<CategoryId, MarkerCategorySettingV1>>,
-}
-
-impl Default for SettingsV1 {
-    fn default() -> Self {
-        Self {
-            version: 1,
+    #[serde(default = "default_active_marker_preset")]
+    pub active_marker_preset: Name,
 
-            default_trail_color: TrailColor::default(),
-            default_trail_width: TrailWidth::default(),
-            trail_simplify_epsilon: TrailSimplifyEpsilon::default(),
+    pub marker_presets: HashMap<Name, HashMap<CategoryId, MarkerCategorySettingV1>>,
 
-            limit_markers_to_current_map: false,
+    #[serde(default)]
+    pub marker_preset_rules: MarkerPresetRules,
+}
 
-            marker_presets: HashMap::new(),
-        }
-    }
+fn default_active_marker_preset() -> Name {
+    DEFAULT_MARKER_PRESET_NAME.to_owned()
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Deserialize)]
 pub struct MarkerCategorySettingV1 {
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub active: Option<bool>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub trail_color: Option<TrailColor>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub trail_width: Option<TrailWidth>,
 }
@@ -0,0 +1,157 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::localization::Language;
+use crate::markers::TrailId;
+use crate::settings::{
+    CompassCalibration, MarkerPresetRules, PackDirectory, TrailColor, TrailGapDistance,
+    TrailOverride, TrailSimplifier, TrailSimplifyEpsilon, TrailStyle, TrailWidth, UiAppearance,
+    UiSize, WindowSetting, DEFAULT_MARKER_PRESET_NAME,
+};
+
+use super::v1::{MarkerCategorySettingV1, SettingsV1};
+
+type Name = String;
+type CategoryId = String;
+
+/// Compared to v1, every field is optional. Missing fields fall back to their defaults instead of making the
+/// whole file unreadable.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SettingsV2 {
+    version: usize,
+
+    pub default_trail_color: TrailColor,
+
+    pub default_trail_width: TrailWidth,
+
+    pub default_trail_style: TrailStyle,
+
+    pub trail_simplify_epsilon: TrailSimplifyEpsilon,
+
+    pub trail_simplifier: TrailSimplifier,
+
+    pub trail_gap_distance: TrailGapDistance,
+
+    pub limit_markers_to_current_map: bool,
+
+    pub active_marker_preset: Name,
+
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub marker_presets: HashMap<Name, HashMap<CategoryId, MarkerCategorySettingV2>>,
+
+    #[serde(skip_serializing_if = "MarkerPresetRules::is_empty")]
+    pub marker_preset_rules: MarkerPresetRules,
+
+    /// Shared by all presets, because they belong to the content of a pack rather than to a selection.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub trail_overrides: BTreeMap<TrailId, TrailOverride>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pack_directories: Vec<PackDirectory>,
+
+    /// Follows the language of the game if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<Language>,
+
+    pub ui_appearance: UiAppearance,
+
+    /// Only for the interface sizes where the compass is misaligned.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub compass_calibrations: BTreeMap<UiSize, CompassCalibration>,
+
+    /// By window id.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub windows: BTreeMap<String, WindowSetting>,
+}
+
+impl Default for SettingsV2 {
+    fn default() -> Self {
+        Self {
+            version: 2,
+
+            default_trail_color: TrailColor::default(),
+            default_trail_width: TrailWidth::default(),
+            default_trail_style: TrailStyle::default(),
+            trail_simplify_epsilon: TrailSimplifyEpsilon::default(),
+            trail_simplifier: TrailSimplifier::default(),
+            trail_gap_distance: TrailGapDistance::default(),
+
+            limit_markers_to_current_map: false,
+
+            active_marker_preset: DEFAULT_MARKER_PRESET_NAME.to_owned(),
+            marker_presets: HashMap::new(),
+
+            marker_preset_rules: MarkerPresetRules::default(),
+
+            trail_overrides: BTreeMap::new(),
+
+            pack_directories: vec![],
+
+            language: None,
+
+            ui_appearance: UiAppearance::default(),
+
+            compass_calibrations: BTreeMap::new(),
+
+            windows: BTreeMap::new(),
+        }
+    }
+}
+
+/// Compared to v1, categories can have a line style.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MarkerCategorySettingV2 {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trail_color: Option<TrailColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trail_width: Option<TrailWidth>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trail_style: Option<TrailStyle>,
+}
+
+impl From<MarkerCategorySettingV1> for MarkerCategorySettingV2 {
+    fn from(v1: MarkerCategorySettingV1) -> Self {
+        Self {
+            active: v1.active,
+            trail_color: v1.trail_color,
+            trail_width: v1.trail_width,
+            trail_style: None,
+        }
+    }
+}
+
+impl From<SettingsV1> for SettingsV2 {
+    fn from(v1: SettingsV1) -> Self {
+        Self {
+            default_trail_color: v1.default_trail_color,
+            default_trail_width: v1.default_trail_width,
+            trail_simplify_epsilon: v1.trail_simplify_epsilon,
+            trail_simplifier: TrailSimplifier::default(),
+            trail_gap_distance: TrailGapDistance::default(),
+
+            limit_markers_to_current_map: v1.limit_markers_to_current_map,
+
+            active_marker_preset: v1.active_marker_preset,
+            marker_presets: v1
+                .marker_presets
+                .into_iter()
+                .map(|(name, preset)| {
+                    let preset = preset
+                        .into_iter()
+                        .map(|(id, setting)| (id, setting.into()))
+                        .collect();
+
+                    (name, preset)
+                })
+                .collect(),
+
+            marker_preset_rules: v1.marker_preset_rules,
+
+            ..Self::default()
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use egui::{Color32, Context, Stroke, Style, Visuals};
+
+use crate::settings::{CustomTheme, UiAppearance, UiTheme};
+
+pub fn apply_ui_appearance(ctx: &Context, appearance: &UiAppearance) {
+    let mut style = Style {
+        visuals: visuals(appearance),
+        ..Style::default()
+    };
+
+    style.interaction.selectable_labels = false;
+
+    scale(
+        &mut style,
+        appearance
+            .font_scale
+            .clamp(UiAppearance::MIN_FONT_SCALE, UiAppearance::MAX_FONT_SCALE),
+    );
+
+    ctx.set_style(style);
+}
+
+fn visuals(appearance: &UiAppearance) -> Visuals {
+    let mut visuals = match appearance.theme {
+        UiTheme::Light => Visuals::light(),
+        UiTheme::Dark => Visuals::dark(),
+        UiTheme::Custom => custom_visuals(&appearance.custom_theme),
+    };
+
+    if appearance.high_contrast {
+        apply_high_contrast(&mut visuals);
+    }
+
+    visuals
+}
+
+fn custom_visuals(theme: &CustomTheme) -> Visuals {
+    let mut visuals = if theme.dark {
+        Visuals::dark()
+    } else {
+        Visuals::light()
+    };
+
+    let [r, g, b] = theme.background_color;
+    let background_color = Color32::from_rgb(r, g, b);
+
+    let [r, g, b] = theme.text_color;
+    let text_color = Color32::from_rgb(r, g, b);
+
+    let [r, g, b] = theme.accent_color;
+    let accent_color = Color32::from_rgb(r, g, b);
+
+    visuals.window_fill = background_color;
+    visuals.panel_fill = background_color;
+    visuals.override_text_color = Some(text_color);
+    visuals.hyperlink_color = accent_color;
+    visuals.selection.bg_fill = accent_color;
+    visuals.widgets.hovered.bg_stroke.color = accent_color;
+
+    visuals
+}
+
+/// Draws text and the outlines of widgets in black or white.
+fn apply_high_contrast(visuals: &mut Visuals) {
+    let color = if visuals.dark_mode {
+        Color32::WHITE
+    } else {
+        Color32::BLACK
+    };
+
+    visuals.override_text_color = Some(color);
+    visuals.window_stroke = Stroke::new(2.0, color);
+    visuals.selection.stroke = Stroke::new(2.0, color);
+
+    for widget in [
+        &mut visuals.widgets.noninteractive,
+        &mut visuals.widgets.inactive,
+        &mut visuals.widgets.hovered,
+        &mut visuals.widgets.active,
+        &mut visuals.widgets.open,
+    ] {
+        widget.fg_stroke = Stroke::new(widget.fg_stroke.width.max(1.5), color);
+        widget.bg_stroke = Stroke::new(widget.bg_stroke.width.max(1.0), color);
+    }
+}
+
+/// Scales the text together with the widgets containing it.
+fn scale(style: &mut Style, factor: f32) {
+    for font in style.text_styles.values_mut() {
+        font.size *= factor;
+    }
+
+    let spacing = &mut style.spacing;
+
+    spacing.item_spacing *= factor;
+    spacing.button_padding *= factor;
+    spacing.interact_size *= factor;
+    spacing.indent *= factor;
+    spacing.icon_width *= factor;
+    spacing.icon_width_inner *= factor;
+    spacing.icon_spacing *= factor;
+}
@@ -0,0 +1,97 @@
+use egui::{Context, Grid, Slider, Ui};
+
+use crate::{
+    localization::tr,
+    settings::{CustomTheme, Settings, UiAppearance, UiTheme},
+};
+
+use super::{utils::restorable_window, UiActions};
+
+pub struct AppearanceWindow<A: UiActions> {
+    pub actions: A,
+    pub open: bool,
+}
+
+impl<A: UiActions> AppearanceWindow<A> {
+    pub const ID: &'static str = "appearance_window";
+
+    pub fn render(&mut self, ctx: &Context, settings: &mut Settings) {
+        restorable_window(ctx, tr("Appearance"), Self::ID)
+            .open(&mut self.open)
+            .auto_sized()
+            .show(ctx, |ui| {
+                let appearance = &mut settings.ui_appearance;
+                let mut changed = false;
+
+                ui.horizontal(|ui| {
+                    ui.label(tr("Theme:"));
+
+                    changed |= ui
+                        .radio_value(&mut appearance.theme, UiTheme::Light, tr("Light"))
+                        .changed();
+                    changed |= ui
+                        .radio_value(&mut appearance.theme, UiTheme::Dark, tr("Dark"))
+                        .changed();
+                    changed |= ui
+                        .radio_value(&mut appearance.theme, UiTheme::Custom, tr("Custom"))
+                        .changed();
+                });
+
+                if appearance.theme == UiTheme::Custom {
+                    changed |= custom_theme_editor(ui, &mut appearance.custom_theme);
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label(tr("Text size:"));
+
+                    changed |= ui
+                        .add(
+                            Slider::new(
+                                &mut appearance.font_scale,
+                                UiAppearance::MIN_FONT_SCALE..=UiAppearance::MAX_FONT_SCALE,
+                            )
+                            .step_by(0.05)
+                            .custom_formatter(|scale, _| format!("{:.0} %", scale * 100.0)),
+                        )
+                        .changed();
+                });
+
+                changed |= ui
+                    .checkbox(&mut appearance.high_contrast, tr("High contrast"))
+                    .changed();
+
+                if ui.button(tr("Reset")).clicked() {
+                    *appearance = UiAppearance::default();
+                    changed = true;
+                }
+
+                if changed {
+                    self.actions.save_settings();
+                }
+            });
+    }
+}
+
+fn custom_theme_editor(ui: &mut Ui, theme: &mut CustomTheme) -> bool {
+    let mut changed = false;
+
+    ui.indent("custom_theme", |ui| {
+        changed |= ui.checkbox(&mut theme.dark, tr("Dark base")).changed();
+
+        Grid::new("custom_theme_colors")
+            .num_columns(2)
+            .show(ui, |ui| {
+                for (label, color) in [
+                    (tr("Background:"), &mut theme.background_color),
+                    (tr("Text:"), &mut theme.text_color),
+                    (tr("Accent:"), &mut theme.accent_color),
+                ] {
+                    ui.label(label);
+                    changed |= ui.color_edit_button_srgb(color).changed();
+                    ui.end_row();
+                }
+            });
+    });
+
+    changed
+}
@@ -1,11 +1,11 @@
-use std::iter::once;
+use std::{iter::once, slice::from_ref};
 
-use egui::{Context, Window};
+use egui::Context;
 
-use crate::markers::MarkerCategoryTreeNode;
+use crate::{localization::tr, markers::MarkerCategoryTreeNode};
 
 use super::{
-    utils::{trail_color_selector, trail_width_selector},
+    utils::{restorable_window, trail_color_selector, trail_style_selector, trail_width_selector},
     UiActions,
 };
 
@@ -15,10 +15,12 @@ pub struct CategoryPropertiesWindow<'a, A: UiActions> {
 }
 
 impl<'a, A: UiActions> CategoryPropertiesWindow<'a, A> {
+    pub const ID: &'static str = "category_properties_window";
+
     pub fn render(&mut self, ctx: &Context) {
         let mut open = self.current_category_node.is_some();
 
-        Window::new("Category properties")
+        restorable_window(ctx, tr("Category properties"), Self::ID)
             .open(&mut open)
             .auto_sized()
             .show(ctx, |ui| {
@@ -40,8 +42,11 @@ impl<'a, A: UiActions> CategoryPropertiesWindow<'a, A> {
 
                     ui.label(path.join(" > "));
 
-                    trail_color_selector(&self.actions, ui, "Route color:", node, true);
-                    trail_width_selector(&self.actions, ui, "Route width:", node, true);
+                    let nodes = from_ref(node);
+
+                    trail_color_selector(&self.actions, ui, tr("Route color:"), nodes, true);
+                    trail_width_selector(&self.actions, ui, tr("Route width:"), nodes, true);
+                    trail_style_selector(&self.actions, ui, tr("Route style:"), nodes, true);
                 }
             });
 
@@ -0,0 +1,105 @@
+use egui::{Button, Color32, Context, DragValue, Grid, Id, LayerId, Order, Stroke};
+
+use crate::{
+    localization::tr,
+    maps::{compass_rect, CompassLayout},
+    settings::{Settings, UiSize},
+};
+
+use super::{utils::restorable_window, UiActions};
+
+pub struct CompassCalibrationWindow<A: UiActions> {
+    pub actions: A,
+    pub open: bool,
+}
+
+impl<A: UiActions> CompassCalibrationWindow<A> {
+    pub const ID: &'static str = "compass_calibration_window";
+
+    /// `compass` is `None` while the compass is hidden, for example while the map is open.
+    pub fn render(
+        &mut self,
+        ctx: &Context,
+        settings: &mut Settings,
+        compass: Option<&CompassLayout>,
+    ) {
+        restorable_window(ctx, tr("Minimap calibration"), Self::ID)
+            .open(&mut self.open)
+            .auto_sized()
+            .show(ctx, |ui| {
+                let Some(compass) = compass else {
+                    ui.label(tr("Close the map to calibrate the minimap."));
+                    return;
+                };
+
+                ui.label(tr(
+                    "Move the outline until it matches the minimap. The correction only applies to the current interface size.",
+                ));
+
+                ui.label(format!(
+                    "{} {}",
+                    tr("Interface size:"),
+                    ui_size_name(compass.ui_size)
+                ));
+
+                let mut calibration = settings
+                    .compass_calibrations
+                    .get(&compass.ui_size)
+                    .copied()
+                    .unwrap_or_default();
+
+                let mut changed = false;
+
+                Grid::new("compass_calibration").show(ui, |ui| {
+                    for (label, value) in [
+                        (tr("Horizontal offset:"), &mut calibration.offset_x),
+                        (tr("Vertical offset:"), &mut calibration.offset_y),
+                        (tr("Extra width:"), &mut calibration.extra_width),
+                        (tr("Extra height:"), &mut calibration.extra_height),
+                    ] {
+                        ui.label(label);
+                        changed |= ui
+                            .add(DragValue::new(value).speed(0.5).suffix(" px"))
+                            .changed();
+                        ui.end_row();
+                    }
+                });
+
+                if ui
+                    .add_enabled(!calibration.is_default(), Button::new(tr("Reset")))
+                    .clicked()
+                {
+                    calibration = Default::default();
+                    changed = true;
+                }
+
+                if changed {
+                    if calibration.is_default() {
+                        settings.compass_calibrations.remove(&compass.ui_size);
+                    } else {
+                        settings
+                            .compass_calibrations
+                            .insert(compass.ui_size, calibration);
+                    }
+
+                    self.actions.save_settings();
+                }
+
+                ctx.layer_painter(LayerId::new(Order::Foreground, Id::new(Self::ID)))
+                    .rect_stroke(
+                        compass_rect(compass, &calibration),
+                        0.0,
+                        Stroke::new(2.0, Color32::RED),
+                    );
+            });
+    }
+}
+
+fn ui_size_name(ui_size: UiSize) -> &'static str {
+    match ui_size {
+        UiSize::Small => tr("Small"),
+        UiSize::Normal => tr("Normal"),
+        UiSize::Large => tr("Large"),
+        UiSize::Larger => tr("Larger"),
+    }
+}
@@ -1,15 +1,23 @@
-use egui::{Context, Ui, Window};
+use egui::{Button, ComboBox, Context, Slider, TextEdit, Ui};
+use log::warn;
 use log_err::LogErrOption;
 
 use crate::{
     loadable::BackgroundLoadable,
+    localization::{tr, Language},
     markers::{ActiveMarkerCategories, MarkerCategoryTree},
-    settings::Settings,
+    settings::{
+        create_marker_preset, delete_marker_preset, duplicate_marker_preset,
+        export_marker_selection_code, import_marker_selection_code, marker_preset_names,
+        rename_marker_preset, switch_marker_preset, unused_marker_preset_name, Settings,
+        TrailSimplifier, TrailSimplifyEpsilon,
+    },
 };
 
 use super::{
     utils::{
-        format_categories, format_points, format_trails, trail_color_selector, trail_width_selector,
+        format_categories, format_points, format_trails, restorable_window, trail_color_selector,
+        trail_style_selector, trail_width_selector, unknown_categories_message,
     },
     UiActions,
 };
@@ -17,9 +25,14 @@ use super::{
 pub struct MainWindow<A: UiActions> {
     pub actions: A,
     pub open: bool,
+    pub preset_name_input: String,
+    pub settings_error: Option<String>,
+    pub selection_code_message: Option<String>,
 }
 
 impl<A: UiActions> MainWindow<A> {
+    pub const ID: &'static str = "main_window";
+
     pub fn render(
         &mut self,
         ctx: &Context,
@@ -28,10 +41,12 @@ impl<A: UiActions> MainWindow<A> {
         active_marker_categories: &ActiveMarkerCategories,
         settings: &mut Settings,
     ) {
-        Window::new("Paths")
+        restorable_window(ctx, "Paths", Self::ID)
             .open(&mut self.open)
             .auto_sized()
             .show(ctx, |ui| {
+                settings_error(ui, &mut self.settings_error);
+
                 let is_loading_settings = matches!(tree, BackgroundLoadable::Loading);
 
                 active_markers_info(
@@ -51,27 +66,81 @@ impl<A: UiActions> MainWindow<A> {
                 );
 
                 if let BackgroundLoadable::Loaded(tree) = tree {
-                    let root_node = tree.tree.root().log_unwrap();
+                    ui.separator();
+
+                    marker_preset_selector(
+                        &self.actions,
+                        ui,
+                        tree,
+                        settings,
+                        &mut self.preset_name_input,
+                    );
+
+                    marker_selection_code_buttons(
+                        &self.actions,
+                        ui,
+                        tree,
+                        settings,
+                        &mut self.preset_name_input,
+                        &mut self.selection_code_message,
+                    );
+
+                    ui.separator();
+
+                    let root_node = [tree.tree.root().log_unwrap()];
 
                     trail_color_selector(
                         &self.actions,
                         ui,
-                        "Default route color:",
+                        tr("Default route color:"),
                         &root_node,
                         false,
                     );
                     trail_width_selector(
                         &self.actions,
                         ui,
-                        "Default route width:",
+                        tr("Default route width:"),
+                        &root_node,
+                        false,
+                    );
+                    trail_style_selector(
+                        &self.actions,
+                        ui,
+                        tr("Default route style:"),
                         &root_node,
                         false,
                     );
+
+                    trail_simplification_selector(&self.actions, ui, settings);
                 }
+
+                ui.separator();
+
+                language_selector(&self.actions, ui, &mut settings.language);
             });
     }
 }
 
+fn settings_error(ui: &mut Ui, settings_error: &mut Option<String>) {
+    let Some(error) = settings_error else {
+        return;
+    };
+
+    let mut dismiss = false;
+
+    ui.horizontal(|ui| {
+        ui.colored_label(ui.visuals().error_fg_color, error.as_str());
+
+        dismiss = ui.small_button(tr("Dismiss")).clicked();
+    });
+
+    if dismiss {
+        *settings_error = None;
+    }
+
+    ui.separator();
+}
+
 fn active_markers_info<A: UiActions>(
     actions: &A,
     ui: &mut Ui,
@@ -79,7 +148,7 @@ fn active_markers_info<A: UiActions>(
     active_marker_categories: &ActiveMarkerCategories,
 ) {
     ui.horizontal(|ui| {
-        let label = "Active markers".to_owned();
+        let label = tr("Active markers");
 
         if is_loading {
             ui.label(label);
@@ -96,7 +165,7 @@ fn active_markers_info<A: UiActions>(
                 format_trails(active_marker_categories.all_active_trails().count()),
             ));
 
-            if ui.link("change...").clicked() {
+            if ui.link(tr("change...")).clicked() {
                 actions.display_marker_tree_window();
             }
         }
@@ -111,7 +180,7 @@ fn limit_to_current_map_checkbox<A: UiActions>(
     active_marker_categories: &ActiveMarkerCategories,
     limit_markers_to_current_map: &mut bool,
 ) {
-    let mut label = "limit to current map".to_owned();
+    let mut label = tr("limit to current map").to_owned();
 
     if !is_loading_settings && is_in_gameplay {
         label = format!(
@@ -133,3 +202,298 @@ fn limit_to_current_map_checkbox<A: UiActions>(
         actions.save_settings();
     }
 }
+
+enum MarkerPresetAction {
+    Switch(String),
+    Create(String),
+    Duplicate(String),
+    Rename(String),
+    Delete,
+}
+
+fn marker_preset_selector<A: UiActions>(
+    actions: &A,
+    ui: &mut Ui,
+    tree: &MarkerCategoryTree,
+    settings: &mut Settings,
+    preset_name_input: &mut String,
+) {
+    let mut action = None;
+
+    ui.horizontal(|ui| {
+        ui.label(tr("Preset:"));
+
+        ComboBox::from_id_salt("marker_preset")
+            .selected_text(&settings.active_marker_preset)
+            .show_ui(ui, |ui| {
+                for name in marker_preset_names(settings) {
+                    let is_active = name == settings.active_marker_preset;
+
+                    if ui.selectable_label(is_active, &name).clicked() && !is_active {
+                        action = Some(MarkerPresetAction::Switch(name));
+                    }
+                }
+            });
+
+        let can_delete = marker_preset_names(settings).len() > 1;
+
+        if ui
+            .add_enabled(can_delete, Button::new(tr("Delete")))
+            .on_hover_text(tr("Delete the active preset"))
+            .clicked()
+        {
+            action = Some(MarkerPresetAction::Delete);
+        }
+
+        if ui.link(tr("rules...")).clicked() {
+            actions.display_marker_preset_rules_window();
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.add(
+            TextEdit::singleline(preset_name_input)
+                .hint_text(tr("Preset name"))
+                .desired_width(120.0),
+        );
+
+        let name = preset_name_input.trim();
+        let is_valid_name =
+            !name.is_empty() && !marker_preset_names(settings).iter().any(|n| n == name);
+
+        ui.add_enabled_ui(is_valid_name, |ui| {
+            if ui
+                .button(tr("New"))
+                .on_hover_text(tr("Create an empty preset"))
+                .clicked()
+            {
+                action = Some(MarkerPresetAction::Create(name.to_owned()));
+            }
+
+            if ui
+                .button(tr("Duplicate"))
+                .on_hover_text(tr("Copy the active preset"))
+                .clicked()
+            {
+                action = Some(MarkerPresetAction::Duplicate(name.to_owned()));
+            }
+
+            if ui
+                .button(tr("Rename"))
+                .on_hover_text(tr("Rename the active preset"))
+                .clicked()
+            {
+                action = Some(MarkerPresetAction::Rename(name.to_owned()));
+            }
+        });
+    });
+
+    let Some(action) = action else {
+        return;
+    };
+
+    let active_preset = settings.active_marker_preset.clone();
+
+    let result = match &action {
+        MarkerPresetAction::Switch(name) => switch_marker_preset(settings, tree, name),
+
+        MarkerPresetAction::Create(name) => create_marker_preset(settings, name)
+            .and_then(|_| switch_marker_preset(settings, tree, name)),
+
+        MarkerPresetAction::Duplicate(name) => {
+            duplicate_marker_preset(settings, &active_preset, name)
+                .and_then(|_| switch_marker_preset(settings, tree, name))
+        }
+
+        MarkerPresetAction::Rename(name) => rename_marker_preset(settings, &active_preset, name),
+
+        MarkerPresetAction::Delete => {
+            delete_marker_preset(settings, &active_preset).and_then(|_| {
+                let next_preset = settings.active_marker_preset.clone();
+
+                switch_marker_preset(settings, tree, &next_preset)
+            })
+        }
+    };
+
+    match result {
+        Ok(()) => {
+            if !matches!(action, MarkerPresetAction::Switch(_)) {
+                preset_name_input.clear();
+            }
+
+            actions.update_active_marker_categories();
+            actions.save_settings();
+        }
+
+        Err(err) => warn!("could not change marker presets: {err:?}"),
+    }
+}
+
+fn marker_selection_code_buttons<A: UiActions>(
+    actions: &A,
+    ui: &mut Ui,
+    tree: &MarkerCategoryTree,
+    settings: &mut Settings,
+    preset_name_input: &mut String,
+    message: &mut Option<String>,
+) {
+    ui.horizontal(|ui| {
+        ui.label(tr("Selection code:"));
+
+        if ui
+            .button(tr("Copy"))
+            .on_hover_text(tr("Copy the active preset as a code to share it"))
+            .clicked()
+        {
+            // Persists the latest changes of the tree to the preset.
+            actions.save_settings();
+
+            let code = export_marker_selection_code(settings);
+            ui.output_mut(|output| output.copied_text = code);
+
+            *message = Some(tr("Copied the code to the clipboard.").to_owned());
+        }
+
+        if ui
+            .button(tr("Import"))
+            .on_hover_text(tr("Create a new preset from the code in the clipboard"))
+            .clicked()
+        {
+            *message = Some(import_marker_selection_code_from_clipboard(
+                actions,
+                tree,
+                settings,
+                preset_name_input,
+            ));
+        }
+
+        if ui.link(tr("other overlays...")).clicked() {
+            actions.display_overlay_import_window();
+        }
+    });
+
+    if let Some(text) = message {
+        ui.label(text.as_str());
+    }
+}
+
+fn import_marker_selection_code_from_clipboard<A: UiActions>(
+    actions: &A,
+    tree: &MarkerCategoryTree,
+    settings: &mut Settings,
+    preset_name_input: &mut String,
+) -> String {
+    let Some(code) = actions.clipboard_text() else {
+        return tr("The clipboard does not contain text.").to_owned();
+    };
+
+    let name = match preset_name_input.trim() {
+        "" => unused_marker_preset_name(settings, tr("Imported")),
+        name => name.to_owned(),
+    };
+
+    let unknown_ids = match import_marker_selection_code(settings, tree, &name, &code) {
+        Ok(unknown_ids) => unknown_ids,
+        Err(err) => {
+            return tr("Could not import the code: {err}").replace("{err}", &err.to_string())
+        }
+    };
+
+    if let Err(err) = switch_marker_preset(settings, tree, &name) {
+        warn!("could not switch to imported marker preset: {err:?}");
+    }
+
+    preset_name_input.clear();
+
+    actions.update_active_marker_categories();
+    actions.save_settings();
+
+    let message = tr("Imported the code as preset {name}.").replace("{name}", &name);
+
+    if unknown_ids.is_empty() {
+        message
+    } else {
+        format!("{message} {}", unknown_categories_message(&unknown_ids))
+    }
+}
+
+fn trail_simplification_selector<A: UiActions>(actions: &A, ui: &mut Ui, settings: &mut Settings) {
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        ui.label(tr("Route simplification:"));
+
+        ComboBox::from_id_salt("trail_simplifier")
+            .selected_text(trail_simplifier_name(settings.trail_simplifier))
+            .show_ui(ui, |ui| {
+                for simplifier in TrailSimplifier::ALL {
+                    changed |= ui
+                        .selectable_value(
+                            &mut settings.trail_simplifier,
+                            simplifier,
+                            trail_simplifier_name(simplifier),
+                        )
+                        .changed();
+                }
+            });
+
+        changed |= ui
+            .add(
+                Slider::new(
+                    &mut settings.trail_simplify_epsilon.0,
+                    TrailSimplifyEpsilon::MIN..=TrailSimplifyEpsilon::MAX,
+                )
+                .logarithmic(true)
+                .suffix(" m"),
+            )
+            .on_hover_text(tr(
+                "Smallest detail of routes that is kept. Zoomed out maps use coarser versions of the routes.",
+            ))
+            .changed();
+    });
+
+    if changed {
+        actions.save_settings();
+    }
+}
+
+fn trail_simplifier_name(simplifier: TrailSimplifier) -> &'static str {
+    match simplifier {
+        TrailSimplifier::RamerDouglasPeucker => "Ramer–Douglas–Peucker",
+        TrailSimplifier::VisvalingamWhyatt => "Visvalingam–Whyatt",
+    }
+}
+
+fn language_selector<A: UiActions>(actions: &A, ui: &mut Ui, language: &mut Option<Language>) {
+    ui.horizontal(|ui| {
+        ui.label(tr("Language:"));
+
+        let mut changed = false;
+
+        ComboBox::from_id_salt("language")
+            .selected_text(language.map_or(tr("Game"), Language::name))
+            .show_ui(ui, |ui| {
+                changed |= ui
+                    .selectable_value(language, None, tr("Game"))
+                    .on_hover_text(tr("Follow the language of the game as set in Nexus"))
+                    .changed();
+
+                for l in Language::ALL {
+                    changed |= ui.selectable_value(language, Some(l), l.name()).changed();
+                }
+            });
+
+        if changed {
+            actions.save_settings();
+        }
+
+        if ui.link(tr("appearance...")).clicked() {
+            actions.display_appearance_window();
+        }
+
+        if ui.link(tr("minimap...")).clicked() {
+            actions.display_compass_calibration_window();
+        }
+    });
+}
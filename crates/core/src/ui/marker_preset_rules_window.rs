@@ -0,0 +1,295 @@
+use egui::{Button, ComboBox, Context, DragValue, Grid, TextEdit, Ui};
+
+use crate::{
+    localization::tr,
+    settings::{
+        marker_preset_names, MapCategory, MarkerPresetRule, MarkerPresetRuleCondition,
+        PlayerContext, Profession, Settings,
+    },
+};
+
+use super::{utils::restorable_window, UiActions};
+
+pub struct MarkerPresetRulesWindow<A: UiActions> {
+    pub actions: A,
+    pub open: bool,
+}
+
+impl<A: UiActions> MarkerPresetRulesWindow<A> {
+    pub const ID: &'static str = "marker_preset_rules_window";
+
+    pub fn render(
+        &mut self,
+        ctx: &Context,
+        settings: &mut Settings,
+        player: Option<&PlayerContext>,
+    ) {
+        restorable_window(ctx, tr("Preset rules"), Self::ID)
+            .open(&mut self.open)
+            .auto_sized()
+            .show(ctx, |ui| {
+                ui.label(tr("The first matching rule selects the active preset."));
+
+                let preset_names = marker_preset_names(settings);
+
+                let mut changed = marker_preset_rules(ui, settings, &preset_names);
+
+                ui.separator();
+
+                changed |= add_rule_buttons(ui, settings, &preset_names, player);
+
+                ui.separator();
+
+                changed |= fallback_selector(ui, settings, &preset_names);
+
+                if changed {
+                    self.actions.save_settings();
+                    self.actions.apply_marker_preset_rules();
+                }
+            });
+    }
+}
+
+fn marker_preset_rules(ui: &mut Ui, settings: &mut Settings, preset_names: &[String]) -> bool {
+    let rules = &mut settings.marker_preset_rules.rules;
+
+    if rules.is_empty() {
+        ui.label(tr("No rules yet."));
+
+        return false;
+    }
+
+    let mut changed = false;
+    let mut move_up = None;
+    let mut remove = None;
+
+    Grid::new("marker_preset_rules")
+        .num_columns(4)
+        .show(ui, |ui| {
+            for (idx, rule) in rules.iter_mut().enumerate() {
+                ui.label(condition_label(&rule.condition));
+
+                changed |= condition_value_editor(ui, idx, &mut rule.condition);
+
+                ui.horizontal(|ui| {
+                    ui.label("→");
+
+                    changed |=
+                        preset_selector(ui, ("rule_preset", idx), &mut rule.preset, preset_names);
+                });
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(idx > 0, Button::new("⏶").small())
+                        .on_hover_text(tr("Move up"))
+                        .clicked()
+                    {
+                        move_up = Some(idx);
+                    }
+
+                    if ui
+                        .small_button("🗑")
+                        .on_hover_text(tr("Remove rule"))
+                        .clicked()
+                    {
+                        remove = Some(idx);
+                    }
+                });
+
+                ui.end_row();
+            }
+        });
+
+    if let Some(idx) = move_up {
+        rules.swap(idx - 1, idx);
+        changed = true;
+    }
+
+    if let Some(idx) = remove {
+        rules.remove(idx);
+        changed = true;
+    }
+
+    changed
+}
+
+fn condition_value_editor(
+    ui: &mut Ui,
+    idx: usize,
+    condition: &mut MarkerPresetRuleCondition,
+) -> bool {
+    match condition {
+        MarkerPresetRuleCondition::CharacterName(name) => ui
+            .add(
+                TextEdit::singleline(name)
+                    .hint_text(tr("Character name"))
+                    .desired_width(120.0),
+            )
+            .changed(),
+
+        MarkerPresetRuleCondition::Profession(profession) => {
+            let mut changed = false;
+
+            ComboBox::from_id_salt(("rule_profession", idx))
+                .selected_text(profession_label(*profession))
+                .show_ui(ui, |ui| {
+                    for p in Profession::ALL {
+                        changed |= ui
+                            .selectable_value(profession, p, profession_label(p))
+                            .changed();
+                    }
+                });
+
+            changed
+        }
+
+        MarkerPresetRuleCondition::MapId(map_id) => ui.add(DragValue::new(map_id)).changed(),
+
+        MarkerPresetRuleCondition::MapCategory(category) => {
+            let mut changed = false;
+
+            ComboBox::from_id_salt(("rule_map_category", idx))
+                .selected_text(map_category_label(*category))
+                .show_ui(ui, |ui| {
+                    for c in MapCategory::ALL {
+                        changed |= ui
+                            .selectable_value(category, c, map_category_label(c))
+                            .changed();
+                    }
+                });
+
+            changed
+        }
+    }
+}
+
+fn add_rule_buttons(
+    ui: &mut Ui,
+    settings: &mut Settings,
+    preset_names: &[String],
+    player: Option<&PlayerContext>,
+) -> bool {
+    let mut condition = None;
+
+    ui.horizontal(|ui| {
+        ui.label(tr("Add rule for the current"));
+
+        // Prefill the rule with the current values. These can be changed afterwards.
+        let player = player.cloned().unwrap_or_default();
+
+        if ui.button(tr("character")).clicked() {
+            condition = Some(MarkerPresetRuleCondition::CharacterName(
+                player.character_name.clone(),
+            ));
+        }
+
+        if ui.button(tr("profession")).clicked() {
+            condition = Some(MarkerPresetRuleCondition::Profession(
+                player.profession.unwrap_or(Profession::Guardian),
+            ));
+        }
+
+        if ui.button(tr("map")).clicked() {
+            condition = Some(MarkerPresetRuleCondition::MapId(player.map_id));
+        }
+
+        if ui.button(tr("map type")).clicked() {
+            condition = Some(MarkerPresetRuleCondition::MapCategory(
+                player.map_category.unwrap_or(MapCategory::Pve),
+            ));
+        }
+    });
+
+    let Some(condition) = condition else {
+        return false;
+    };
+
+    let preset = if preset_names.contains(&settings.active_marker_preset) {
+        settings.active_marker_preset.clone()
+    } else {
+        preset_names.first().cloned().unwrap_or_default()
+    };
+
+    settings
+        .marker_preset_rules
+        .rules
+        .push(MarkerPresetRule { condition, preset });
+
+    true
+}
+
+fn fallback_selector(ui: &mut Ui, settings: &mut Settings, preset_names: &[String]) -> bool {
+    let fallback = &mut settings.marker_preset_rules.fallback;
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        ui.label(tr("Otherwise:"));
+
+        ComboBox::from_id_salt("marker_preset_rules_fallback")
+            .selected_text(fallback.as_deref().unwrap_or(tr("keep the current preset")))
+            .show_ui(ui, |ui| {
+                changed |= ui
+                    .selectable_value(fallback, None, tr("keep the current preset"))
+                    .changed();
+
+                for name in preset_names {
+                    changed |= ui
+                        .selectable_value(fallback, Some(name.clone()), name)
+                        .changed();
+                }
+            });
+    });
+
+    changed
+}
+
+fn preset_selector(
+    ui: &mut Ui,
+    id_salt: impl std::hash::Hash,
+    preset: &mut String,
+    preset_names: &[String],
+) -> bool {
+    let mut changed = false;
+
+    ComboBox::from_id_salt(id_salt)
+        .selected_text(preset.as_str())
+        .show_ui(ui, |ui| {
+            for name in preset_names {
+                changed |= ui.selectable_value(preset, name.clone(), name).changed();
+            }
+        });
+
+    changed
+}
+
+fn condition_label(condition: &MarkerPresetRuleCondition) -> &'static str {
+    match condition {
+        MarkerPresetRuleCondition::CharacterName(_) => tr("Character"),
+        MarkerPresetRuleCondition::Profession(_) => tr("Profession"),
+        MarkerPresetRuleCondition::MapId(_) => tr("Map"),
+        MarkerPresetRuleCondition::MapCategory(_) => tr("Map type"),
+    }
+}
+
+fn profession_label(profession: Profession) -> &'static str {
+    match profession {
+        Profession::Guardian => tr("Guardian"),
+        Profession::Warrior => tr("Warrior"),
+        Profession::Engineer => tr("Engineer"),
+        Profession::Ranger => tr("Ranger"),
+        Profession::Thief => tr("Thief"),
+        Profession::Elementalist => tr("Elementalist"),
+        Profession::Mesmer => tr("Mesmer"),
+        Profession::Necromancer => tr("Necromancer"),
+        Profession::Revenant => tr("Revenant"),
+    }
+}
+
+fn map_category_label(category: MapCategory) -> &'static str {
+    match category {
+        MapCategory::Pve => "PvE",
+        MapCategory::Instance => tr("Instance"),
+        MapCategory::Pvp => "PvP",
+        MapCategory::Wvw => tr("WvW"),
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::HashSet;
+
+use log_err::LogErrOption;
+
+use crate::markers::{
+    ActiveMarkerCategories, MarkerCategoryFilter, MarkerCategoryTree, MarkerCategoryTreeNode,
+    NodeId,
+};
+
+/// A category or one of its trails as displayed in the flattened tree.
+#[derive(Debug)]
+pub struct MarkerTreeRow {
+    pub node_id: NodeId,
+    /// The index in the trails of the category if the row displays a trail.
+    pub trail_idx: Option<usize>,
+    pub depth: usize,
+    pub parent_is_active: bool,
+    pub is_expandable: bool,
+    pub is_expanded: bool,
+}
+
+#[derive(Debug, PartialEq)]
+struct MarkerTreeRowsKey {
+    tree_id: u64,
+    generation: u64,
+    current_map_id: u32,
+    filter: MarkerCategoryFilter,
+}
+
+/// The rows of the expanded part of the tree. They are only built again if the tree, the categories, the
+/// filter or the expanded categories change, so only the visible rows have to be rendered each frame.
+#[derive(Debug, Default)]
+pub struct MarkerTreeRows {
+    key: Option<MarkerTreeRowsKey>,
+    rows: Vec<MarkerTreeRow>,
+}
+
+impl MarkerTreeRows {
+    /// Has to be called after categories were expanded or collapsed.
+    pub fn invalidate(&mut self) {
+        self.key = None;
+    }
+
+    /// While filtering, all categories are expanded unless they were collapsed explicitly.
+    pub fn get(
+        &mut self,
+        tree: &MarkerCategoryTree,
+        active_marker_categories: &ActiveMarkerCategories,
+        filter: &MarkerCategoryFilter,
+        expanded: &HashSet<Vec<String>>,
+        collapsed_while_filtering: &HashSet<Vec<String>>,
+    ) -> &[MarkerTreeRow] {
+        let key = MarkerTreeRowsKey {
+            tree_id: tree.id(),
+            generation: active_marker_categories.generation(),
+            current_map_id: active_marker_categories.current_map_id(),
+            filter: filter.clone(),
+        };
+
+        if self.key.as_ref() != Some(&key) {
+            let visible_nodes =
+                (!filter.is_empty()).then(|| filter.visible_nodes(tree, key.current_map_id));
+
+            let is_expanded = |identifier: &[String]| match visible_nodes {
+                Some(_) => !collapsed_while_filtering.contains(identifier),
+                None => expanded.contains(identifier),
+            };
+
+            self.rows.clear();
+
+            collect_rows(
+                &tree.tree.root().log_unwrap(),
+                0,
+                false,
+                visible_nodes.as_ref(),
+                filter.only_current_map.then_some(key.current_map_id),
+                &is_expanded,
+                &mut self.rows,
+            );
+
+            self.key = Some(key);
+        }
+
+        &self.rows
+    }
+}
+
+fn collect_rows(
+    parent: &MarkerCategoryTreeNode,
+    depth: usize,
+    parent_is_active: bool,
+    visible_nodes: Option<&HashSet<NodeId>>,
+    trail_map_id: Option<u32>,
+    is_expanded_category: &impl Fn(&[String]) -> bool,
+    rows: &mut Vec<MarkerTreeRow>,
+) {
+    for child in parent
+        .children()
+        .filter(|child| is_displayed(child, visible_nodes))
+    {
+        let category = child.data();
+
+        let trail_idxs = category
+            .trails
+            .iter()
+            .enumerate()
+            .filter(|(_, trail)| trail_map_id.map_or(true, |map_id| trail.map_id == map_id))
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+
+        let is_expandable = !trail_idxs.is_empty()
+            || child
+                .children()
+                .any(|child| is_displayed(&child, visible_nodes));
+        let is_expanded = is_expandable && is_expanded_category(&category.identifier);
+
+        let is_active = category.is_active.borrow().unwrap_or(parent_is_active);
+
+        rows.push(MarkerTreeRow {
+            node_id: child.node_id(),
+            trail_idx: None,
+            depth,
+            parent_is_active,
+            is_expandable,
+            is_expanded,
+        });
+
+        if is_expanded {
+            collect_rows(
+                &child,
+                depth + 1,
+                is_active,
+                visible_nodes,
+                trail_map_id,
+                is_expanded_category,
+                rows,
+            );
+
+            rows.extend(trail_idxs.into_iter().map(|trail_idx| MarkerTreeRow {
+                node_id: child.node_id(),
+                trail_idx: Some(trail_idx),
+                depth: depth + 1,
+                parent_is_active: is_active,
+                is_expandable: false,
+                is_expanded: false,
+            }));
+        }
+    }
+}
+
+fn is_displayed(node: &MarkerCategoryTreeNode, visible_nodes: Option<&HashSet<NodeId>>) -> bool {
+    let category = node.data();
+
+    visible_nodes.map_or(true, |visible_nodes| {
+        visible_nodes.contains(&node.node_id())
+    }) && (category.total_point_of_interest_count > 0
+        || category.total_trail_count > 0
+        || category.is_separator)
+}
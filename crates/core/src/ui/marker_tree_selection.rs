@@ -0,0 +1,99 @@
+use std::collections::BTreeSet;
+
+use egui::Modifiers;
+
+use crate::markers::{MarkerCategoryTree, MarkerCategoryTreeNode};
+
+use super::marker_tree_rows::MarkerTreeRow;
+
+/// The categories selected for bulk editing. They are kept by their identifiers, so the selection survives
+/// reloading the marker packs.
+#[derive(Debug, Default)]
+pub struct MarkerTreeSelection {
+    selected: BTreeSet<Vec<String>>,
+    /// The category a range selection starts from.
+    anchor: Option<Vec<String>>,
+}
+
+impl MarkerTreeSelection {
+    pub fn is_empty(&self) -> bool {
+        self.selected.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.selected.len()
+    }
+
+    pub fn contains(&self, identifier: &[String]) -> bool {
+        self.selected.contains(identifier)
+    }
+
+    pub fn clear(&mut self) {
+        self.selected.clear();
+        self.anchor = None;
+    }
+
+    /// Ctrl adds or removes a single category and shift selects all displayed rows between the last
+    /// clicked category and this one. With both, the range is added to the selection.
+    pub fn click(
+        &mut self,
+        tree: &MarkerCategoryTree,
+        rows: &[MarkerTreeRow],
+        row_idx: usize,
+        modifiers: Modifiers,
+    ) {
+        let identifier = |row: &MarkerTreeRow| {
+            tree.tree
+                .get(row.node_id)
+                .map(|node| node.data().identifier.clone())
+        };
+
+        let Some(clicked) = identifier(&rows[row_idx]) else {
+            return;
+        };
+
+        let anchor_idx = self.anchor.as_ref().and_then(|anchor| {
+            rows.iter()
+                .position(|row| identifier(row).as_ref() == Some(anchor))
+        });
+
+        match anchor_idx {
+            Some(anchor_idx) if modifiers.shift => {
+                if !modifiers.command {
+                    self.selected.clear();
+                }
+
+                let range = anchor_idx.min(row_idx)..=anchor_idx.max(row_idx);
+
+                self.selected.extend(
+                    rows[range]
+                        .iter()
+                        .filter(|row| row.trail_idx.is_none() && !is_separator(tree, row))
+                        .filter_map(identifier),
+                );
+            }
+
+            _ => {
+                if !self.selected.remove(&clicked) {
+                    self.selected.insert(clicked.clone());
+                }
+
+                self.anchor = Some(clicked);
+            }
+        }
+    }
+
+    /// The selected categories which exist in the tree.
+    pub fn nodes<'a>(&self, tree: &'a MarkerCategoryTree) -> Vec<MarkerCategoryTreeNode<'a>> {
+        self.selected
+            .iter()
+            .filter_map(|identifier| tree.find(identifier))
+            .collect()
+    }
+}
+
+fn is_separator(tree: &MarkerCategoryTree, row: &MarkerTreeRow) -> bool {
+    tree.tree
+        .get(row.node_id)
+        .is_some_and(|node| node.data().is_separator)
+}
@@ -1,24 +1,55 @@
-use egui::{collapsing_header::CollapsingState, Align, Context, Layout, ScrollArea, Ui, Window};
+use std::collections::HashSet;
+
+use egui::{
+    collapsing_header::paint_default_icon, popup_below_widget, Align, Button, Checkbox, Context,
+    DragValue, Layout, Modifiers, PopupCloseBehavior, ScrollArea, Sense, Shape, TextEdit, Ui, Vec2,
+};
 use log_err::LogErrOption;
 
 use crate::{
     loadable::BackgroundLoadable,
-    markers::{MarkerCategoryTree, MarkerCategoryTreeNode},
+    localization::{tr, tr_plural},
+    markers::{
+        effective_trail_color, effective_trail_width, reset_category_settings,
+        reset_subtree_overrides, set_category_active, set_subtree_active, ActiveMarkerCategories,
+        MarkerCategoryFilter, MarkerCategoryTree, MarkerCategoryTreeNode, SubtreeActiveState,
+    },
+    settings::{TrailColor, TrailOverride, TrailWidth},
 };
 
 use super::{
-    utils::{format_categories, format_points, format_trails},
+    marker_tree_rows::{MarkerTreeRow, MarkerTreeRows},
+    marker_tree_selection::MarkerTreeSelection,
+    utils::{
+        format_categories, format_points, format_trails, restorable_window, trail_color_selector,
+        trail_style_selector, trail_width_selector,
+    },
     UiActions,
 };
 
 pub struct MarkerTreeWindow<A: UiActions> {
     pub actions: A,
     pub open: bool,
+    pub filter: MarkerCategoryFilter,
+    /// Identifiers of the expanded categories.
+    pub expanded: HashSet<Vec<String>>,
+    /// While filtering, categories are expanded by default. The state is kept separately, so the unfiltered
+    /// tree looks the same as before afterwards.
+    pub collapsed_while_filtering: HashSet<Vec<String>>,
+    pub rows: MarkerTreeRows,
+    pub selection: MarkerTreeSelection,
 }
 
 impl<A: UiActions> MarkerTreeWindow<A> {
-    pub fn render(&mut self, ctx: &Context, tree: &BackgroundLoadable<MarkerCategoryTree>) {
-        Window::new("Active markers")
+    pub const ID: &'static str = "marker_tree_window";
+
+    pub fn render(
+        &mut self,
+        ctx: &Context,
+        tree: &BackgroundLoadable<MarkerCategoryTree>,
+        active_marker_categories: &ActiveMarkerCategories,
+    ) {
+        restorable_window(ctx, tr("Active markers"), Self::ID)
             .open(&mut self.open)
             .show(ctx, |ui| {
                 marker_category_overview(&self.actions, ui, tree);
@@ -26,16 +57,116 @@ impl<A: UiActions> MarkerTreeWindow<A> {
                 if let BackgroundLoadable::Loaded(tree) = tree {
                     ui.separator();
 
-                    ScrollArea::vertical()
-                        .auto_shrink([false, true])
-                        .show(ui, |ui| {
-                            marker_category_tree(&self.actions, ui, tree);
-                        });
+                    marker_category_filter(ui, &mut self.filter);
+
+                    ui.separator();
+
+                    if !self.selection.is_empty() {
+                        marker_category_selection_editor(
+                            &self.actions,
+                            ui,
+                            tree,
+                            &mut self.selection,
+                        );
+
+                        ui.separator();
+                    }
+
+                    let rows = self.rows.get(
+                        tree,
+                        active_marker_categories,
+                        &self.filter,
+                        &self.expanded,
+                        &self.collapsed_while_filtering,
+                    );
+
+                    let mut toggled_category = None;
+                    let mut selection_click = None;
+
+                    ScrollArea::vertical().auto_shrink([false, true]).show_rows(
+                        ui,
+                        ui.spacing().interact_size.y,
+                        rows.len(),
+                        |ui, row_range| {
+                            for (row_idx, row) in rows
+                                .iter()
+                                .enumerate()
+                                .skip(row_range.start)
+                                .take(row_range.len())
+                            {
+                                if let Some(trail_idx) = row.trail_idx {
+                                    marker_trail_row(&self.actions, ui, tree, row, trail_idx);
+                                    continue;
+                                }
+
+                                let response = marker_category_row(
+                                    &self.actions,
+                                    ui,
+                                    tree,
+                                    row,
+                                    &self.selection,
+                                );
+
+                                if response.toggled {
+                                    toggled_category = Some(row.node_id);
+                                }
+
+                                if let Some(modifiers) = response.selection_click {
+                                    selection_click = Some((row_idx, modifiers));
+                                }
+                            }
+                        },
+                    );
+
+                    if let Some((row_idx, modifiers)) = selection_click {
+                        self.selection.click(tree, rows, row_idx, modifiers);
+                    }
+
+                    if let Some(node) = toggled_category.and_then(|node_id| tree.tree.get(node_id))
+                    {
+                        let identifier = &node.data().identifier;
+
+                        let categories = if self.filter.is_empty() {
+                            &mut self.expanded
+                        } else {
+                            &mut self.collapsed_while_filtering
+                        };
+
+                        if !categories.remove(identifier) {
+                            categories.insert(identifier.clone());
+                        }
+
+                        self.rows.invalidate();
+                    }
                 }
             });
     }
 }
 
+fn marker_category_filter(ui: &mut Ui, filter: &mut MarkerCategoryFilter) {
+    ui.horizontal(|ui| {
+        ui.add(
+            TextEdit::singleline(&mut filter.search)
+                .hint_text(tr("Search"))
+                .desired_width(200.0),
+        );
+
+        if ui
+            .add_enabled(!filter.search.is_empty(), Button::new("✖").small())
+            .on_hover_text(tr("Clear search"))
+            .clicked()
+        {
+            filter.search.clear();
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut filter.only_current_map, tr("current map"));
+        ui.checkbox(&mut filter.only_active, tr("active"));
+        ui.checkbox(&mut filter.only_customized, tr("customized"));
+    });
+}
+
 fn marker_category_overview<A: UiActions>(
     actions: &A,
     ui: &mut Ui,
@@ -43,7 +174,7 @@ fn marker_category_overview<A: UiActions>(
 ) {
     ui.horizontal_top(|ui| {
         ui.vertical(|ui| {
-            ui.label("Total markers:");
+            ui.label(tr("Total markers:"));
 
             ui.indent("marker_overview", |ui| {
                 if let BackgroundLoadable::Loaded(tree) = tree {
@@ -60,11 +191,15 @@ fn marker_category_overview<A: UiActions>(
             let is_loading = matches!(tree, BackgroundLoadable::Loading);
 
             ui.add_enabled_ui(!is_loading, |ui| {
-                if ui.button("Reload").clicked() {
+                if ui.button(tr("Reload")).clicked() {
                     actions.reload_settings();
                 }
 
-                if ui.button("Deselect all").clicked() {
+                if ui.button(tr("Pack folders")).clicked() {
+                    actions.display_pack_directories_window();
+                }
+
+                if ui.button(tr("Deselect all")).clicked() {
                     if let BackgroundLoadable::Loaded(tree) = tree {
                         for node in tree.tree.root().log_unwrap().traverse_level_order().skip(1) {
                             // Each node inherits the false state from the root.
@@ -75,113 +210,336 @@ fn marker_category_overview<A: UiActions>(
                         actions.save_settings();
                     }
                 }
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(actions.can_undo(), Button::new(tr("Undo")))
+                        .on_hover_text("Ctrl+Z")
+                        .clicked()
+                    {
+                        actions.undo();
+                    }
+
+                    if ui
+                        .add_enabled(actions.can_redo(), Button::new(tr("Redo")))
+                        .on_hover_text("Ctrl+Y")
+                        .clicked()
+                    {
+                        actions.redo();
+                    }
+                });
             });
         });
     });
 }
 
-fn marker_category_tree<A: UiActions>(actions: &A, ui: &mut Ui, tree: &MarkerCategoryTree) {
-    let root = tree.tree.root().log_expect("tree has no root node");
+fn marker_category_selection_editor<A: UiActions>(
+    actions: &A,
+    ui: &mut Ui,
+    tree: &MarkerCategoryTree,
+    selection: &mut MarkerTreeSelection,
+) {
+    let nodes = selection.nodes(tree);
+
+    ui.horizontal(|ui| {
+        ui.label(tr_plural(
+            selection.len(),
+            "{num} category selected",
+            "{num} categories selected",
+        ));
+
+        if ui
+            .small_button("✖")
+            .on_hover_text(tr("Clear selection"))
+            .clicked()
+        {
+            selection.clear();
+        }
+    });
+
+    trail_color_selector(actions, ui, tr("Route color:"), &nodes, true);
+    trail_width_selector(actions, ui, tr("Route width:"), &nodes, true);
+    trail_style_selector(actions, ui, tr("Route style:"), &nodes, true);
+
+    ui.horizontal(|ui| {
+        let mut changed = false;
+
+        if ui.button(tr("Enable")).clicked() {
+            for node in &nodes {
+                set_category_active(node, true);
+            }
+
+            changed = true;
+        }
+
+        if ui.button(tr("Disable")).clicked() {
+            for node in &nodes {
+                set_category_active(node, false);
+            }
+
+            changed = true;
+        }
+
+        if ui
+            .button(tr("Reset overrides"))
+            .on_hover_text(tr(
+                "Let the selected categories inherit the active state, color, width and style of their parents",
+            ))
+            .clicked()
+        {
+            for node in &nodes {
+                reset_category_settings(node);
+            }
+
+            changed = true;
+        }
+
+        if changed {
+            actions.update_active_marker_categories();
+            actions.save_settings();
+        }
+    });
+}
 
-    marker_category_nodes(actions, ui, tree, &root, false);
+struct MarkerCategoryRowResponse {
+    /// The category was expanded or collapsed.
+    toggled: bool,
+    /// The category was clicked with the modifiers for selecting it.
+    selection_click: Option<Modifiers>,
 }
 
-fn marker_category_nodes<A: UiActions>(
+fn marker_category_row<A: UiActions>(
     actions: &A,
     ui: &mut Ui,
     tree: &MarkerCategoryTree,
-    parent: &MarkerCategoryTreeNode,
-    parent_is_active: bool,
-) {
-    for child in parent.children() {
-        let category = child.data();
+    row: &MarkerTreeRow,
+    selection: &MarkerTreeSelection,
+) -> MarkerCategoryRowResponse {
+    let mut toggled = false;
+    let mut selection_click = None;
 
-        let mut point_of_interest_count = 0;
-        let mut trail_count = 0;
+    // The row may have been built from a tree that was replaced since.
+    let Some(child) = tree.tree.get(row.node_id) else {
+        return MarkerCategoryRowResponse {
+            toggled,
+            selection_click,
+        };
+    };
+    let category = child.data();
+
+    ui.horizontal(|ui| {
+        // Filled in once the size of the row is known.
+        let background = ui.painter().add(Shape::Noop);
+
+        ui.add_space(row.depth as f32 * ui.spacing().indent);
+
+        let (_, icon_rect) = ui.allocate_space(Vec2::splat(ui.spacing().icon_width));
 
-        for cat in child.traverse_pre_order().map(|n| n.data()) {
-            point_of_interest_count += cat.points_of_interest.len();
-            trail_count += cat.trails.len();
+        if row.is_expandable {
+            let response = ui.interact(icon_rect, ui.id().with(row.node_id), Sense::click());
+
+            paint_default_icon(ui, if row.is_expanded { 1.0 } else { 0.0 }, &response);
+
+            toggled = response.clicked();
         }
 
-        if point_of_interest_count == 0 && trail_count == 0 && !category.is_separator {
-            continue;
+        if category.is_separator {
+            ui.label(&category.label);
+            return;
         }
 
-        let mut child_is_active = category.is_active.borrow().unwrap_or(parent_is_active);
+        let mut child_is_active = category.is_active.borrow().unwrap_or(row.parent_is_active);
+
+        let is_mixed = category.subtree_active_state.get() == SubtreeActiveState::Mixed;
 
-        let mut row = |ui: &mut Ui| {
-            let checkbox = ui.checkbox(
+        let checkbox = ui.add(
+            Checkbox::new(
                 &mut child_is_active,
                 format!(
                     "{} ({}; {})",
                     category.label,
-                    format_points(point_of_interest_count),
-                    format_trails(trail_count),
+                    format_points(category.total_point_of_interest_count),
+                    format_trails(category.total_trail_count),
                 ),
+            )
+            .indeterminate(is_mixed),
+        );
+
+        let subtree_menu_id = ui.make_persistent_id((&category.identifier, "subtree_menu"));
+        let mut changed = false;
+
+        let modifiers = ui.input(|input| input.modifiers);
+
+        if checkbox.clicked() && (modifiers.command || modifiers.shift) {
+            // Selecting does not change the active state.
+            selection_click = Some(modifiers);
+        } else if checkbox.changed() && is_mixed {
+            // The state is kept until the user picked what to do with the mixed subcategories.
+            ui.memory_mut(|memory| memory.toggle_popup(subtree_menu_id));
+        } else if checkbox.changed() {
+            set_category_active(&child, child_is_active);
+
+            changed = true;
+        }
+
+        if child.first_child().is_some() {
+            popup_below_widget(
+                ui,
+                subtree_menu_id,
+                &checkbox,
+                PopupCloseBehavior::CloseOnClick,
+                |ui| changed |= subtree_active_menu(ui, &child),
             );
 
-            if checkbox.changed() {
-                *category.is_active.borrow_mut() = Some(child_is_active);
+            checkbox.context_menu(|ui| changed |= subtree_active_menu(ui, &child));
+        }
+
+        if changed {
+            actions.update_active_marker_categories();
+            actions.save_settings();
+        }
 
-                fn inherit_active_state_if_possible(
-                    parent: &MarkerCategoryTreeNode,
-                    is_active: bool,
-                ) {
-                    for child in parent.children() {
-                        let category = child.data();
-                        let mut child_is_active = category.is_active.borrow_mut();
+        if ui
+            .small_button("⚙")
+            .on_hover_text(tr("Edit route color and width"))
+            .clicked()
+        {
+            actions.display_category_properties_window(child.node_id());
+        }
 
-                        if let Some(child_is_active_) = *child_is_active {
-                            // This category is explicitly enabled or disabled.
+        if selection.contains(&category.identifier) {
+            ui.painter().set(
+                background,
+                Shape::rect_filled(
+                    ui.min_rect(),
+                    ui.visuals().widgets.noninteractive.rounding,
+                    ui.visuals().selection.bg_fill,
+                ),
+            );
+        }
+    });
 
-                            if child_is_active_ == is_active {
-                                // Now it has the same state as its parent's.
-                                *child_is_active = None;
+    MarkerCategoryRowResponse {
+        toggled,
+        selection_click,
+    }
+}
 
-                                inherit_active_state_if_possible(&child, is_active);
-                            } else {
-                                // The active state is different than its parent's. Skip this sub tree.
-                            }
-                        } else {
-                            inherit_active_state_if_possible(&child, is_active);
-                        }
-                    }
-                }
+fn marker_trail_row<A: UiActions>(
+    actions: &A,
+    ui: &mut Ui,
+    tree: &MarkerCategoryTree,
+    row: &MarkerTreeRow,
+    trail_idx: usize,
+) {
+    let Some(node) = tree.tree.get(row.node_id) else {
+        return;
+    };
+    let Some(trail) = node.data().trails.get(trail_idx) else {
+        return;
+    };
 
-                inherit_active_state_if_possible(&child, child_is_active);
+    let mut overrides = *trail.overrides.borrow();
 
-                actions.update_active_marker_categories();
-                actions.save_settings();
-            };
+    ui.horizontal(|ui| {
+        // Aligned with the checkboxes of the categories on the same level.
+        ui.add_space(
+            row.depth as f32 * ui.spacing().indent
+                + ui.spacing().icon_width
+                + ui.spacing().item_spacing.x,
+        );
 
-            if ui
-                .small_button("⚙")
-                .on_hover_text("Edit route color and width")
-                .clicked()
-            {
-                actions.display_category_properties_window(child.node_id());
-            }
-        };
+        let mut is_visible = !overrides.hidden;
 
-        if category.is_separator {
-            ui.indent(&category.label, |ui| {
-                ui.label(&category.label);
-            });
-        } else if child.children().count() == 0 {
-            ui.indent(&category.label, |ui| {
-                ui.horizontal(row);
-            });
-        } else {
-            let id = ui.make_persistent_id(&category.identifier);
-
-            CollapsingState::load_with_default_open(ui.ctx(), id, false)
-                .show_header(ui, |ui| {
-                    row(ui);
-                })
-                .body(|ui| {
-                    marker_category_nodes(actions, ui, tree, &child, child_is_active);
-                });
+        if ui
+            .checkbox(
+                &mut is_visible,
+                format!("{} ({})", trail.label(), format_points(trail.points.len())),
+            )
+            .on_hover_text(tr("Show this route"))
+            .changed()
+        {
+            overrides.hidden = !is_visible;
+        }
+
+        let mut color = overrides
+            .color
+            .unwrap_or_else(|| effective_trail_color(&node))
+            .0;
+
+        if ui
+            .color_edit_button_srgba_unmultiplied(&mut color)
+            .on_hover_text(tr("Route color"))
+            .changed()
+        {
+            overrides.color = Some(TrailColor(color));
+        }
+
+        let mut width = overrides
+            .width
+            .unwrap_or_else(|| effective_trail_width(&node))
+            .0;
+
+        if ui
+            .add(
+                DragValue::new(&mut width)
+                    .range(TrailWidth::MIN..=TrailWidth::MAX)
+                    .speed(0.1),
+            )
+            .on_hover_text(tr("Route width"))
+            .changed()
+        {
+            overrides.width = Some(TrailWidth(width));
+        }
+
+        ui.toggle_value(&mut overrides.reversed, "⇄")
+            .on_hover_text(tr("Reverse the direction of the route"));
+
+        if !overrides.is_default()
+            && ui
+                .small_button("🗑")
+                .on_hover_text(tr("Reset to the values of the category"))
+                .clicked()
+        {
+            overrides = TrailOverride::default();
         }
+    });
+
+    if overrides != *trail.overrides.borrow() {
+        *trail.overrides.borrow_mut() = overrides;
+
+        actions.update_active_marker_categories();
+        actions.save_settings();
+    }
+}
+
+fn subtree_active_menu(ui: &mut Ui, node: &MarkerCategoryTreeNode) -> bool {
+    let mut changed = false;
+
+    if ui.button(tr("Enable all")).clicked() {
+        set_subtree_active(node, true);
+        changed = true;
+    }
+
+    if ui.button(tr("Disable all")).clicked() {
+        set_subtree_active(node, false);
+        changed = true;
+    }
+
+    if ui
+        .button(tr("Reset overrides"))
+        .on_hover_text(tr(
+            "Let all subcategories inherit the state of this category",
+        ))
+        .clicked()
+    {
+        reset_subtree_overrides(node);
+        changed = true;
+    }
+
+    if changed {
+        ui.close_menu();
     }
+
+    changed
 }
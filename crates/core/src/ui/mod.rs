@@ -1,24 +1,63 @@
+mod appearance;
+mod appearance_window;
 mod category_properties_window;
+mod compass_calibration_window;
 mod main_window;
+mod marker_preset_rules_window;
+mod marker_tree_rows;
+mod marker_tree_selection;
 mod marker_tree_window;
+mod overlay_import_window;
+mod pack_directories_window;
 mod utils;
 
-use egui::{Context, Visuals};
+use std::collections::HashSet;
+
+use egui::{Context, Id, Key, KeyboardShortcut, Modifiers, Pos2};
 use nary_tree::NodeId;
 
-use crate::markers::ActiveMarkerCategories;
-use crate::settings::Settings;
+use self::utils::{restore_window_position, window_position};
+
+use crate::maps::CompassLayout;
+use crate::markers::{ActiveMarkerCategories, MarkerCategoryFilter};
+use crate::settings::{Overlay, PlayerContext, Settings, UiAppearance, WindowSetting};
 use crate::{loadable::BackgroundLoadable, markers::MarkerCategoryTree};
 
+pub use self::appearance::apply_ui_appearance;
+pub use self::appearance_window::AppearanceWindow;
 pub use self::category_properties_window::CategoryPropertiesWindow;
+pub use self::compass_calibration_window::CompassCalibrationWindow;
 pub use self::main_window::MainWindow;
+pub use self::marker_preset_rules_window::MarkerPresetRulesWindow;
+pub use self::marker_tree_rows::MarkerTreeRows;
+pub use self::marker_tree_selection::MarkerTreeSelection;
 pub use self::marker_tree_window::MarkerTreeWindow;
+pub use self::overlay_import_window::OverlayImportWindow;
+pub use self::pack_directories_window::PackDirectoriesWindow;
+
+/// What the UI shows in a frame besides the settings, which it can change.
+pub struct UiFrame<'a> {
+    pub tree: &'a BackgroundLoadable<MarkerCategoryTree>,
+    pub is_in_gameplay: bool,
+    pub active_marker_categories: &'a ActiveMarkerCategories<'a>,
+    pub player: Option<&'a PlayerContext>,
+    /// `None` while the compass is hidden.
+    pub compass: Option<&'a CompassLayout>,
+}
 
 pub struct UiState<'a, A: UiActions> {
     pub actions: A,
     pub ui_was_displayed_once: bool,
+    /// The appearance the egui style was last built from.
+    pub applied_ui_appearance: Option<UiAppearance>,
+    pub window_settings_restored: bool,
     pub main_window: MainWindow<A>,
     pub marker_tree_window: MarkerTreeWindow<A>,
+    pub marker_preset_rules_window: MarkerPresetRulesWindow<A>,
+    pub overlay_import_window: OverlayImportWindow<A>,
+    pub pack_directories_window: PackDirectoriesWindow<A>,
+    pub appearance_window: AppearanceWindow<A>,
+    pub compass_calibration_window: CompassCalibrationWindow<A>,
     pub category_properties_window: CategoryPropertiesWindow<'a, A>,
 }
 
@@ -27,13 +66,47 @@ impl<A: UiActions + Copy> UiState<'_, A> {
         Self {
             actions,
             ui_was_displayed_once: false,
+            applied_ui_appearance: None,
+            window_settings_restored: false,
             main_window: MainWindow {
                 actions,
                 open: false,
+                preset_name_input: String::new(),
+                settings_error: None,
+                selection_code_message: None,
             },
             marker_tree_window: MarkerTreeWindow {
                 actions,
                 open: false,
+                filter: MarkerCategoryFilter::default(),
+                expanded: HashSet::new(),
+                collapsed_while_filtering: HashSet::new(),
+                rows: MarkerTreeRows::default(),
+                selection: MarkerTreeSelection::default(),
+            },
+            marker_preset_rules_window: MarkerPresetRulesWindow {
+                actions,
+                open: false,
+            },
+            overlay_import_window: OverlayImportWindow {
+                actions,
+                open: false,
+                overlay: Overlay::TacO,
+                file_path: String::new(),
+                message: None,
+            },
+            pack_directories_window: PackDirectoriesWindow {
+                actions,
+                open: false,
+                path_input: String::new(),
+            },
+            appearance_window: AppearanceWindow {
+                actions,
+                open: false,
+            },
+            compass_calibration_window: CompassCalibrationWindow {
+                actions,
+                open: false,
             },
             category_properties_window: CategoryPropertiesWindow {
                 actions,
@@ -44,16 +117,30 @@ impl<A: UiActions + Copy> UiState<'_, A> {
 }
 
 impl<A: UiActions> UiState<'_, A> {
-    pub fn render(
-        &mut self,
-        _screen_width: f32,
-        _screen_height: f32,
-        ctx: &Context,
-        tree: &BackgroundLoadable<MarkerCategoryTree>,
-        is_in_gameplay: bool,
-        settings: &mut Settings,
-        active_marker_categories: &ActiveMarkerCategories,
-    ) {
+    pub fn render(&mut self, ctx: &Context, frame: &UiFrame, settings: &mut Settings) {
+        let UiFrame {
+            tree,
+            is_in_gameplay,
+            active_marker_categories,
+            player,
+            compass,
+        } = *frame;
+
+        if self.applied_ui_appearance.as_ref() != Some(&settings.ui_appearance) {
+            apply_ui_appearance(ctx, &settings.ui_appearance);
+
+            self.applied_ui_appearance = Some(settings.ui_appearance.clone());
+        }
+
+        // The settings are read in the background.
+        if !self.window_settings_restored && matches!(tree, BackgroundLoadable::Loaded(_)) {
+            self.restore_window_settings(ctx, settings);
+
+            self.window_settings_restored = true;
+        }
+
+        self.handle_undo_shortcuts(ctx);
+
         self.main_window.render(
             ctx,
             tree,
@@ -62,25 +149,176 @@ impl<A: UiActions> UiState<'_, A> {
             settings,
         );
 
-        self.marker_tree_window.render(ctx, tree);
+        self.marker_tree_window
+            .render(ctx, tree, active_marker_categories);
+
+        self.marker_preset_rules_window
+            .render(ctx, settings, player);
+
+        self.overlay_import_window.render(ctx, tree, settings);
+
+        self.pack_directories_window.render(ctx, settings);
+
+        self.appearance_window.render(ctx, settings);
+
+        self.compass_calibration_window
+            .render(ctx, settings, compass);
 
         self.category_properties_window.render(ctx);
+
+        if self.window_settings_restored && self.store_window_settings(ctx, settings) {
+            self.actions.save_settings();
+        }
+    }
+
+    fn handle_undo_shortcuts(&mut self, ctx: &Context) {
+        let are_enabled = self.should_handle_undo_shortcuts(ctx);
+
+        ctx.data_mut(|data| data.insert_temp(Id::new(UNDO_SHORTCUTS_ENABLED_ID), are_enabled));
+
+        if !are_enabled {
+            return;
+        }
+
+        let redo_shortcuts = [
+            KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, Key::Z),
+            KeyboardShortcut::new(Modifiers::COMMAND, Key::Y),
+        ];
+
+        // Ctrl+Shift+Z has to be consumed before Ctrl+Z, which matches it as well.
+        let redo = ctx.input_mut(|input| {
+            redo_shortcuts
+                .iter()
+                .any(|shortcut| input.consume_shortcut(shortcut))
+        });
+
+        let undo = ctx.input_mut(|input| {
+            input.consume_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::Z))
+        });
+
+        if redo {
+            self.actions.redo();
+        } else if undo {
+            self.actions.undo();
+        }
+    }
+
+    fn should_handle_undo_shortcuts(&mut self, ctx: &Context) -> bool {
+        // The shortcuts of text fields take precedence.
+        if ctx.wants_keyboard_input() {
+            return false;
+        }
+
+        // Otherwise the shortcuts would also undo changes while they are used in the game.
+        let is_window_open = self.window_open_states().iter().any(|(_, open)| **open);
+        let is_window_used =
+            ctx.is_pointer_over_area() || ctx.memory(|memory| memory.focused().is_some());
+
+        is_window_open && is_window_used
+    }
+
+    fn window_open_states(&mut self) -> [(&'static str, &mut bool); 7] {
+        [
+            (MainWindow::<A>::ID, &mut self.main_window.open),
+            (MarkerTreeWindow::<A>::ID, &mut self.marker_tree_window.open),
+            (
+                MarkerPresetRulesWindow::<A>::ID,
+                &mut self.marker_preset_rules_window.open,
+            ),
+            (
+                OverlayImportWindow::<A>::ID,
+                &mut self.overlay_import_window.open,
+            ),
+            (
+                PackDirectoriesWindow::<A>::ID,
+                &mut self.pack_directories_window.open,
+            ),
+            (AppearanceWindow::<A>::ID, &mut self.appearance_window.open),
+            (
+                CompassCalibrationWindow::<A>::ID,
+                &mut self.compass_calibration_window.open,
+            ),
+        ]
+    }
+
+    fn restore_window_settings(&mut self, ctx: &Context, settings: &Settings) {
+        for (id, open) in self.window_open_states() {
+            if let Some(window) = settings.windows.get(id) {
+                *open |= window.open;
+            }
+        }
+
+        for (id, window) in &settings.windows {
+            if let Some([x, y]) = window.position {
+                restore_window_position(ctx, id, Pos2::new(x, y));
+            }
+        }
+    }
+
+    /// Returns whether a window was moved, opened or closed.
+    fn store_window_settings(&mut self, ctx: &Context, settings: &mut Settings) -> bool {
+        // Windows are stored after they were dropped.
+        if ctx.input(|input| input.pointer.any_down()) {
+            return false;
+        }
+
+        let states = self
+            .window_open_states()
+            .map(|(id, open)| (id, *open))
+            .into_iter()
+            // Only the position is kept. Which category it shows is not.
+            .chain([(CategoryPropertiesWindow::<A>::ID, false)]);
+
+        let mut changed = false;
+
+        for (id, open) in states {
+            let position = window_position(ctx, id)
+                .map(|position| [position.x, position.y])
+                .or_else(|| settings.windows.get(id).and_then(|window| window.position));
+
+            let window = WindowSetting { open, position };
+
+            if settings.windows.get(id) != Some(&window) {
+                settings.windows.insert(id.to_owned(), window);
+                changed = true;
+            }
+        }
+
+        changed
     }
 }
 
 pub trait UiActions {
     fn reload_settings(&self);
+    fn reload_marker_packs(&self);
     fn save_settings(&self);
     fn update_active_marker_categories(&self);
+    fn apply_marker_preset_rules(&self);
+    fn undo(&self);
+    fn redo(&self);
+    fn can_undo(&self) -> bool;
+    fn can_redo(&self) -> bool;
     fn display_marker_tree_window(&self);
+    fn display_marker_preset_rules_window(&self);
+    fn display_overlay_import_window(&self);
+    fn display_pack_directories_window(&self);
+    fn display_appearance_window(&self);
+    fn display_compass_calibration_window(&self);
     fn display_category_properties_window(&self, node_id: NodeId);
+    fn clipboard_text(&self) -> Option<String>;
+}
+
+const UNDO_SHORTCUTS_ENABLED_ID: &str = "undo_shortcuts_enabled";
+
+/// Whether the last frame used Ctrl+Z and Ctrl+Y for undo and redo. Window messages arrive between frames,
+/// so this decides whether the keys are kept from the game.
+pub fn are_undo_shortcuts_enabled(ctx: &Context) -> bool {
+    ctx.data(|data| data.get_temp(Id::new(UNDO_SHORTCUTS_ENABLED_ID)))
+        .unwrap_or(false)
 }
 
 pub fn prepare_egui_context(ctx: Context) -> Context {
-    ctx.set_visuals(Visuals::light());
-    ctx.style_mut(|style| {
-        style.interaction.selectable_labels = false;
-    });
+    apply_ui_appearance(&ctx, &UiAppearance::default());
 
     ctx
 }
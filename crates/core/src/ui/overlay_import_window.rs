@@ -0,0 +1,169 @@
+use std::path::Path;
+
+use egui::{Context, TextEdit, Ui};
+use log::warn;
+
+use crate::{
+    loadable::BackgroundLoadable,
+    localization::tr,
+    markers::MarkerCategoryTree,
+    settings::{
+        export_overlay_category_state, import_overlay_category_state, switch_marker_preset,
+        unused_marker_preset_name, Overlay, Settings,
+    },
+};
+
+use super::{
+    utils::{restorable_window, unknown_categories_message},
+    UiActions,
+};
+
+pub struct OverlayImportWindow<A: UiActions> {
+    pub actions: A,
+    pub open: bool,
+    pub overlay: Overlay,
+    pub file_path: String,
+    pub message: Option<String>,
+}
+
+impl<A: UiActions> OverlayImportWindow<A> {
+    pub const ID: &'static str = "overlay_import_window";
+
+    pub fn render(
+        &mut self,
+        ctx: &Context,
+        tree: &BackgroundLoadable<MarkerCategoryTree>,
+        settings: &mut Settings,
+    ) {
+        restorable_window(ctx, tr("Other overlays"), Self::ID)
+            .open(&mut self.open)
+            .auto_sized()
+            .show(ctx, |ui| {
+                let BackgroundLoadable::Loaded(tree) = tree else {
+                    ui.spinner();
+                    return;
+                };
+
+                ui.horizontal(|ui| {
+                    for overlay in Overlay::ALL {
+                        ui.radio_value(&mut self.overlay, overlay, overlay_label(overlay));
+                    }
+                });
+
+                file_path_input(&self.actions, ui, self.overlay, &mut self.file_path);
+
+                let file_path = Path::new(self.file_path.trim());
+                let has_file_path = !self.file_path.trim().is_empty();
+
+                ui.horizontal(|ui| {
+                    ui.add_enabled_ui(has_file_path, |ui| {
+                        if ui
+                            .button(tr("Import"))
+                            .on_hover_text(tr(
+                                "Create a new preset from the categories of the overlay",
+                            ))
+                            .clicked()
+                        {
+                            self.message = Some(import(
+                                &self.actions,
+                                tree,
+                                settings,
+                                self.overlay,
+                                file_path,
+                            ));
+                        }
+
+                        if ui
+                            .button(tr("Export"))
+                            .on_hover_text(tr(
+                                "Overwrite the categories of the overlay with the active preset",
+                            ))
+                            .clicked()
+                        {
+                            self.message = Some(
+                                match export_overlay_category_state(tree, self.overlay, file_path) {
+                                    Ok(()) => tr("Exported the preset {name}.")
+                                        .replace("{name}", &settings.active_marker_preset),
+                                    Err(err) => tr("Could not export the preset: {err}")
+                                        .replace("{err}", &err.to_string()),
+                                },
+                            );
+                        }
+                    });
+                });
+
+                if let Some(message) = &self.message {
+                    ui.label(message.as_str());
+                }
+            });
+    }
+}
+
+fn file_path_input<A: UiActions>(
+    actions: &A,
+    ui: &mut Ui,
+    overlay: Overlay,
+    file_path: &mut String,
+) {
+    ui.horizontal(|ui| {
+        ui.add(
+            TextEdit::singleline(file_path)
+                .hint_text(match overlay {
+                    Overlay::TacO => tr("Path to TacOConfig.xml"),
+                    Overlay::BlishHud => tr("Path to the categories.txt of the pathing module"),
+                })
+                .desired_width(300.0),
+        );
+
+        if ui
+            .button(tr("Paste"))
+            .on_hover_text(tr("Use the path in the clipboard"))
+            .clicked()
+        {
+            if let Some(text) = actions.clipboard_text() {
+                // Paths copied from the explorer are quoted.
+                *file_path = text.trim().trim_matches('"').to_owned();
+            }
+        }
+    });
+}
+
+fn import<A: UiActions>(
+    actions: &A,
+    tree: &MarkerCategoryTree,
+    settings: &mut Settings,
+    overlay: Overlay,
+    file_path: &Path,
+) -> String {
+    let name = unused_marker_preset_name(settings, overlay_label(overlay));
+
+    let unknown_ids = match import_overlay_category_state(settings, tree, overlay, file_path, &name)
+    {
+        Ok(unknown_ids) => unknown_ids,
+        Err(err) => {
+            return tr("Could not import the categories: {err}").replace("{err}", &err.to_string())
+        }
+    };
+
+    if let Err(err) = switch_marker_preset(settings, tree, &name) {
+        warn!("could not switch to imported marker preset: {err:?}");
+    }
+
+    actions.update_active_marker_categories();
+    actions.save_settings();
+
+    let message = tr("Imported the categories as preset {name}.").replace("{name}", &name);
+
+    if unknown_ids.is_empty() {
+        message
+    } else {
+        format!("{message} {}", unknown_categories_message(&unknown_ids))
+    }
+}
+
+fn overlay_label(overlay: Overlay) -> &'static str {
+    match overlay {
+        Overlay::TacO => "TacO",
+        Overlay::BlishHud => "Blish HUD",
+    }
+}
@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+
+use egui::{Button, Context, Grid, TextEdit};
+
+use crate::{
+    localization::tr,
+    settings::{PackDirectory, Settings},
+};
+
+use super::{utils::restorable_window, UiActions};
+
+pub struct PackDirectoriesWindow<A: UiActions> {
+    pub actions: A,
+    pub open: bool,
+    pub path_input: String,
+}
+
+impl<A: UiActions> PackDirectoriesWindow<A> {
+    pub const ID: &'static str = "pack_directories_window";
+
+    pub fn render(&mut self, ctx: &Context, settings: &mut Settings) {
+        restorable_window(ctx, tr("Pack folders"), Self::ID)
+            .open(&mut self.open)
+            .auto_sized()
+            .show(ctx, |ui| {
+                ui.label(tr("Packs are always loaded from the markers folder of the addon. Other folders, like the POIs folder of TacO, can be added here."));
+
+                let mut changed = false;
+                let mut remove = None;
+
+                Grid::new("pack_directories")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        for (idx, dir) in settings.pack_directories.iter_mut().enumerate() {
+                            changed |= ui
+                                .checkbox(&mut dir.enabled, dir.path.display().to_string())
+                                .changed();
+
+                            if ui.small_button("🗑").on_hover_text(tr("Remove folder")).clicked() {
+                                remove = Some(idx);
+                            }
+
+                            ui.end_row();
+                        }
+                    });
+
+                if let Some(idx) = remove {
+                    settings.pack_directories.remove(idx);
+                    changed = true;
+                }
+
+                ui.horizontal(|ui| {
+                    ui.add(
+                        TextEdit::singleline(&mut self.path_input)
+                            .hint_text(tr("Folder with .taco, .zip or XML files"))
+                            .desired_width(300.0),
+                    );
+
+                    if ui
+                        .button(tr("Paste"))
+                        .on_hover_text(tr("Use the path in the clipboard"))
+                        .clicked()
+                    {
+                        if let Some(text) = self.actions.clipboard_text() {
+                            // Paths copied from the explorer are quoted.
+                            self.path_input = text.trim().trim_matches('"').to_owned();
+                        }
+                    }
+
+                    let path = PathBuf::from(self.path_input.trim());
+                    let can_add = !self.path_input.trim().is_empty()
+                        && !settings.pack_directories.iter().any(|dir| dir.path == path);
+
+                    if ui.add_enabled(can_add, Button::new(tr("Add"))).clicked() {
+                        settings.pack_directories.push(PackDirectory {
+                            path,
+                            enabled: true,
+                        });
+
+                        self.path_input.clear();
+                        changed = true;
+                    }
+                });
+
+                if changed {
+                    // Persists the state of the current tree before it is replaced.
+                    self.actions.save_settings();
+                    self.actions.reload_marker_packs();
+                }
+            });
+    }
+}
@@ -0,0 +1,6 @@
+use crate::localization::tr;
+
+pub fn unknown_categories_message(unknown_ids: &[String]) -> String {
+    tr("These categories are not part of the installed marker packs: {ids}")
+        .replace("{ids}", &unknown_ids.join(", "))
+}
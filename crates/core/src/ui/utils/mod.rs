@@ -1,5 +1,9 @@
+mod messages;
 mod plurals;
 mod widgets;
+mod windows;
 
+pub use self::messages::*;
 pub use self::plurals::*;
 pub use self::widgets::*;
+pub use self::windows::*;
@@ -1,11 +1,13 @@
+use crate::localization::tr_plural;
+
 pub fn format_categories(num: usize) -> String {
-    format!("{num} categor{}", if num == 1 { "y" } else { "ies" })
+    tr_plural(num, "{num} category", "{num} categories")
 }
 
 pub fn format_points(num: usize) -> String {
-    format!("{num} point{}", if num == 1 { "" } else { "s" })
+    tr_plural(num, "{num} point", "{num} points")
 }
 
 pub fn format_trails(num: usize) -> String {
-    format!("{num} route{}", if num == 1 { "" } else { "s" })
+    tr_plural(num, "{num} route", "{num} routes")
 }
@@ -1,11 +1,11 @@
-use std::iter::once;
-
-use egui::{Slider, Ui};
-use log_err::LogErrOption;
+use egui::{ComboBox, Slider, Ui};
 
 use crate::{
-    markers::MarkerCategoryTreeNode,
-    settings::{TrailColor, TrailWidth},
+    localization::tr,
+    markers::{
+        effective_trail_color, effective_trail_style, effective_trail_width, MarkerCategoryTreeNode,
+    },
+    settings::{TrailColor, TrailLineStyle, TrailStyle, TrailWidth},
     ui::UiActions,
 };
 
@@ -13,22 +13,24 @@ pub fn trail_color_selector<A: UiActions>(
     actions: &A,
     ui: &mut Ui,
     label: &str,
-    category_node: &MarkerCategoryTreeNode,
+    category_nodes: &[MarkerCategoryTreeNode],
     show_reset: bool,
 ) {
+    let Some(category_node) = category_nodes.first() else {
+        return;
+    };
+
     ui.horizontal(|ui| {
         ui.label(label);
 
-        let mut color = once(category_node.data())
-            .chain(category_node.ancestors().map(|n| n.data()))
-            .filter_map(|n| *n.trail_color.borrow())
-            .next()
-            .log_expect("the root always has the default color")
-            .0;
+        // With several categories, the value of the first one is displayed.
+        let mut color = effective_trail_color(category_node).0;
 
-        let resp = ui.color_edit_button_srgb(&mut color);
+        let resp = ui.color_edit_button_srgba_unmultiplied(&mut color);
         if resp.changed() {
-            *category_node.data().trail_color.borrow_mut() = Some(TrailColor(color));
+            for category_node in category_nodes {
+                *category_node.data().trail_color.borrow_mut() = Some(TrailColor(color));
+            }
 
             actions.update_active_marker_categories();
             actions.save_settings();
@@ -37,10 +39,12 @@ pub fn trail_color_selector<A: UiActions>(
         if show_reset {
             if ui
                 .small_button("🗑")
-                .on_hover_text("Reset to parent's value")
+                .on_hover_text(tr("Reset to parent's value"))
                 .clicked()
             {
-                *category_node.data().trail_color.borrow_mut() = None;
+                for category_node in category_nodes {
+                    *category_node.data().trail_color.borrow_mut() = None;
+                }
 
                 actions.update_active_marker_categories();
                 actions.save_settings();
@@ -53,22 +57,24 @@ pub fn trail_width_selector<A: UiActions>(
     actions: &A,
     ui: &mut Ui,
     label: &str,
-    category_node: &MarkerCategoryTreeNode,
+    category_nodes: &[MarkerCategoryTreeNode],
     show_reset: bool,
 ) {
+    let Some(category_node) = category_nodes.first() else {
+        return;
+    };
+
     ui.horizontal(|ui| {
         ui.label(label);
 
-        let mut width = once(category_node.data())
-            .chain(category_node.ancestors().map(|n| n.data()))
-            .filter_map(|n| *n.trail_width.borrow())
-            .next()
-            .log_expect("the root always has the default width")
-            .0;
+        // With several categories, the value of the first one is displayed.
+        let mut width = effective_trail_width(category_node).0;
 
         let resp = ui.add(Slider::new(&mut width, TrailWidth::MIN..=TrailWidth::MAX));
         if resp.changed() {
-            *category_node.data().trail_width.borrow_mut() = Some(TrailWidth(width));
+            for category_node in category_nodes {
+                *category_node.data().trail_width.borrow_mut() = Some(TrailWidth(width));
+            }
 
             actions.update_active_marker_categories();
             actions.save_settings();
@@ -77,10 +83,12 @@ pub fn trail_width_selector<A: UiActions>(
         if show_reset {
             if ui
                 .small_button("🗑")
-                .on_hover_text("Reset to parent's value")
+                .on_hover_text(tr("Reset to parent's value"))
                 .clicked()
             {
-                *category_node.data().trail_width.borrow_mut() = None;
+                for category_node in category_nodes {
+                    *category_node.data().trail_width.borrow_mut() = None;
+                }
 
                 actions.update_active_marker_categories();
                 actions.save_settings();
@@ -88,3 +96,100 @@ pub fn trail_width_selector<A: UiActions>(
         }
     });
 }
+
+pub fn trail_style_selector<A: UiActions>(
+    actions: &A,
+    ui: &mut Ui,
+    label: &str,
+    category_nodes: &[MarkerCategoryTreeNode],
+    show_reset: bool,
+) {
+    let Some(category_node) = category_nodes.first() else {
+        return;
+    };
+
+    // With several categories, the value of the first one is displayed.
+    let mut style = effective_trail_style(category_node);
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        ui.label(label);
+
+        ComboBox::from_id_salt(("trail_line_style", label))
+            .selected_text(trail_line_style_name(style.line))
+            .show_ui(ui, |ui| {
+                for line in TrailLineStyle::ALL {
+                    changed |= ui
+                        .selectable_value(&mut style.line, line, trail_line_style_name(line))
+                        .changed();
+                }
+            });
+
+        if show_reset
+            && ui
+                .small_button("🗑")
+                .on_hover_text(tr("Reset to parent's value"))
+                .clicked()
+        {
+            for category_node in category_nodes {
+                *category_node.data().trail_style.borrow_mut() = None;
+            }
+
+            actions.update_active_marker_categories();
+            actions.save_settings();
+        }
+    });
+
+    ui.horizontal(|ui| {
+        changed |= ui.checkbox(&mut style.arrows, tr("Arrows")).changed();
+
+        changed |= ui
+            .add_enabled(
+                style.arrows,
+                Slider::new(
+                    &mut style.arrow_spacing,
+                    TrailStyle::MIN_ARROW_SPACING..=TrailStyle::MAX_ARROW_SPACING,
+                )
+                .text(tr("spacing")),
+            )
+            .changed();
+    });
+
+    ui.horizontal(|ui| {
+        changed |= ui.checkbox(&mut style.outline, tr("Outline")).changed();
+
+        ui.add_enabled_ui(style.outline, |ui| {
+            let mut has_color = style.outline_color.is_some();
+
+            if ui
+                .checkbox(&mut has_color, tr("own color"))
+                .on_hover_text(tr("Otherwise black or white, depending on the route color"))
+                .changed()
+            {
+                style.outline_color = has_color.then(|| TrailColor::from_rgb([0, 0, 0]));
+                changed = true;
+            }
+
+            if let Some(TrailColor(color)) = &mut style.outline_color {
+                changed |= ui.color_edit_button_srgba_unmultiplied(color).changed();
+            }
+        });
+    });
+
+    if changed {
+        for category_node in category_nodes {
+            *category_node.data().trail_style.borrow_mut() = Some(style);
+        }
+
+        actions.update_active_marker_categories();
+        actions.save_settings();
+    }
+}
+
+fn trail_line_style_name(line: TrailLineStyle) -> &'static str {
+    match line {
+        TrailLineStyle::Solid => tr("solid"),
+        TrailLineStyle::Dashed => tr("dashed"),
+        TrailLineStyle::Dotted => tr("dotted"),
+    }
+}
@@ -0,0 +1,26 @@
+use egui::{Context, Id, Pos2, Window};
+
+/// A window with a fixed id, so its state does not depend on the translated title. A position restored
+/// from the settings is applied the next time the window is shown.
+pub fn restorable_window<'open>(ctx: &Context, title: &str, id: &str) -> Window<'open> {
+    let window = Window::new(title).id(Id::new(id));
+
+    match ctx.data_mut(|data| data.remove_temp::<Pos2>(restored_position_id(id))) {
+        Some(position) => window.current_pos(position),
+        None => window,
+    }
+}
+
+pub fn restore_window_position(ctx: &Context, id: &str, position: Pos2) {
+    ctx.data_mut(|data| data.insert_temp(restored_position_id(id), position));
+}
+
+/// The top left corner of the window the last time it was shown.
+pub fn window_position(ctx: &Context, id: &str) -> Option<Pos2> {
+    ctx.memory(|memory| memory.area_rect(Id::new(id)))
+        .map(|rect| rect.min)
+}
+
+fn restored_position_id(id: &str) -> Id {
+    Id::new((id, "restored_position"))
+}